@@ -107,15 +107,15 @@ fn bench_event_serialization(c: &mut Criterion) {
         session_id: Some("sess_bench456".to_string()),
         environment: Environment {
             os: "linux".to_string(),
-            os_version: None,
             arch: Some("x86_64".to_string()),
             ci: Some(false),
-            shell: None,
+            ..Default::default()
         },
         event: EventData {
             event_type: "test_event".to_string(),
             category: Some("test".to_string()),
             data: serde_json::json!({"test": true}),
+            ..Default::default()
         },
         metadata: Metadata {
             sdk_version: "0.2.0".to_string(),
@@ -200,15 +200,15 @@ fn bench_storage_write(c: &mut Criterion) {
                 session_id: Some("sess_bench456".to_string()),
                 environment: Environment {
                     os: "linux".to_string(),
-                    os_version: None,
                     arch: Some("x86_64".to_string()),
                     ci: Some(false),
-                    shell: None,
+                    ..Default::default()
                 },
                 event: EventData {
                     event_type: "test_event".to_string(),
                     category: Some("test".to_string()),
                     data: serde_json::json!({"test": true}),
+                    ..Default::default()
                 },
                 metadata: Metadata {
                     sdk_version: "0.2.0".to_string(),
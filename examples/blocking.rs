@@ -0,0 +1,46 @@
+//! Example: Using telemetry-kit from a synchronous CLI without a tokio runtime
+//!
+//! Run with:
+//! ```bash
+//! cargo run --example blocking --features blocking
+//! ```
+
+#[cfg(feature = "blocking")]
+fn main() -> telemetry_kit::Result<()> {
+    use telemetry_kit::prelude::*;
+
+    println!("=== Telemetry Kit Blocking Example ===\n");
+
+    // No #[tokio::main]: uploads run on a background thread
+    let telemetry = TelemetryKit::builder()
+        .service_name("blocking-cli")?
+        .service_version("1.0.0")
+        .build_blocking()?;
+
+    println!("1. Tracking build command...");
+    telemetry.track_command("build", |event| {
+        event.flag("--release").duration_ms(5420).success(true)
+    })?;
+
+    println!("2. Tracking feature usage...");
+    telemetry.track_feature("config-loader", |event| event.method("toml").success(true))?;
+
+    let stats = telemetry.stats()?;
+    println!(
+        "\nBuffered events: {} ({} unsynced)",
+        stats.total_events, stats.unsynced_events
+    );
+
+    // Stops the upload thread (syncing first when sync is configured)
+    telemetry.shutdown()?;
+
+    println!("\n✓ Done");
+    Ok(())
+}
+
+#[cfg(not(feature = "blocking"))]
+fn main() {
+    eprintln!("This example requires the 'blocking' feature.");
+    eprintln!("Run with: cargo run --example blocking --features blocking");
+    std::process::exit(1);
+}
@@ -1,7 +1,8 @@
 //! Example: Using the #[instrument] macro for automatic function instrumentation
 //!
 //! This example demonstrates how to use the #[instrument] procedural macro
-//! to automatically track function execution timing.
+//! to automatically track function execution timing through a global
+//! TelemetryKit instance.
 //!
 //! Run with:
 //! ```bash
@@ -9,7 +10,7 @@
 //! ```
 
 #[cfg(feature = "macros")]
-use telemetry_kit::instrument;
+use telemetry_kit::{instrument, set_global, TelemetryKit};
 
 /// Example async function that fetches data
 #[cfg(feature = "macros")]
@@ -25,9 +26,9 @@ async fn fetch_user_data(user_id: u64) -> Result<String, String> {
     }
 }
 
-/// Example sync function for calculations
+/// Example sync function for calculations, recorded as "calculate" with its input
 #[cfg(feature = "macros")]
-#[instrument]
+#[instrument(name = "calculate", fields(items))]
 fn calculate_total(items: &[i32]) -> Result<i32, String> {
     if items.is_empty() {
         Err("Empty items list".to_string())
@@ -50,6 +51,13 @@ async fn process_data(data: &str) -> usize {
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("🔭 telemetry-kit #[instrument] Macro Example\n");
 
+    // Register a global instance so instrumented functions can record events
+    let telemetry = TelemetryKit::builder()
+        .service_name("instrument-example")?
+        .service_version(env!("CARGO_PKG_VERSION"))
+        .build()?;
+    set_global(telemetry.clone())?;
+
     // Test async function with Result
     println!("Testing async function with Result...");
     match fetch_user_data(123).await {
@@ -80,8 +88,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("  ✅ Processed {} characters", length);
 
     println!("\n✅ All tests completed!");
-    println!("\nNote: The #[instrument] macro currently measures execution time");
-    println!("      but doesn't send telemetry yet. Full integration coming soon!");
+
+    let stats = telemetry.stats().await?;
+    println!("\n📊 Recorded {} events", stats.total_events);
 
     Ok(())
 }
@@ -0,0 +1,7 @@
+-- Parent/child relationships between events recorded by SDK spans
+
+ALTER TABLE events ADD COLUMN IF NOT EXISTS trace_id VARCHAR(64);
+ALTER TABLE events ADD COLUMN IF NOT EXISTS parent_event_id UUID;
+
+CREATE INDEX IF NOT EXISTS idx_events_trace_id ON events(trace_id) WHERE trace_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_events_parent_event_id ON events(parent_event_id) WHERE parent_event_id IS NOT NULL;
@@ -0,0 +1,3 @@
+-- Properties attached by the SDK to every event
+
+ALTER TABLE events ADD COLUMN IF NOT EXISTS event_context JSONB;
@@ -0,0 +1,3 @@
+-- Sample rate of events recorded by SDK sampling rules, used to re-weight counts
+
+ALTER TABLE events ADD COLUMN IF NOT EXISTS sample_rate DOUBLE PRECISION;
@@ -0,0 +1,6 @@
+-- CI provider and runner image detected by the SDK, to tell CI traffic apart
+
+ALTER TABLE events ADD COLUMN IF NOT EXISTS ci_provider VARCHAR(50);
+ALTER TABLE events ADD COLUMN IF NOT EXISTS ci_runner_image VARCHAR(50);
+
+CREATE INDEX IF NOT EXISTS idx_events_ci_provider ON events(app_id, ci_provider) WHERE ci_provider IS NOT NULL;
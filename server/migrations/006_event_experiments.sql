@@ -0,0 +1,5 @@
+-- Experiment variants the session was exposed to, by experiment name
+
+ALTER TABLE events ADD COLUMN IF NOT EXISTS experiments JSONB;
+
+CREATE INDEX IF NOT EXISTS idx_events_experiments ON events USING GIN (experiments) WHERE experiments IS NOT NULL;
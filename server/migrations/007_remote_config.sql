@@ -0,0 +1,18 @@
+-- Remote configuration served to SDKs, per app and optionally per release
+
+CREATE TABLE IF NOT EXISTS remote_configs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    org_id UUID NOT NULL,
+    app_id UUID NOT NULL,
+    -- NULL applies to every version without a row of its own
+    service_version VARCHAR(100),
+    telemetry_enabled BOOLEAN NOT NULL DEFAULT true,
+    default_sample_rate DOUBLE PRECISION CHECK (default_sample_rate BETWEEN 0 AND 1),
+    -- Sample rates by event type
+    sample_rates JSONB NOT NULL DEFAULT '{}',
+    disabled_event_types TEXT[] NOT NULL DEFAULT '{}',
+    sync_interval_secs INTEGER CHECK (sync_interval_secs > 0),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_remote_configs_app_version ON remote_configs(org_id, app_id, COALESCE(service_version, ''));
@@ -0,0 +1,12 @@
+-- Counts of events SDKs dropped from their local buffer, reported with each sync
+
+CREATE TABLE IF NOT EXISTS dropped_events (
+    id BIGSERIAL PRIMARY KEY,
+    org_id UUID NOT NULL,
+    app_id UUID NOT NULL,
+    category VARCHAR(100) NOT NULL,
+    count BIGINT NOT NULL CHECK (count > 0),
+    reported_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_dropped_events_app ON dropped_events(org_id, app_id, reported_at);
@@ -0,0 +1,7 @@
+-- Identical events the SDK merged into one; `timestamp` holds the first occurrence
+
+ALTER TABLE events ADD COLUMN IF NOT EXISTS event_count BIGINT NOT NULL DEFAULT 1;
+
+ALTER TABLE events ADD COLUMN IF NOT EXISTS last_timestamp TIMESTAMPTZ;
+
+ALTER TABLE events ADD COLUMN IF NOT EXISTS total_duration_ms BIGINT;
@@ -0,0 +1,9 @@
+-- Environment details detected by the SDK: distribution, kernel, container and terminal
+
+ALTER TABLE events ADD COLUMN IF NOT EXISTS os_distro VARCHAR(50);
+ALTER TABLE events ADD COLUMN IF NOT EXISTS kernel_version VARCHAR(100);
+ALTER TABLE events ADD COLUMN IF NOT EXISTS container VARCHAR(50);
+ALTER TABLE events ADD COLUMN IF NOT EXISTS wsl BOOLEAN;
+ALTER TABLE events ADD COLUMN IF NOT EXISTS ssh BOOLEAN;
+ALTER TABLE events ADD COLUMN IF NOT EXISTS tty BOOLEAN;
+ALTER TABLE events ADD COLUMN IF NOT EXISTS terminal VARCHAR(100);
@@ -114,6 +114,18 @@ pub async fn verify_hmac(
     Ok(next.run(request).await)
 }
 
+/// Sign a response so SDKs can verify it came from this server
+///
+/// Message format: `{timestamp}:{body}`, as for request signatures.
+pub fn sign_response(timestamp: &str, body: &str, secret: &str) -> String {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
+
+    mac.update(format!("{}:{}", timestamp, body).as_bytes());
+
+    hex::encode(mac.finalize().into_bytes())
+}
+
 /// Verify HMAC signature
 fn verify_signature(message: &str, signature: &str, secret: &str) -> bool {
     let mut mac = match HmacSha256::new_from_slice(secret.as_bytes()) {
@@ -194,6 +206,21 @@ mod tests {
         assert!(!verify_signature(message, &signature, wrong_secret));
     }
 
+    #[test]
+    fn test_sign_response() {
+        let secret = "test_secret";
+        let body = r#"{"enabled":false}"#;
+
+        let signature = sign_response("1234567890", body, secret);
+
+        assert!(verify_signature(
+            &format!("1234567890:{}", body),
+            &signature,
+            secret
+        ));
+        assert!(!verify_signature("1234567890:{}", &signature, secret));
+    }
+
     #[test]
     fn test_constant_time_eq_equal() {
         let a = b"test_string";
@@ -1,17 +1,20 @@
 //! Request handlers
 
 use axum::{
-    extract::{Path, Request, State},
-    http::StatusCode,
+    extract::{Path, Query, Request, State},
+    http::{header, HeaderMap, StatusCode},
     response::{IntoResponse, Json, Response},
 };
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::collections::HashMap;
 use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::{
-    models::{ApiToken, EventBatch, IncomingEvent},
+    auth,
+    models::{ApiToken, EventBatch, IncomingEvent, RemoteConfig, RemoteConfigRow},
     AppState,
 };
 
@@ -22,6 +25,12 @@ pub struct IngestPath {
     pub app_id: Uuid,
 }
 
+/// Remote configuration query parameters
+#[derive(Debug, Deserialize)]
+pub struct RemoteConfigQuery {
+    pub version: Option<String>,
+}
+
 /// Success response
 #[derive(Debug, Serialize)]
 pub struct SuccessResponse {
@@ -131,8 +140,8 @@ pub async fn ingest(
     let mut accepted = 0;
     let mut errors = Vec::new();
 
-    for event in batch.events {
-        match process_event(&state, &event, params.org_id, params.app_id).await {
+    for event in &batch.events {
+        match process_event(&state, event, params.org_id, params.app_id).await {
             Ok(()) => accepted += 1,
             Err(e) => errors.push(EventError {
                 event_id: event.event_id,
@@ -144,6 +153,11 @@ pub async fn ingest(
 
     let rejected = errors.len();
 
+    // Reports of events dropped from full SDK buffers are informational
+    if let Err(e) = record_dropped_events(&state, &batch.dropped_events, &params).await {
+        tracing::warn!("Failed to record dropped event counts: {}", e);
+    }
+
     // Return appropriate response
     if errors.is_empty() {
         Ok(Json(SuccessResponse {
@@ -176,6 +190,102 @@ pub async fn ingest(
     }
 }
 
+/// Remote configuration endpoint
+///
+/// Returns the configuration for the requested version, falling back to the
+/// app-wide one. The response is signed with the token's secret (`X-Signature`
+/// over `{X-Timestamp}:{body}`) so SDKs can verify it.
+pub async fn remote_config(
+    State(state): State<Arc<AppState>>,
+    Path(params): Path<IngestPath>,
+    Query(query): Query<RemoteConfigQuery>,
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, Json<serde_json::Value>)> {
+    let token_str = headers
+        .get("Authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.strip_prefix("Bearer "))
+        .ok_or_else(|| {
+            error_response(
+                StatusCode::UNAUTHORIZED,
+                "Missing or invalid Authorization header",
+            )
+        })?;
+
+    let token = sqlx::query_as::<_, ApiToken>(
+        "SELECT * FROM api_tokens WHERE token = $1 AND is_active = true",
+    )
+    .bind(token_str)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?
+    .ok_or_else(|| error_response(StatusCode::UNAUTHORIZED, "Invalid token"))?;
+
+    if token.org_id != params.org_id || token.app_id != params.app_id {
+        return Err(error_response(
+            StatusCode::FORBIDDEN,
+            "Token does not match org_id/app_id",
+        ));
+    }
+
+    // A row for the version takes precedence over the app-wide row
+    let row = sqlx::query_as::<_, RemoteConfigRow>(
+        r#"
+        SELECT telemetry_enabled, default_sample_rate, sample_rates, disabled_event_types,
+               sync_interval_secs
+        FROM remote_configs
+        WHERE org_id = $1 AND app_id = $2
+          AND (service_version IS NULL OR service_version = $3)
+        ORDER BY service_version IS NULL
+        LIMIT 1
+        "#,
+    )
+    .bind(params.org_id)
+    .bind(params.app_id)
+    .bind(query.version)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
+
+    let config = row.map(RemoteConfig::from).unwrap_or_default();
+    let body = serde_json::to_string(&config)
+        .map_err(|_| error_response(StatusCode::INTERNAL_SERVER_ERROR, "Serialization error"))?;
+
+    let timestamp = Utc::now().timestamp().to_string();
+    let signature = auth::sign_response(&timestamp, &body, &token.secret);
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/json".to_string()),
+            (header::HeaderName::from_static("x-timestamp"), timestamp),
+            (header::HeaderName::from_static("x-signature"), signature),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// Store counts of events SDKs dropped from their local buffer
+async fn record_dropped_events(
+    state: &AppState,
+    dropped_events: &HashMap<String, i64>,
+    params: &IngestPath,
+) -> Result<(), sqlx::Error> {
+    for (category, count) in dropped_events.iter().filter(|(_, count)| **count > 0) {
+        sqlx::query(
+            "INSERT INTO dropped_events (org_id, app_id, category, count) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(params.org_id)
+        .bind(params.app_id)
+        .bind(category)
+        .bind(count)
+        .execute(&state.db)
+        .await?;
+    }
+
+    Ok(())
+}
+
 /// Process a single event
 async fn process_event(
     state: &AppState,
@@ -246,18 +356,22 @@ async fn process_event(
             event_id, org_id, app_id, schema_version, timestamp,
             service_name, service_version, service_language, service_language_version,
             user_id, session_id,
-            os, os_version, arch, ci, shell,
-            event_type, event_category, event_data,
+            os, os_version, os_distro, kernel_version, arch, ci, ci_provider, ci_runner_image,
+            shell, container, wsl, ssh, tty, terminal,
+            event_type, event_category, event_data, event_context, trace_id, parent_event_id,
+            sample_rate, experiments, event_count, last_timestamp, total_duration_ms,
             sdk_version, transmission_timestamp, batch_size, retry_count,
             received_at
         ) VALUES (
             $1, $2, $3, $4, $5,
             $6, $7, $8, $9,
             $10, $11,
-            $12, $13, $14, $15, $16,
-            $17, $18, $19,
-            $20, $21, $22, $23,
-            $24
+            $12, $13, $14, $15, $16, $17, $18, $19,
+            $20, $21, $22, $23, $24, $25,
+            $26, $27, $28, $29, $30, $31,
+            $32, $33, $34, $35, $36,
+            $37, $38, $39, $40,
+            $41
         )
         "#,
     )
@@ -274,12 +388,29 @@ async fn process_event(
     .bind(stored.session_id)
     .bind(stored.os)
     .bind(stored.os_version)
+    .bind(stored.os_distro)
+    .bind(stored.kernel_version)
     .bind(stored.arch)
     .bind(stored.ci)
+    .bind(stored.ci_provider)
+    .bind(stored.ci_runner_image)
     .bind(stored.shell)
+    .bind(stored.container)
+    .bind(stored.wsl)
+    .bind(stored.ssh)
+    .bind(stored.tty)
+    .bind(stored.terminal)
     .bind(stored.event_type)
     .bind(stored.event_category)
     .bind(stored.event_data)
+    .bind(stored.event_context)
+    .bind(stored.trace_id)
+    .bind(stored.parent_event_id)
+    .bind(stored.sample_rate)
+    .bind(stored.experiments)
+    .bind(stored.event_count)
+    .bind(stored.last_timestamp)
+    .bind(stored.total_duration_ms)
     .bind(stored.sdk_version)
     .bind(stored.transmission_timestamp)
     .bind(stored.batch_size)
@@ -338,14 +469,97 @@ mod tests {
         let event = create_test_event();
         let batch = EventBatch {
             events: vec![event],
+            dropped_events: Default::default(),
         };
         assert_eq!(batch.events.len(), 1);
 
         // Empty batch (should be rejected by handler)
-        let empty_batch = EventBatch { events: vec![] };
+        let empty_batch = EventBatch {
+            events: vec![],
+            dropped_events: Default::default(),
+        };
         assert_eq!(empty_batch.events.len(), 0);
     }
 
+    #[test]
+    fn test_rollup_to_stored() {
+        let mut event = create_test_event();
+        let stored = event.to_stored(Uuid::new_v4(), Uuid::new_v4());
+        assert_eq!(stored.event_count, 1);
+        assert!(stored.last_timestamp.is_none());
+
+        event.event.rollup = Some(crate::models::Rollup {
+            count: 12,
+            first_timestamp: event.timestamp,
+            last_timestamp: Utc::now(),
+            total_duration_ms: Some(340),
+        });
+        let stored = event.to_stored(Uuid::new_v4(), Uuid::new_v4());
+        assert_eq!(stored.event_count, 12);
+        assert_eq!(stored.total_duration_ms, Some(340));
+    }
+
+    #[test]
+    fn test_environment_to_stored() {
+        let event: crate::models::IncomingEvent = serde_json::from_value(json!({
+            "schema_version": "1.0.0",
+            "event_id": Uuid::new_v4(),
+            "timestamp": Utc::now(),
+            "service": { "name": "test-service", "version": "1.0.0", "language": "rust" },
+            "user_id": "client_test123",
+            "environment": {
+                "os": "linux",
+                "os_distro": "ubuntu",
+                "kernel_version": "6.5.0-14-generic",
+                "container": "docker",
+                "wsl": false,
+                "ssh": true,
+                "tty": true,
+                "terminal": "xterm-256color"
+            },
+            "event": { "type": "command", "data": {} },
+            "metadata": {
+                "sdk_version": "0.3.0",
+                "transmission_timestamp": Utc::now(),
+                "batch_size": 1,
+                "retry_count": 0
+            }
+        }))
+        .unwrap();
+
+        let stored = event.to_stored(Uuid::new_v4(), Uuid::new_v4());
+        assert_eq!(stored.os_distro.as_deref(), Some("ubuntu"));
+        assert_eq!(stored.kernel_version.as_deref(), Some("6.5.0-14-generic"));
+        assert_eq!(stored.container.as_deref(), Some("docker"));
+        assert_eq!(stored.wsl, Some(false));
+        assert_eq!(stored.ssh, Some(true));
+        assert_eq!(stored.tty, Some(true));
+        assert_eq!(stored.terminal.as_deref(), Some("xterm-256color"));
+        assert!(stored.shell.is_none());
+    }
+
+    #[test]
+    fn test_remote_config_from_row() {
+        use crate::models::{RemoteConfig, RemoteConfigRow};
+
+        let default = serde_json::to_value(RemoteConfig::default()).unwrap();
+        assert_eq!(default["enabled"], true);
+        assert!(default.get("sync_interval_secs").is_none());
+
+        let config = RemoteConfig::from(RemoteConfigRow {
+            telemetry_enabled: false,
+            default_sample_rate: Some(0.5),
+            sample_rates: sqlx::types::Json([("span".to_string(), 0.1)].into()),
+            disabled_event_types: vec!["crash".to_string()],
+            sync_interval_secs: Some(300),
+        });
+        let json = serde_json::to_value(config).unwrap();
+        assert_eq!(json["enabled"], false);
+        assert_eq!(json["sample_rates"]["span"], 0.1);
+        assert_eq!(json["disabled_event_types"][0], "crash");
+        assert_eq!(json["sync_interval_secs"], 300);
+    }
+
     #[test]
     fn test_user_id_validation() {
         // Valid user IDs
@@ -379,11 +593,13 @@ mod tests {
                 arch: Some("x86_64".to_string()),
                 ci: Some(false),
                 shell: Some("bash".to_string()),
+                ..Default::default()
             },
             event: EventData {
                 event_type: "command".to_string(),
                 category: Some("test".to_string()),
                 data: json!({"success": true}),
+                ..Default::default()
             },
             metadata: Metadata {
                 sdk_version: "0.1.0".to_string(),
@@ -67,6 +67,8 @@ async fn main() -> anyhow::Result<()> {
     let app = Router::new()
         // Health check
         .route("/health", get(handlers::health))
+        // Signed remote configuration, authenticated with the API token
+        .route("/v1/config/:org_id/:app_id", get(handlers::remote_config))
         // Ingestion endpoint with middleware
         .route(
             "/v1/ingest/:org_id/:app_id",
@@ -99,16 +101,35 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Database migrations, applied in order on startup
+const MIGRATIONS: &[&str] = &[
+    include_str!("../migrations/001_init.sql"),
+    include_str!("../migrations/002_event_spans.sql"),
+    include_str!("../migrations/003_event_context.sql"),
+    include_str!("../migrations/004_event_sample_rate.sql"),
+    include_str!("../migrations/005_event_ci_provider.sql"),
+    include_str!("../migrations/006_event_experiments.sql"),
+    include_str!("../migrations/007_remote_config.sql"),
+    include_str!("../migrations/008_dropped_events.sql"),
+    include_str!("../migrations/009_event_rollups.sql"),
+    include_str!("../migrations/010_event_environment.sql"),
+];
+
 /// Run database migrations
 async fn run_migrations(pool: &sqlx::PgPool) -> anyhow::Result<()> {
-    // Read migration file
-    let migration = include_str!("../migrations/001_init.sql");
-
-    // Split by semicolons and execute each statement
-    for statement in migration.split(';') {
-        let statement = statement.trim();
-        if !statement.is_empty() && !statement.starts_with("--") {
-            sqlx::query(statement).execute(pool).await.ok();
+    for migration in MIGRATIONS {
+        // Split by semicolons and execute each statement
+        for statement in migration.split(';') {
+            // Drop comment lines so statements preceded by a comment still run
+            let statement: String = statement
+                .lines()
+                .filter(|line| !line.trim_start().starts_with("--"))
+                .collect::<Vec<_>>()
+                .join("\n");
+            let statement = statement.trim();
+            if !statement.is_empty() {
+                sqlx::query(statement).execute(pool).await.ok();
+            }
         }
     }
 
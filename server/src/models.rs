@@ -3,6 +3,7 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use std::collections::HashMap;
 use uuid::Uuid;
 
 /// API token with associated secret
@@ -30,6 +31,52 @@ pub enum TokenTier {
     Enterprise,
 }
 
+/// Remote configuration row for an app, optionally for a single version
+#[derive(Debug, Clone, FromRow)]
+pub struct RemoteConfigRow {
+    pub telemetry_enabled: bool,
+    pub default_sample_rate: Option<f64>,
+    pub sample_rates: sqlx::types::Json<HashMap<String, f64>>,
+    pub disabled_event_types: Vec<String>,
+    pub sync_interval_secs: Option<i32>,
+}
+
+/// Remote configuration served to SDKs
+#[derive(Debug, Clone, Serialize)]
+pub struct RemoteConfig {
+    pub enabled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_sample_rate: Option<f64>,
+    pub sample_rates: HashMap<String, f64>,
+    pub disabled_event_types: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sync_interval_secs: Option<u32>,
+}
+
+impl Default for RemoteConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            default_sample_rate: None,
+            sample_rates: HashMap::new(),
+            disabled_event_types: Vec::new(),
+            sync_interval_secs: None,
+        }
+    }
+}
+
+impl From<RemoteConfigRow> for RemoteConfig {
+    fn from(row: RemoteConfigRow) -> Self {
+        Self {
+            enabled: row.telemetry_enabled,
+            default_sample_rate: row.default_sample_rate,
+            sample_rates: row.sample_rates.0,
+            disabled_event_types: row.disabled_event_types,
+            sync_interval_secs: row.sync_interval_secs.and_then(|s| u32::try_from(s).ok()),
+        }
+    }
+}
+
 /// Stored telemetry event
 #[derive(Debug, Clone, FromRow)]
 #[allow(dead_code)]
@@ -54,14 +101,31 @@ pub struct StoredEvent {
     // Environment
     pub os: Option<String>,
     pub os_version: Option<String>,
+    pub os_distro: Option<String>,
+    pub kernel_version: Option<String>,
     pub arch: Option<String>,
     pub ci: Option<bool>,
+    pub ci_provider: Option<String>,
+    pub ci_runner_image: Option<String>,
     pub shell: Option<String>,
+    pub container: Option<String>,
+    pub wsl: Option<bool>,
+    pub ssh: Option<bool>,
+    pub tty: Option<bool>,
+    pub terminal: Option<String>,
 
     // Event
     pub event_type: String,
     pub event_category: Option<String>,
     pub event_data: sqlx::types::Json<serde_json::Value>,
+    pub event_context: Option<sqlx::types::Json<serde_json::Value>>,
+    pub trace_id: Option<String>,
+    pub parent_event_id: Option<Uuid>,
+    pub sample_rate: Option<f64>,
+    pub experiments: Option<sqlx::types::Json<serde_json::Value>>,
+    pub event_count: i64,
+    pub last_timestamp: Option<DateTime<Utc>>,
+    pub total_duration_ms: Option<i64>,
 
     // Metadata
     pub sdk_version: String,
@@ -95,21 +159,45 @@ pub struct ServiceInfo {
     pub language_version: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Environment {
     pub os: String,
     pub os_version: Option<String>,
+    pub os_distro: Option<String>,
+    pub kernel_version: Option<String>,
     pub arch: Option<String>,
     pub ci: Option<bool>,
+    pub ci_provider: Option<String>,
+    pub ci_runner_image: Option<String>,
     pub shell: Option<String>,
+    pub container: Option<String>,
+    pub wsl: Option<bool>,
+    pub ssh: Option<bool>,
+    pub tty: Option<bool>,
+    pub terminal: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct EventData {
     #[serde(rename = "type")]
     pub event_type: String,
     pub category: Option<String>,
     pub data: serde_json::Value,
+    pub context: Option<serde_json::Value>,
+    pub trace_id: Option<String>,
+    pub parent_event_id: Option<Uuid>,
+    pub sample_rate: Option<f64>,
+    pub experiments: Option<serde_json::Value>,
+    pub rollup: Option<Rollup>,
+}
+
+/// Identical events merged into one by the SDK
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rollup {
+    pub count: i64,
+    pub first_timestamp: DateTime<Utc>,
+    pub last_timestamp: DateTime<Utc>,
+    pub total_duration_ms: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -124,6 +212,9 @@ pub struct Metadata {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EventBatch {
     pub events: Vec<IncomingEvent>,
+    /// Events the SDK dropped from its full local buffer, by category
+    #[serde(default)]
+    pub dropped_events: HashMap<String, i64>,
 }
 
 impl IncomingEvent {
@@ -144,12 +235,29 @@ impl IncomingEvent {
             session_id: self.session_id.clone(),
             os: Some(self.environment.os.clone()),
             os_version: self.environment.os_version.clone(),
+            os_distro: self.environment.os_distro.clone(),
+            kernel_version: self.environment.kernel_version.clone(),
             arch: self.environment.arch.clone(),
             ci: self.environment.ci,
+            ci_provider: self.environment.ci_provider.clone(),
+            ci_runner_image: self.environment.ci_runner_image.clone(),
             shell: self.environment.shell.clone(),
+            container: self.environment.container.clone(),
+            wsl: self.environment.wsl,
+            ssh: self.environment.ssh,
+            tty: self.environment.tty,
+            terminal: self.environment.terminal.clone(),
             event_type: self.event.event_type.clone(),
             event_category: self.event.category.clone(),
             event_data: sqlx::types::Json(self.event.data.clone()),
+            event_context: self.event.context.clone().map(sqlx::types::Json),
+            trace_id: self.event.trace_id.clone(),
+            parent_event_id: self.event.parent_event_id,
+            sample_rate: self.event.sample_rate,
+            experiments: self.event.experiments.clone().map(sqlx::types::Json),
+            event_count: self.event.rollup.as_ref().map_or(1, |r| r.count),
+            last_timestamp: self.event.rollup.as_ref().map(|r| r.last_timestamp),
+            total_duration_ms: self.event.rollup.as_ref().and_then(|r| r.total_duration_ms),
             sdk_version: self.metadata.sdk_version.clone(),
             transmission_timestamp: self.metadata.transmission_timestamp,
             batch_size: self.metadata.batch_size as i32,
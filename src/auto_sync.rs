@@ -2,9 +2,12 @@
 //!
 //! Automatically syncs buffered events to the server in the background.
 
+use crate::coalesce::Coalescer;
 use crate::error::Result;
+use crate::exporter::{export_pending, Exporter};
 use crate::storage::EventStorage;
 use crate::sync::SyncClient;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
@@ -20,6 +23,8 @@ pub struct AutoSyncConfig {
     pub sync_on_shutdown: bool,
     /// Maximum batch size per sync
     pub batch_size: usize,
+    /// Program to hand the shutdown sync to, see [`crate::upload`]
+    pub detached_upload: Option<PathBuf>,
 }
 
 impl Default for AutoSyncConfig {
@@ -28,6 +33,7 @@ impl Default for AutoSyncConfig {
             interval: 60,           // 60 seconds
             sync_on_shutdown: true, // Sync before dropping
             batch_size: 100,        // Match sync client default
+            detached_upload: None,
         }
     }
 }
@@ -41,14 +47,29 @@ pub struct AutoSyncTask {
 
 impl AutoSyncTask {
     /// Start a new auto-sync background task
+    ///
+    /// Events are sent to every exporter on each interval.
     pub fn start(
-        client: Arc<SyncClient>,
+        exporters: Arc<[Arc<dyn Exporter>]>,
         storage: Arc<RwLock<EventStorage>>,
         config: AutoSyncConfig,
+    ) -> Self {
+        Self::start_with_hooks(exporters, storage, config, None, None)
+    }
+
+    /// Start the task, refreshing the remote configuration of the sync client before
+    /// each sync and following its sync interval, and storing rollups whose
+    /// coalescing window closed
+    pub(crate) fn start_with_hooks(
+        exporters: Arc<[Arc<dyn Exporter>]>,
+        storage: Arc<RwLock<EventStorage>>,
+        config: AutoSyncConfig,
+        sync_client: Option<Arc<SyncClient>>,
+        coalescer: Option<Arc<Coalescer>>,
     ) -> Self {
         let shutdown = Arc::new(AtomicBool::new(false));
         let shutdown_clone = shutdown.clone();
-        let interval = Duration::from_secs(config.interval);
+        let default_interval = Duration::from_secs(config.interval);
 
         let handle = tokio::spawn(async move {
             loop {
@@ -57,13 +78,32 @@ impl AutoSyncTask {
                     break;
                 }
 
+                if let Some(client) = &sync_client {
+                    client.refresh_remote_config().await;
+                }
+
+                if let Some(coalescer) = &coalescer {
+                    let events = coalescer.take_closed();
+                    let storage = storage.write().await;
+                    for event in &events {
+                        if let Err(e) = storage.insert(event) {
+                            eprintln!("Failed to store telemetry event: {}", e);
+                        }
+                    }
+                }
+
                 // Perform sync
-                if let Err(e) = Self::sync_once(client.clone(), storage.clone()).await {
+                if let Err(e) = export_pending(&exporters, &storage).await {
                     // Log error but don't crash - sync will retry on next interval
                     eprintln!("Auto-sync error: {}", e);
                 }
 
                 // Wait for next interval
+                let interval = sync_client
+                    .as_ref()
+                    .and_then(|client| client.remote_config_cache().with(|c| c.sync_interval_secs))
+                    .flatten()
+                    .map_or(default_interval, Duration::from_secs);
                 tokio::time::sleep(interval).await;
             }
         });
@@ -75,40 +115,6 @@ impl AutoSyncTask {
         }
     }
 
-    /// Perform a single sync operation
-    async fn sync_once(client: Arc<SyncClient>, storage: Arc<RwLock<EventStorage>>) -> Result<()> {
-        use crate::event::EventBatch;
-        use uuid::Uuid;
-
-        // Get unsynced events
-        let storage_read = storage.read().await;
-        let events = storage_read.get_unsynced(client.config().batch_size)?;
-        drop(storage_read);
-
-        if events.is_empty() {
-            return Ok(());
-        }
-
-        let event_ids: Vec<Uuid> = events.iter().map(|e| e.event_id).collect();
-        let batch = EventBatch::new(events);
-
-        // Attempt sync
-        match client.sync(batch).await {
-            Ok(response) => {
-                if response.accepted() > 0 {
-                    let storage_write = storage.write().await;
-                    storage_write.mark_synced(&event_ids)?;
-                }
-                Ok(())
-            }
-            Err(e) => {
-                let storage_write = storage.write().await;
-                storage_write.increment_retry(&event_ids)?;
-                Err(e)
-            }
-        }
-    }
-
     /// Request graceful shutdown of the background task
     pub fn shutdown(&mut self) {
         self.shutdown.store(true, Ordering::SeqCst);
@@ -128,6 +134,11 @@ impl AutoSyncTask {
     pub fn should_sync_on_shutdown(&self) -> bool {
         self.config.sync_on_shutdown
     }
+
+    /// Program to hand the shutdown sync to, if any
+    pub fn detached_upload(&self) -> Option<&Path> {
+        self.config.detached_upload.as_deref()
+    }
 }
 
 impl Drop for AutoSyncTask {
@@ -140,7 +151,7 @@ impl Drop for AutoSyncTask {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::sync::SyncConfig;
+    use crate::sync::{SyncClient, SyncConfig};
     use std::path::PathBuf;
     use uuid::Uuid;
 
@@ -161,16 +172,17 @@ mod tests {
             .secret("test-secret")
             .build()
             .unwrap();
-        let client = Arc::new(SyncClient::new(config).unwrap());
+        let client: Arc<dyn Exporter> = Arc::new(SyncClient::new(config).unwrap());
 
         // Create auto-sync task
         let mut task = AutoSyncTask::start(
-            client,
+            vec![client].into(),
             storage,
             AutoSyncConfig {
                 interval: 1,
                 sync_on_shutdown: true,
                 batch_size: 100,
+                detached_upload: None,
             },
         );
 
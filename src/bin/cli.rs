@@ -26,6 +26,7 @@ use dialoguer::{Confirm, Input, Password, Select};
 use indicatif::{ProgressBar, ProgressStyle};
 use std::path::PathBuf;
 use std::time::Instant;
+use telemetry_kit::config::{SyncSettings, TelemetryConfig, CONFIG_FILE_NAME};
 use telemetry_kit::TelemetryKit;
 
 #[derive(Parser)]
@@ -79,6 +80,10 @@ enum Commands {
         /// Service name
         #[arg(short = 'n', long)]
         service_name: Option<String>,
+
+        /// Path to write the configuration file to (default: telemetry-kit.toml)
+        #[arg(short, long)]
+        config: Option<PathBuf>,
     },
 
     /// Test sync credentials and connectivity
@@ -112,11 +117,15 @@ enum Commands {
         /// Force sync even if auto-sync is enabled
         #[arg(short, long)]
         force: bool,
+
+        /// Path to configuration file (default: telemetry-kit.toml)
+        #[arg(short, long)]
+        config: Option<PathBuf>,
     },
 
     /// Validate telemetry configuration
     Validate {
-        /// Path to configuration file
+        /// Path to configuration file (default: telemetry-kit.toml)
         #[arg(short, long)]
         config: Option<PathBuf>,
     },
@@ -176,6 +185,12 @@ enum ConsentAction {
 
 #[tokio::main]
 async fn main() {
+    // Detached upload started by `shutdown()`, in this CLI or an application using it
+    if let Some(db_path) = telemetry_kit::upload::requested_db_path() {
+        let code = i32::from(telemetry_kit::upload::run(db_path).await.is_err());
+        std::process::exit(code);
+    }
+
     let start = Instant::now();
     let cli = Cli::parse();
 
@@ -196,6 +211,11 @@ async fn main() {
                         "AR+s0qak9x6LDQoH5MJ0kFaKvvImWo7C0So5kqH6CT0=",
                     )
             })
+            .map(|b| match std::env::current_exe() {
+                // Upload in the background so the CLI exits without waiting on the network
+                Ok(exe) => b.detached_upload(exe),
+                Err(_) => b,
+            })
             .and_then(|b| b.auto_sync(true).build())
             .ok()
     } else {
@@ -209,7 +229,11 @@ async fn main() {
             yes,
             with_sync,
         } => cmd_new(name, project_type, yes, with_sync).await,
-        Commands::Init { yes, service_name } => cmd_init(yes, service_name, cli.service).await,
+        Commands::Init {
+            yes,
+            service_name,
+            config,
+        } => cmd_init(yes, service_name, config, cli.service).await,
         Commands::Test {
             org_id,
             app_id,
@@ -217,7 +241,7 @@ async fn main() {
             secret,
         } => cmd_test(org_id, app_id, token, secret).await,
         Commands::Stats { detailed } => cmd_stats(detailed, cli.service).await,
-        Commands::Sync { force } => cmd_sync(force, cli.service).await,
+        Commands::Sync { force, config } => cmd_sync(force, config, cli.service).await,
         Commands::Validate { config } => cmd_validate(config, cli.service).await,
         Commands::Clean { yes, all } => cmd_clean(yes, all, cli.service).await,
         #[cfg(feature = "privacy")]
@@ -240,7 +264,7 @@ async fn main() {
             })
             .await;
 
-        // Shutdown triggers sync if auto_sync is enabled
+        // Shutdown hands the sync to a detached uploader if auto_sync is enabled
         let _ = t.shutdown().await;
     }
 
@@ -377,11 +401,33 @@ async fn cmd_new(
 async fn cmd_init(
     skip_prompts: bool,
     service_name: Option<String>,
+    config: Option<PathBuf>,
     override_service: Option<String>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     println!("{}", "🔭 Telemetry Kit - Interactive Setup".cyan().bold());
     println!();
 
+    let config_path = config_path(config);
+    if config_path.exists() {
+        let overwrite = !skip_prompts
+            && Confirm::new()
+                .with_prompt(format!(
+                    "{} already exists. Overwrite?",
+                    config_path.display()
+                ))
+                .default(false)
+                .interact()?;
+
+        if !overwrite {
+            println!(
+                "{} Keeping existing {}",
+                "!".yellow().bold(),
+                config_path.display()
+            );
+            return Ok(());
+        }
+    }
+
     // Determine service name
     let service = if let Some(name) = override_service.or(service_name) {
         name
@@ -407,6 +453,9 @@ async fn cmd_init(
     println!("{} {}", "Service:".green(), service);
     println!();
 
+    let mut project_config = TelemetryConfig::default();
+    project_config.service.name = Some(service.clone());
+
     // Ask about sync configuration
     let configure_sync = if skip_prompts {
         false
@@ -439,54 +488,58 @@ async fn cmd_init(
         match test_credentials(&org_id, &app_id, &token, &secret).await {
             Ok(_) => {
                 println!("{} Credentials validated successfully!", "✓".green().bold());
-                println!();
-                println!("{}", "Configuration saved".green());
-                println!();
-                println!("Add to your code:");
-                println!();
-                println!("{}", "  use telemetry_kit::prelude::*;".dimmed());
-                println!();
-                println!("{}", "  let telemetry = TelemetryKit::builder()".dimmed());
-                println!(
-                    "{}",
-                    format!("      .service_name(\"{}\")?", service).dimmed()
-                );
-                println!(
-                    "{}",
-                    format!(
-                        "      .with_sync_credentials(\"{}\", \"{}\", \"{}\", \"{}\")?",
-                        org_id, app_id, token, "***"
-                    )
-                    .dimmed()
-                );
-                println!("{}", "      .build()?;".dimmed());
             }
             Err(e) => {
                 println!("{} Failed to validate credentials: {}", "✗".red().bold(), e);
-                println!();
                 println!(
                     "{}",
-                    "Configuration saved anyway (you can test later with 'telemetry-kit test')"
-                        .yellow()
+                    "Saving anyway (you can test later with 'telemetry-kit test')".yellow()
                 );
             }
         }
-    } else {
-        println!();
-        println!("{}", "Local-only mode".green());
-        println!();
-        println!("Add to your code:");
+
+        // Only a reference to the credentials is written to the file
+        project_config.sync = Some(SyncSettings::new(org_id, app_id));
+    }
+
+    project_config.save(&config_path)?;
+
+    println!();
+    println!(
+        "{} {}",
+        "Configuration saved to".green(),
+        config_path.display()
+    );
+
+    if let Some(sync) = &project_config.sync {
         println!();
-        println!("{}", "  use telemetry_kit::prelude::*;".dimmed());
+        println!("Set the sync credentials in your environment (not in the file):");
         println!();
-        println!("{}", "  let telemetry = TelemetryKit::builder()".dimmed());
         println!(
             "{}",
-            format!("      .service_name(\"{}\")?", service).dimmed()
+            format!("  export {}=<token>", sync.token_env).dimmed()
+        );
+        println!(
+            "{}",
+            format!("  export {}=<secret>", sync.secret_env).dimmed()
         );
-        println!("{}", "      .build()?;".dimmed());
     }
 
+    println!();
+    println!("Add to your code (with the `config` feature enabled):");
+    println!();
+    println!("{}", "  use telemetry_kit::prelude::*;".dimmed());
+    println!();
+    println!(
+        "{}",
+        format!(
+            "  let telemetry = TelemetryBuilder::from_config_file(\"{}\")?",
+            config_path.display()
+        )
+        .dimmed()
+    );
+    println!("{}", "      .build()?;".dimmed());
+
     println!();
     println!("{}", "✓ Initialization complete!".green().bold());
     println!();
@@ -673,49 +726,84 @@ async fn cmd_stats(
 }
 
 /// Manually trigger sync
-async fn cmd_sync(force: bool, service: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+async fn cmd_sync(
+    force: bool,
+    config: Option<PathBuf>,
+    service: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
     println!("{}", "🔄 Manual Sync".cyan().bold());
     println!();
 
-    let service_name = service.unwrap_or_else(|| {
-        std::env::current_dir()
-            .ok()
-            .and_then(|p| {
-                p.file_name()
-                    .and_then(|n| n.to_str())
-                    .map(|s| s.to_string())
-            })
-            .unwrap_or_else(|| "unknown".to_string())
-    });
+    let config_path = config_path(config);
+    if !config_path.exists() {
+        return Err(format!(
+            "No configuration found at {} (run 'telemetry-kit init' first)",
+            config_path.display()
+        )
+        .into());
+    }
+    let project_config = TelemetryConfig::load(&config_path)?;
 
-    // Get database path
-    let mut db_path = dirs::home_dir().ok_or("Cannot determine home directory")?;
-    db_path.push(".telemetry-kit");
-    db_path.push(format!("{}.db", service_name));
+    if project_config.sync.is_none() {
+        return Err(format!("No [sync] section in {}", config_path.display()).into());
+    }
+
+    if project_config.auto_sync.enabled != Some(false) && !force {
+        println!(
+            "{} Auto-sync is enabled, so your application syncs in the background",
+            "!".yellow().bold()
+        );
+        println!(
+            "Run {} to sync now anyway",
+            "telemetry-kit sync --force".cyan()
+        );
+        return Ok(());
+    }
+
+    let service_name = resolve_service_name(service, &project_config);
+    let db_path = resolve_db_path(&service_name, &project_config)?;
 
     if !db_path.exists() {
         return Err(format!("No telemetry data found for service: {}", service_name).into());
     }
 
-    println!(
-        "{} This command requires sync credentials",
-        "!".yellow().bold()
-    );
-    println!("Manual sync from CLI is not yet fully implemented.");
-    println!();
-    println!("Use the SDK's .sync() method in your application:");
-    println!();
-    println!("{}", "  telemetry.sync().await?;".dimmed());
-    println!();
-    println!("Or enable auto-sync:");
-    println!();
-    println!("{}", "  TelemetryKit::builder()".dimmed());
-    println!("{}", "      .auto_sync(true)".dimmed());
-    println!("{}", "      .build()?;".dimmed());
+    use telemetry_kit::prelude::*;
+    let telemetry = TelemetryBuilder::new()
+        .with_config(&project_config)?
+        .service_name(&service_name)?
+        .db_path(db_path)
+        .auto_sync(false)
+        .build()?;
 
-    if force {
-        println!();
-        println!("{} Force flag noted (not yet implemented)", "!".yellow());
+    let before = telemetry.stats().await?.unsynced_events;
+    println!("{} {}", "Service:".green(), service_name.cyan());
+    println!("{} {}", "Unsynced:".green(), before.to_string().yellow());
+
+    // Each sync exports one batch; stop when a batch makes no progress
+    let mut remaining = before;
+    while remaining > 0 {
+        telemetry.sync().await?;
+        let unsynced = telemetry.stats().await?.unsynced_events;
+        if unsynced >= remaining {
+            break;
+        }
+        remaining = unsynced;
+    }
+
+    println!();
+    if remaining == 0 {
+        println!(
+            "{} Synced {} events",
+            "✓".green().bold(),
+            before.to_string().cyan()
+        );
+    } else {
+        println!(
+            "{} Synced {} events, {} still pending",
+            "!".yellow().bold(),
+            (before - remaining).to_string().cyan(),
+            remaining.to_string().yellow()
+        );
     }
 
     Ok(())
@@ -723,29 +811,66 @@ async fn cmd_sync(force: bool, service: Option<String>) -> Result<(), Box<dyn st
 
 /// Validate configuration
 async fn cmd_validate(
-    _config: Option<PathBuf>,
+    config: Option<PathBuf>,
     service: Option<String>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     println!("{}", "✓ Validating Configuration".cyan().bold());
     println!();
 
-    let service_name = service.unwrap_or_else(|| {
-        std::env::current_dir()
-            .ok()
-            .and_then(|p| {
-                p.file_name()
-                    .and_then(|n| n.to_str())
-                    .map(|s| s.to_string())
-            })
-            .unwrap_or_else(|| "unknown".to_string())
-    });
+    // A missing file is only an error when its path was given explicitly
+    let explicit_path = config.is_some();
+    let config_path = config_path(config);
+    let project_config = if config_path.exists() {
+        match TelemetryConfig::load(&config_path) {
+            Ok(project_config) => {
+                println!(
+                    "{} Configuration file: {}",
+                    "✓".green().bold(),
+                    config_path.display().to_string().dimmed()
+                );
+                project_config
+            }
+            Err(e) => {
+                println!("{} {}", "✗".red().bold(), e);
+                return Err(e.into());
+            }
+        }
+    } else if explicit_path {
+        return Err(format!("Configuration file not found: {}", config_path.display()).into());
+    } else {
+        println!(
+            "{} No {} found (run {} to create one)",
+            "!".yellow().bold(),
+            CONFIG_FILE_NAME,
+            "telemetry-kit init".cyan()
+        );
+        TelemetryConfig::default()
+    };
 
+    let service_name = resolve_service_name(service, &project_config);
     println!("{} {}", "Service:".green(), service_name.cyan());
 
+    // Applying the settings checks IDs, sampling rules and sync credentials
+    use telemetry_kit::prelude::*;
+    let builder = match TelemetryBuilder::new().with_config(&project_config) {
+        Ok(builder) => builder,
+        Err(e) => {
+            println!("{} {}", "✗".red().bold(), e);
+            return Err(e.into());
+        }
+    };
+
+    if let Some(sync) = &project_config.sync {
+        println!(
+            "{} Sync credentials found in {} and {}",
+            "✓".green().bold(),
+            sync.token_env,
+            sync.secret_env
+        );
+    }
+
     // Check database
-    let mut db_path = dirs::home_dir().ok_or("Cannot determine home directory")?;
-    db_path.push(".telemetry-kit");
-    db_path.push(format!("{}.db", service_name));
+    let db_path = resolve_db_path(&service_name, &project_config)?;
 
     if db_path.exists() {
         println!(
@@ -755,10 +880,10 @@ async fn cmd_validate(
         );
 
         // Try to load telemetry
-        use telemetry_kit::prelude::*;
-        match TelemetryKit::builder()
+        match builder
             .service_name(&service_name)?
             .db_path(db_path)
+            .auto_sync(false)
             .build()
         {
             Ok(_) => {
@@ -771,9 +896,8 @@ async fn cmd_validate(
         }
     } else {
         println!(
-            "{} No database found (run {} first)",
-            "!".yellow().bold(),
-            "telemetry-kit init".cyan()
+            "{} No database found yet (it is created when your application first runs)",
+            "!".yellow().bold()
         );
     }
 
@@ -903,6 +1027,42 @@ async fn test_credentials(
     Ok(())
 }
 
+/// Path of the configuration file, defaulting to the working directory
+fn config_path(config: Option<PathBuf>) -> PathBuf {
+    config.unwrap_or_else(|| PathBuf::from(CONFIG_FILE_NAME))
+}
+
+/// Get service name from override, configuration file or current directory
+fn resolve_service_name(override_service: Option<String>, config: &TelemetryConfig) -> String {
+    override_service
+        .or_else(|| config.service.name.clone())
+        .unwrap_or_else(|| {
+            std::env::current_dir()
+                .ok()
+                .and_then(|p| {
+                    p.file_name()
+                        .and_then(|n| n.to_str())
+                        .map(|s| s.to_string())
+                })
+                .unwrap_or_else(|| "unknown".to_string())
+        })
+}
+
+/// Get database path from configuration file or the default location
+fn resolve_db_path(
+    service_name: &str,
+    config: &TelemetryConfig,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    if let Some(path) = &config.service.db_path {
+        return Ok(path.clone());
+    }
+
+    let mut db_path = dirs::home_dir().ok_or("Cannot determine home directory")?;
+    db_path.push(".telemetry-kit");
+    db_path.push(format!("{}.db", service_name));
+    Ok(db_path)
+}
+
 /// Get service name from override or current directory
 fn get_service_name(
     override_service: Option<String>,
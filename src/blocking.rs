@@ -0,0 +1,409 @@
+//! Blocking (non-async) API for synchronous applications
+//!
+//! [`TelemetryKit`] wraps the async SDK for applications that do not run a tokio
+//! runtime. Tracking calls write straight to the local SQLite buffer, while uploads
+//! run on a dedicated background thread.
+//!
+//! The feature does not remove the tokio dependency: it enables `sync`, which pulls
+//! in tokio, and the background thread drives a current-thread tokio runtime for
+//! uploads. Your application just never has to create or enter one.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use telemetry_kit::prelude::*;
+//!
+//! fn main() -> telemetry_kit::Result<()> {
+//!     let telemetry = TelemetryKit::builder()
+//!         .service_name("my-cli")?
+//!         .build_blocking()?;
+//!
+//!     telemetry.track_command("build", |event| event.success(true))?;
+//!
+//!     telemetry.shutdown()?;
+//!     Ok(())
+//! }
+//! ```
+
+use crate::auto_sync::AutoSyncConfig;
+use crate::error::{Result, TelemetryError};
+use crate::event::{
+    CommandEventBuilder, ErrorEventBuilder, FeatureEventBuilder, TelemetryEvent, TrackableError,
+};
+use crate::metrics::{Counter, Gauge, Histogram};
+use crate::span::Span;
+use crate::telemetry::EventStats;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Blocking telemetry SDK
+///
+/// Created with [`TelemetryBuilder::build_blocking`](crate::TelemetryBuilder::build_blocking).
+/// Must not be used for `sync`, `stats` or `shutdown` from inside an async task,
+/// as those calls block the current thread.
+pub struct TelemetryKit {
+    inner: crate::TelemetryKit,
+    commands: Sender<Command>,
+    worker: Mutex<Option<JoinHandle<()>>>,
+}
+
+/// Requests handled by the background thread
+enum Command {
+    Sync(Sender<Result<()>>),
+    Stats(Sender<Result<EventStats>>),
+    Shutdown(Sender<Result<()>>),
+}
+
+impl TelemetryKit {
+    /// Wrap an async instance and start the background thread
+    pub(crate) fn new(
+        inner: crate::TelemetryKit,
+        auto_sync_enabled: bool,
+        auto_sync_config: AutoSyncConfig,
+        metrics_interval: Duration,
+    ) -> Result<Self> {
+        let (commands, receiver) = mpsc::channel();
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+
+        let kit = inner.clone();
+        let schedule = Schedule {
+            sync_interval: auto_sync_enabled
+                .then(|| Duration::from_secs(auto_sync_config.interval)),
+            metrics_interval,
+            sync_on_shutdown: auto_sync_enabled && auto_sync_config.sync_on_shutdown,
+            detached_upload: auto_sync_config.detached_upload.clone(),
+        };
+
+        let worker = std::thread::Builder::new()
+            .name("telemetry-kit-upload".to_string())
+            .spawn(move || run_worker(kit, runtime, receiver, schedule))?;
+
+        Ok(Self {
+            inner,
+            commands,
+            worker: Mutex::new(Some(worker)),
+        })
+    }
+
+    /// Track a command execution
+    pub fn track_command<F>(&self, command: impl Into<String>, builder_fn: F) -> Result<()>
+    where
+        F: FnOnce(CommandEventBuilder) -> CommandEventBuilder,
+    {
+        let event_data = builder_fn(CommandEventBuilder::new(command)).build();
+        self.inner
+            .track_event_detached("command_execution", Some("usage"), event_data)
+    }
+
+    /// Track a feature usage
+    pub fn track_feature<F>(&self, feature: impl Into<String>, builder_fn: F) -> Result<()>
+    where
+        F: FnOnce(FeatureEventBuilder) -> FeatureEventBuilder,
+    {
+        let event_data = builder_fn(FeatureEventBuilder::new(feature)).build();
+        self.inner
+            .track_event_detached("feature_used", Some("library"), event_data)
+    }
+
+    /// Track an error and its chain of sources, without messages
+    pub fn track_error<E: TrackableError + ?Sized>(&self, error: &E) -> Result<()> {
+        self.track_error_with(error, |event| event)
+    }
+
+    /// Track an error, customizing the event
+    pub fn track_error_with<E, F>(&self, error: &E, builder_fn: F) -> Result<()>
+    where
+        E: TrackableError + ?Sized,
+        F: FnOnce(ErrorEventBuilder) -> ErrorEventBuilder,
+    {
+        let event_data = builder_fn(ErrorEventBuilder::new(error)).build();
+        self.inner
+            .track_event_detached("error", Some("error"), event_data)
+    }
+
+    /// Track that an experiment or feature flag variant was shown
+    pub fn track_exposure(
+        &self,
+        experiment: impl Into<String>,
+        variant: impl Into<String>,
+    ) -> Result<()> {
+        self.inner
+            .track_exposure_detached(experiment.into(), variant.into())
+    }
+
+    /// Pick the variant of an experiment for this installation
+    ///
+    /// # Panics
+    ///
+    /// Panics if `variants` is empty.
+    pub fn variant<'a>(&self, experiment: &str, variants: &[&'a str]) -> &'a str {
+        self.inner.variant(experiment, variants)
+    }
+
+    /// Track a custom event
+    pub fn track_custom(
+        &self,
+        event_type: impl Into<String>,
+        data: serde_json::Value,
+    ) -> Result<()> {
+        self.inner.track_event_detached(event_type, None, data)
+    }
+
+    /// Track a strongly typed event
+    pub fn track<E: TelemetryEvent>(&self, event: E) -> Result<()> {
+        self.inner
+            .track_event_detached(event.event_type(), event.category(), event.to_data())
+    }
+
+    /// Set a property attached to every subsequent event
+    pub fn set_property<T: serde::Serialize + ?Sized>(&self, key: impl Into<String>, value: &T) {
+        self.inner.set_property(key, value)
+    }
+
+    /// Stop attaching a property to events
+    pub fn remove_property(&self, key: &str) {
+        self.inner.remove_property(key)
+    }
+
+    /// Start a span that records its duration and outcome when dropped (see [`crate::span`])
+    pub fn span(&self, name: impl Into<String>) -> Span {
+        self.inner.span(name)
+    }
+
+    /// Get or create a counter (see [`crate::metrics`])
+    pub fn counter(&self, name: &str) -> Counter {
+        self.inner.counter(name)
+    }
+
+    /// Get or create a gauge (see [`crate::metrics`])
+    pub fn gauge(&self, name: &str) -> Gauge {
+        self.inner.gauge(name)
+    }
+
+    /// Get or create a histogram (see [`crate::metrics`])
+    pub fn histogram(&self, name: &str) -> Histogram {
+        self.inner.histogram(name)
+    }
+
+    /// Write the current metrics summary to the local buffer now
+    pub fn flush_metrics(&self) -> Result<()> {
+        self.inner.flush_metrics_detached()
+    }
+
+    /// Send buffered events now, waiting for the upload to finish
+    pub fn sync(&self) -> Result<()> {
+        self.request(Command::Sync)
+    }
+
+    /// Get statistics about buffered events
+    pub fn stats(&self) -> Result<EventStats> {
+        self.request(Command::Stats)
+    }
+
+    /// Stop the background thread, syncing first if auto-sync and `sync_on_shutdown` are enabled
+    ///
+    /// Pending metrics and, with session tracking, a `session_end` event are written
+    /// to the local buffer first.
+    ///
+    /// Calling this more than once is a no-op.
+    pub fn shutdown(&self) -> Result<()> {
+        let Some(worker) = self.worker.lock().ok().and_then(|mut w| w.take()) else {
+            return Ok(());
+        };
+
+        let result = self.request(Command::Shutdown);
+        worker
+            .join()
+            .map_err(|_| TelemetryError::Other("Upload thread panicked".to_string()))?;
+        result
+    }
+
+    /// Get the underlying async instance
+    ///
+    /// Useful for registering it with [`set_global`](crate::set_global).
+    pub fn as_async(&self) -> &crate::TelemetryKit {
+        &self.inner
+    }
+
+    fn request<T>(&self, command: impl FnOnce(Sender<Result<T>>) -> Command) -> Result<T> {
+        let (reply, response) = mpsc::channel();
+        self.commands
+            .send(command(reply))
+            .map_err(|_| TelemetryError::Other("TelemetryKit has been shut down".to_string()))?;
+        response
+            .recv()
+            .map_err(|_| TelemetryError::Other("Upload thread stopped".to_string()))?
+    }
+}
+
+/// When the background thread does periodic work
+struct Schedule {
+    sync_interval: Option<Duration>,
+    metrics_interval: Duration,
+    sync_on_shutdown: bool,
+    detached_upload: Option<PathBuf>,
+}
+
+/// Background thread: serves requests, syncs on the auto-sync interval and
+/// flushes metrics on the metrics interval
+fn run_worker(
+    kit: crate::TelemetryKit,
+    runtime: tokio::runtime::Runtime,
+    receiver: Receiver<Command>,
+    schedule: Schedule,
+) {
+    let mut next_sync = schedule.sync_interval.map(|i| Instant::now() + i);
+    let mut next_metrics = Instant::now() + schedule.metrics_interval;
+
+    loop {
+        let deadline = next_sync.map_or(next_metrics, |s| s.min(next_metrics));
+
+        match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(Command::Sync(reply)) => {
+                let _ = reply.send(runtime.block_on(kit.sync()));
+            }
+            Ok(Command::Stats(reply)) => {
+                let _ = reply.send(runtime.block_on(kit.stats()));
+            }
+            Ok(Command::Shutdown(reply)) => {
+                let result = runtime.block_on(async {
+                    kit.flush_metrics().await?;
+                    kit.end_session().await?;
+                    kit.flush_rollups().await?;
+                    if schedule.sync_on_shutdown && kit.has_exporters() {
+                        kit.sync_on_shutdown(schedule.detached_upload.as_deref())
+                            .await?;
+                    }
+                    Ok(())
+                });
+                let _ = reply.send(result);
+                break;
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                let now = Instant::now();
+
+                if now >= next_metrics {
+                    if let Err(e) = runtime.block_on(kit.flush_metrics()) {
+                        eprintln!("Metrics flush error: {}", e);
+                    }
+                    next_metrics = now + schedule.metrics_interval;
+                }
+
+                if let (Some(at), Some(interval)) = (next_sync, schedule.sync_interval) {
+                    if now >= at {
+                        if let Err(e) = runtime.block_on(kit.sync_events()) {
+                            // Log error but don't crash - sync will retry on next interval
+                            eprintln!("Auto-sync error: {}", e);
+                        }
+                        let interval = kit.remote_sync_interval().unwrap_or(interval);
+                        next_sync = Some(now + interval);
+                    }
+                }
+            }
+            // Every handle was dropped without an explicit shutdown
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::EventBatch;
+    use crate::exporter::{ExportFuture, ExportResult, Exporter};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use uuid::Uuid;
+
+    struct Counting(Arc<AtomicUsize>);
+
+    impl Exporter for Counting {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        fn export<'a>(&'a self, batch: &'a EventBatch) -> ExportFuture<'a> {
+            Box::pin(async move {
+                self.0.fetch_add(batch.size(), Ordering::SeqCst);
+                Ok(ExportResult::accept_all(batch))
+            })
+        }
+    }
+
+    fn build(exported: Arc<AtomicUsize>, auto_sync: bool) -> (TelemetryKit, std::path::PathBuf) {
+        let unique_name = format!("test-blocking-{}", Uuid::new_v4());
+        let db_path = std::env::temp_dir().join(format!("{}.db", unique_name));
+
+        let telemetry = crate::TelemetryKit::builder()
+            .service_name(&unique_name)
+            .unwrap()
+            .db_path(&db_path)
+            .exporter(Counting(exported))
+            .auto_sync(auto_sync)
+            .build_blocking()
+            .unwrap();
+
+        (telemetry, db_path)
+    }
+
+    #[test]
+    fn test_track_and_sync_without_runtime() {
+        let exported = Arc::new(AtomicUsize::new(0));
+        let (telemetry, db_path) = build(exported.clone(), true);
+
+        telemetry
+            .track_command("build", |event| event.success(true))
+            .unwrap();
+        telemetry
+            .track_feature("auth", |event| event.method("oauth"))
+            .unwrap();
+
+        assert_eq!(telemetry.stats().unwrap().unsynced_events, 2);
+
+        telemetry.sync().unwrap();
+        assert_eq!(exported.load(Ordering::SeqCst), 2);
+        assert_eq!(telemetry.stats().unwrap().unsynced_events, 0);
+
+        telemetry.shutdown().unwrap();
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[test]
+    fn test_shutdown_syncs_and_is_idempotent() {
+        let exported = Arc::new(AtomicUsize::new(0));
+        let (telemetry, db_path) = build(exported.clone(), true);
+
+        telemetry
+            .track_custom("custom", serde_json::json!({}))
+            .unwrap();
+        telemetry.counter("cache_hits").add(5);
+
+        // The metrics summary is flushed and synced along with the custom event
+        telemetry.shutdown().unwrap();
+        assert_eq!(exported.load(Ordering::SeqCst), 2);
+
+        telemetry.shutdown().unwrap();
+        assert!(telemetry.sync().is_err());
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[test]
+    fn test_shutdown_without_auto_sync_does_not_sync() {
+        let exported = Arc::new(AtomicUsize::new(0));
+        let (telemetry, db_path) = build(exported.clone(), false);
+
+        telemetry
+            .track_custom("custom", serde_json::json!({}))
+            .unwrap();
+
+        telemetry.shutdown().unwrap();
+        assert_eq!(exported.load(Ordering::SeqCst), 0);
+        let _ = std::fs::remove_file(db_path);
+    }
+}
@@ -9,10 +9,27 @@ use crate::sync::SyncConfig;
 #[cfg(feature = "sync")]
 use crate::auto_sync::AutoSyncConfig;
 
+#[cfg(feature = "sync")]
+use crate::exporter::Exporter;
+
 #[cfg(feature = "privacy")]
 use crate::privacy::PrivacyConfig;
 
+#[cfg(feature = "otlp")]
+use crate::otlp::OtlpConfig;
+
+#[cfg(feature = "blocking")]
+use crate::blocking;
+
+use crate::config::TelemetryConfig;
+use crate::environment::{detect_environment, CiPolicy, EnvironmentConfig};
+use crate::metrics::DEFAULT_METRICS_INTERVAL;
+use crate::processor::EventProcessor;
+use crate::sampling::{Sampler, SamplingRule};
+use crate::storage::{BufferLimits, OverflowPolicy};
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 
 /// Builder for configuring telemetry
 #[derive(Debug, Default)]
@@ -20,10 +37,28 @@ pub struct TelemetryBuilder {
     service_name: Option<String>,
     service_version: Option<String>,
     db_path: Option<PathBuf>,
+    metrics_interval: Option<u64>,
+    capture_panics: bool,
+    include_panic_messages: bool,
+    session_tracking: bool,
+    session_timeout: Option<u64>,
+    lifecycle_tracking: bool,
+    sampling_rules: Vec<SamplingRule>,
+    loaded_sampling_rules: Vec<SamplingRule>,
+    default_sample_rate: Option<f64>,
+    processors: Vec<Arc<dyn EventProcessor>>,
+    default_properties: serde_json::Map<String, serde_json::Value>,
+    environment: EnvironmentConfig,
+    ci_policy: CiPolicy,
+    buffer_limits: BufferLimits,
+    coalesce_window: Option<u64>,
 
     #[cfg(feature = "sync")]
     sync_config: Option<SyncConfig>,
 
+    #[cfg(feature = "sync")]
+    exporters: Vec<Arc<dyn Exporter>>,
+
     #[cfg(feature = "sync")]
     auto_sync_enabled: bool,
 
@@ -32,6 +67,9 @@ pub struct TelemetryBuilder {
 
     #[cfg(feature = "privacy")]
     privacy_config: Option<PrivacyConfig>,
+
+    #[cfg(feature = "otlp")]
+    otlp_config: Option<OtlpConfig>,
 }
 
 impl TelemetryBuilder {
@@ -40,6 +78,44 @@ impl TelemetryBuilder {
         Self::default()
     }
 
+    /// Create a builder from a `telemetry-kit.toml` file
+    ///
+    /// Builder calls made afterwards override the file's values. Sync credentials
+    /// are read from the environment variables the file names.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use telemetry_kit::prelude::*;
+    /// # fn example() -> Result<()> {
+    /// let telemetry = TelemetryBuilder::from_config_file("telemetry-kit.toml")?
+    ///     .service_version(env!("CARGO_PKG_VERSION"))
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "config")]
+    pub fn from_config_file(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        Self::new().with_config(&TelemetryConfig::load(path)?)
+    }
+
+    /// Create a builder from `TK_*` environment variables
+    ///
+    /// See [`TelemetryConfig::from_env`] for the variables read. Builder calls made
+    /// afterwards override their values.
+    pub fn from_env() -> Result<Self> {
+        Self::new().with_config(&TelemetryConfig::from_env()?)
+    }
+
+    /// Apply loaded settings, replacing the values they set
+    ///
+    /// Can be chained to layer sources, e.g. a file and then the environment.
+    /// Sampling rules from settings are evaluated after rules added with
+    /// [`sampling_rule`](Self::sampling_rule).
+    pub fn with_config(self, config: &TelemetryConfig) -> Result<Self> {
+        config.apply(self)
+    }
+
     /// Set the service name (required)
     pub fn service_name(mut self, name: impl Into<String>) -> Result<Self> {
         let name_str = name.into();
@@ -78,6 +154,181 @@ impl TelemetryBuilder {
         self
     }
 
+    /// Add a custom export destination
+    ///
+    /// May be called any number of times. Custom exporters receive the same batches
+    /// as the built-in sync client and take part in auto-sync.
+    #[cfg(feature = "sync")]
+    pub fn exporter(mut self, exporter: impl Exporter + 'static) -> Self {
+        self.exporters.push(Arc::new(exporter));
+        self
+    }
+
+    /// Set the interval between metrics summaries in seconds (default: 60)
+    pub fn metrics_interval(mut self, seconds: u64) -> Self {
+        self.metrics_interval = Some(seconds);
+        self
+    }
+
+    /// Record a `crash` event whenever a thread panics
+    ///
+    /// Installs a panic hook that writes the panic location and a path-sanitized
+    /// backtrace to the local buffer before running the previously installed hook.
+    /// The crash is synced on the next run if the process aborts.
+    pub fn capture_panics(mut self) -> Self {
+        self.capture_panics = true;
+        self
+    }
+
+    /// Record sanitized panic messages in `crash` events (off by default)
+    ///
+    /// Panic messages are free text and often include user input, so they are only
+    /// recorded when enabled, with paths and email addresses sanitized. Requires the
+    /// `privacy` feature; without it messages are never recorded.
+    pub fn include_panic_messages(mut self, include: bool) -> Self {
+        self.include_panic_messages = include;
+        self
+    }
+
+    /// Record `session_start` and `session_end` events (disabled by default)
+    ///
+    /// `session_start` is recorded on build and `session_end`, with the session
+    /// duration and event count, on `shutdown()`.
+    pub fn session_tracking(mut self, enabled: bool) -> Self {
+        self.session_tracking = enabled;
+        self
+    }
+
+    /// Start a new session after this many seconds without events
+    ///
+    /// By default a session lasts as long as the process. Useful for long-running
+    /// services; with session tracking enabled, the expired session's `session_end`
+    /// and the new `session_start` are recorded before the next event.
+    pub fn session_timeout(mut self, seconds: u64) -> Self {
+        self.session_timeout = Some(seconds);
+        self
+    }
+
+    /// Record `app_installed` and `app_updated` events (disabled by default)
+    ///
+    /// `app_installed` is recorded on the first build with a new database, and
+    /// `app_updated`, with `from_version` and `to_version`, on the first build after
+    /// the service version changed. The last version seen is kept in the database.
+    pub fn lifecycle_tracking(mut self, enabled: bool) -> Self {
+        self.lifecycle_tracking = enabled;
+        self
+    }
+
+    /// Add a processor that can enrich, rewrite or drop events before they are stored
+    ///
+    /// May be called any number of times; processors run in the order they were added,
+    /// followed by the built-in privacy sanitizer. See [`crate::processor`].
+    pub fn processor(mut self, processor: impl EventProcessor + 'static) -> Self {
+        self.processors.push(Arc::new(processor));
+        self
+    }
+
+    /// Choose which environment details are attached to events
+    ///
+    /// Everything is collected by default; the OS family is always recorded. See
+    /// [`crate::environment`].
+    pub fn environment(mut self, config: EnvironmentConfig) -> Self {
+        self.environment = config;
+        self
+    }
+
+    /// Choose how events recorded in CI are handled (default: [`CiPolicy::Track`])
+    ///
+    /// CI runners are usually fresh machines, so each run otherwise counts as a new
+    /// user. The policy applies whether or not CI details are collected.
+    pub fn ci_policy(mut self, policy: CiPolicy) -> Self {
+        self.ci_policy = policy;
+        self
+    }
+
+    /// Cap the number of events kept in the local buffer (default: unbounded)
+    ///
+    /// Applies to synced and unsynced events; synced events are removed first, then
+    /// unsynced events according to the [`buffer_overflow_policy`](Self::buffer_overflow_policy).
+    pub fn max_buffer_events(mut self, max: usize) -> Self {
+        self.buffer_limits.max_events = Some(max);
+        self
+    }
+
+    /// Cap the total size of buffered events in bytes (default: unbounded)
+    ///
+    /// Measured as serialized JSON; the database file itself stays slightly larger.
+    pub fn max_buffer_bytes(mut self, max: u64) -> Self {
+        self.buffer_limits.max_bytes = Some(max);
+        self
+    }
+
+    /// Choose what is dropped when the buffer is full (default: [`OverflowPolicy::DropOldest`])
+    ///
+    /// Dropped events are counted by category and the counts are sent with the next
+    /// synced batch.
+    pub fn buffer_overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.buffer_limits.overflow_policy = policy;
+        self
+    }
+
+    /// Merge identical events recorded within this many seconds into one counted event
+    ///
+    /// Disabled by default. Useful for chatty features; see [`crate::coalesce`].
+    pub fn coalesce_window(mut self, seconds: u64) -> Self {
+        self.coalesce_window = Some(seconds);
+        self
+    }
+
+    /// Add a sampling rule (see [`crate::sampling`])
+    ///
+    /// May be called any number of times; the first rule matching an event decides
+    /// its sample rate.
+    pub fn sampling_rule(mut self, rule: SamplingRule) -> Self {
+        self.sampling_rules.push(rule);
+        self
+    }
+
+    /// Add sampling rules loaded from settings, evaluated after explicit rules
+    pub(crate) fn loaded_sampling_rules(mut self, rules: Vec<SamplingRule>) -> Self {
+        self.loaded_sampling_rules.extend(rules);
+        self
+    }
+
+    /// Set the sample rate for events no rule matches (default: 1.0, keep all)
+    pub fn default_sample_rate(mut self, rate: f64) -> Self {
+        self.default_sample_rate = Some(rate);
+        self
+    }
+
+    /// Set properties attached to every event
+    ///
+    /// May be called any number of times; later values replace earlier ones with the
+    /// same key. Properties can be changed at runtime with
+    /// [`TelemetryKit::set_property`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use telemetry_kit::prelude::*;
+    /// # fn example() -> Result<()> {
+    /// let telemetry = TelemetryKit::builder()
+    ///     .service_name("my-app")?
+    ///     .default_properties([("edition", "community"), ("license_tier", "free")])
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn default_properties<K, V>(mut self, properties: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<serde_json::Value>,
+    {
+        self.default_properties
+            .extend(properties.into_iter().map(|(k, v)| (k.into(), v.into())));
+        self
+    }
+
     /// Enable automatic background syncing (enabled by default)
     #[cfg(feature = "sync")]
     pub fn auto_sync(mut self, enabled: bool) -> Self {
@@ -99,6 +350,20 @@ impl TelemetryBuilder {
         self
     }
 
+    /// Hand the shutdown sync to a detached uploader process so `shutdown()` returns
+    /// without waiting on the network
+    ///
+    /// The program is started as `<program> __upload --db <path>`; use the
+    /// `telemetry-kit` CLI or an application that handles
+    /// [`upload::requested_db_path`](crate::upload::requested_db_path). Applies to
+    /// the auto-sync shutdown sync, when the built-in sync is the only export
+    /// destination.
+    #[cfg(feature = "sync")]
+    pub fn detached_upload(mut self, program: impl Into<PathBuf>) -> Self {
+        self.auto_sync_config.detached_upload = Some(program.into());
+        self
+    }
+
     /// Shorthand for setting sync credentials
     #[cfg(feature = "sync")]
     pub fn with_sync_credentials(
@@ -119,6 +384,16 @@ impl TelemetryBuilder {
         Ok(self)
     }
 
+    /// Export events to an OpenTelemetry collector over OTLP/HTTP
+    ///
+    /// Can be combined with [`sync`](Self::sync) and custom exporters; events are
+    /// only marked as synced once every exporter has accepted them.
+    #[cfg(feature = "otlp")]
+    pub fn otlp(mut self, config: OtlpConfig) -> Self {
+        self.otlp_config = Some(config);
+        self
+    }
+
     /// Configure privacy settings
     #[cfg(feature = "privacy")]
     pub fn privacy(mut self, config: PrivacyConfig) -> Self {
@@ -270,6 +545,27 @@ impl TelemetryBuilder {
 
     /// Build the TelemetryKit instance
     pub fn build(self) -> Result<TelemetryKit> {
+        self.build_kit(false)
+    }
+
+    /// Build a [`TestTelemetry`](crate::testing::TestTelemetry) that keeps events in memory
+    ///
+    /// Nothing is written to disk and auto-sync is disabled. The service name
+    /// defaults to `test-app`.
+    #[cfg(feature = "testing")]
+    pub fn build_test(mut self) -> Result<crate::testing::TestTelemetry> {
+        if self.service_name.is_none() {
+            self = self.service_name("test-app")?;
+        }
+        #[cfg(feature = "sync")]
+        {
+            self.auto_sync_enabled = false;
+        }
+
+        Ok(crate::testing::TestTelemetry::new(self.build_kit(true)?))
+    }
+
+    fn build_kit(self, in_memory: bool) -> Result<TelemetryKit> {
         let service_name = self
             .service_name
             .ok_or_else(|| TelemetryError::missing_field("service_name"))?;
@@ -279,8 +575,10 @@ impl TelemetryBuilder {
             .unwrap_or_else(|| env!("CARGO_PKG_VERSION").to_string());
 
         // Determine database path
-        let db_path = if let Some(path) = self.db_path {
-            path
+        let db_path = if in_memory {
+            None
+        } else if let Some(path) = self.db_path {
+            Some(path)
         } else {
             // Default: ~/.telemetry-kit/<service_name>.db
             let mut path = dirs::home_dir()
@@ -290,21 +588,95 @@ impl TelemetryBuilder {
                 ))?;
             path.push(".telemetry-kit");
             path.push(format!("{}.db", service_name));
-            path
+            Some(path)
         };
 
-        TelemetryKit::new(
+        let metrics_interval = self.metrics_interval.unwrap_or(DEFAULT_METRICS_INTERVAL);
+        if metrics_interval == 0 {
+            return Err(TelemetryError::invalid_config(
+                "metrics_interval",
+                "Metrics interval must be at least 1 second",
+            ));
+        }
+
+        if self.session_timeout == Some(0) {
+            return Err(TelemetryError::invalid_config(
+                "session_timeout",
+                "Session timeout must be at least 1 second",
+            ));
+        }
+
+        if self.coalesce_window == Some(0) {
+            return Err(TelemetryError::invalid_config(
+                "coalesce_window",
+                "Coalesce window must be at least 1 second",
+            ));
+        }
+
+        if self.buffer_limits.max_events == Some(0) || self.buffer_limits.max_bytes == Some(0) {
+            return Err(TelemetryError::invalid_config(
+                "max_buffer",
+                "Buffer limits must allow at least one event",
+            ));
+        }
+
+        let mut sampling_rules = self.sampling_rules;
+        sampling_rules.extend(self.loaded_sampling_rules);
+        let sampler = Sampler::new(sampling_rules, self.default_sample_rate.unwrap_or(1.0))?;
+        let capture_panics = self.capture_panics;
+        let include_panic_messages = self.include_panic_messages;
+
+        let telemetry = TelemetryKit::new(
             service_name,
             service_version,
             db_path,
+            Duration::from_secs(metrics_interval),
+            self.session_tracking,
+            self.session_timeout.map(Duration::from_secs),
+            self.lifecycle_tracking,
+            sampler,
+            self.processors,
+            detect_environment(&self.environment),
+            self.ci_policy,
+            self.default_properties,
+            self.buffer_limits,
+            self.coalesce_window.map(Duration::from_secs),
             #[cfg(feature = "sync")]
             self.sync_config,
             #[cfg(feature = "sync")]
+            self.exporters,
+            #[cfg(feature = "sync")]
             self.auto_sync_enabled,
             #[cfg(feature = "sync")]
             self.auto_sync_config,
             #[cfg(feature = "privacy")]
             self.privacy_config,
+            #[cfg(feature = "otlp")]
+            self.otlp_config,
+        )?;
+
+        if capture_panics {
+            telemetry.install_panic_hook(include_panic_messages);
+        }
+
+        Ok(telemetry)
+    }
+
+    /// Build a [`blocking::TelemetryKit`] for applications without a tokio runtime
+    ///
+    /// Auto-sync and metrics flushes run on a background thread instead of tokio tasks.
+    #[cfg(feature = "blocking")]
+    pub fn build_blocking(mut self) -> Result<blocking::TelemetryKit> {
+        let auto_sync_enabled = std::mem::take(&mut self.auto_sync_enabled);
+        let auto_sync_config = self.auto_sync_config.clone();
+        let metrics_interval =
+            Duration::from_secs(self.metrics_interval.unwrap_or(DEFAULT_METRICS_INTERVAL));
+
+        blocking::TelemetryKit::new(
+            self.build()?,
+            auto_sync_enabled,
+            auto_sync_config,
+            metrics_interval,
         )
     }
 }
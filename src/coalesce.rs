@@ -0,0 +1,242 @@
+//! Coalescing of repeated identical events into counted rollups
+//!
+//! Chatty events, such as a feature used hundreds of times per session, can be merged
+//! into one event per window with
+//! [`TelemetryBuilder::coalesce_window`](crate::TelemetryBuilder::coalesce_window).
+//! Events are identical when everything but their ID, timestamp and `duration_ms`
+//! matches, including the session and properties. The merged event keeps the first
+//! event's ID and timestamp, drops `duration_ms` from its data and carries a
+//! [`Rollup`] with the count, the last timestamp and the summed duration. An event
+//! that was not repeated within its window is stored unchanged.
+//!
+//! Events are held in memory until their window closes and are written by the next
+//! event or auto-sync after that, by `sync()` and by `shutdown()`. Events held by a
+//! process that exits without `shutdown()` are lost. Spans are never coalesced, as
+//! nested spans refer to their parent's event ID.
+
+use crate::event::{Event, Rollup};
+use chrono::{DateTime, Utc};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Events held back for merging, by what makes them identical
+#[derive(Debug)]
+pub(crate) struct Coalescer {
+    window: Duration,
+    pending: Mutex<HashMap<String, Pending>>,
+}
+
+/// First event of a window and what was merged into it
+#[derive(Debug)]
+struct Pending {
+    event: Event,
+    opened: Instant,
+    count: u64,
+    last_timestamp: DateTime<Utc>,
+    total_duration_ms: Option<u64>,
+}
+
+impl Coalescer {
+    pub(crate) fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Hold back or merge new events, returning the events to store now
+    ///
+    /// These are events that cannot be coalesced and rollups whose window closed.
+    pub(crate) fn add(&self, events: Vec<Event>) -> Vec<Event> {
+        let Ok(mut pending) = self.pending.lock() else {
+            return events;
+        };
+
+        let mut ready = take(&mut pending, |p| p.opened.elapsed() >= self.window);
+        for event in events {
+            let Some(key) = coalesce_key(&event) else {
+                ready.push(event);
+                continue;
+            };
+
+            match pending.entry(key) {
+                Entry::Occupied(mut entry) => entry.get_mut().merge(&event),
+                Entry::Vacant(entry) => {
+                    entry.insert(Pending::new(event));
+                }
+            }
+        }
+
+        ready
+    }
+
+    /// Take the rollups whose window closed
+    #[cfg_attr(not(feature = "sync"), allow(dead_code))]
+    pub(crate) fn take_closed(&self) -> Vec<Event> {
+        match self.pending.lock() {
+            Ok(mut pending) => take(&mut pending, |p| p.opened.elapsed() >= self.window),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Take every held event, closing open windows early
+    pub(crate) fn take_all(&self) -> Vec<Event> {
+        match self.pending.lock() {
+            Ok(mut pending) => take(&mut pending, |_| true),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+impl Pending {
+    fn new(event: Event) -> Self {
+        Self {
+            opened: Instant::now(),
+            count: 1,
+            last_timestamp: event.timestamp,
+            total_duration_ms: duration_ms(&event),
+            event,
+        }
+    }
+
+    fn merge(&mut self, event: &Event) {
+        self.count += 1;
+        self.last_timestamp = self.last_timestamp.max(event.timestamp);
+        if let Some(duration_ms) = duration_ms(event) {
+            self.total_duration_ms = Some(self.total_duration_ms.unwrap_or(0) + duration_ms);
+        }
+    }
+
+    fn into_event(self) -> Event {
+        let mut event = self.event;
+        if self.count > 1 {
+            if let Some(data) = event.event.data.as_object_mut() {
+                data.remove("duration_ms");
+            }
+            event.event.rollup = Some(Rollup {
+                count: self.count,
+                first_timestamp: event.timestamp,
+                last_timestamp: self.last_timestamp,
+                total_duration_ms: self.total_duration_ms,
+            });
+        }
+        event
+    }
+}
+
+/// Remove matching entries, returning their events in timestamp order
+fn take(pending: &mut HashMap<String, Pending>, closed: impl Fn(&Pending) -> bool) -> Vec<Event> {
+    let (taken, kept): (HashMap<_, _>, HashMap<_, _>) = std::mem::take(pending)
+        .into_iter()
+        .partition(|(_, p)| closed(p));
+    *pending = kept;
+
+    let mut events: Vec<Event> = taken.into_values().map(Pending::into_event).collect();
+    events.sort_by_key(|event| event.timestamp);
+    events
+}
+
+/// Everything that must match for two events to be merged, or `None` for spans
+fn coalesce_key(event: &Event) -> Option<String> {
+    if event.event.event_type == "span" || event.event.rollup.is_some() {
+        return None;
+    }
+
+    let mut data = event.event.data.clone();
+    if let Some(data) = data.as_object_mut() {
+        data.remove("duration_ms");
+    }
+
+    serde_json::to_string(&(
+        &event.session_id,
+        &event.event.event_type,
+        &event.event.category,
+        &data,
+        &event.event.context,
+        &event.event.sample_rate,
+        &event.event.experiments,
+        &event.event.parent_event_id,
+    ))
+    .ok()
+}
+
+fn duration_ms(event: &Event) -> Option<u64> {
+    event.event.data.get("duration_ms")?.as_u64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::*;
+
+    fn feature_event(feature: &str, duration_ms: u64) -> Event {
+        let mut event = Event::test_event(
+            "feature_used",
+            serde_json::json!({ "feature": feature, "duration_ms": duration_ms }),
+        );
+        event.event.category = Some("library".to_string());
+        event
+    }
+
+    #[test]
+    fn test_identical_events_are_merged() {
+        let coalescer = Coalescer::new(Duration::from_secs(60));
+        let events: Vec<Event> = (1..=100)
+            .map(|i| feature_event("autocomplete", i))
+            .collect();
+        let first = events[0].clone();
+
+        assert!(coalescer.add(events).is_empty());
+        assert!(coalescer.take_closed().is_empty());
+
+        let merged = coalescer.take_all();
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].event_id, first.event_id);
+        assert!(merged[0].event.data.get("duration_ms").is_none());
+
+        let rollup = merged[0].event.rollup.as_ref().unwrap();
+        assert_eq!(rollup.count, 100);
+        assert_eq!(rollup.first_timestamp, first.timestamp);
+        assert_eq!(rollup.total_duration_ms, Some(5050));
+    }
+
+    #[test]
+    fn test_different_events_are_kept_apart() {
+        let coalescer = Coalescer::new(Duration::from_secs(60));
+        let mut in_other_session = feature_event("search", 1);
+        in_other_session.session_id = Some("sess_other".to_string());
+
+        coalescer.add(vec![
+            feature_event("search", 1),
+            feature_event("export", 1),
+            in_other_session,
+        ]);
+
+        let events = coalescer.take_all();
+        assert_eq!(events.len(), 3);
+        // Events that were not repeated are stored unchanged
+        assert!(events.iter().all(|e| e.event.rollup.is_none()));
+        assert!(events.iter().all(|e| e.event.data["duration_ms"] == 1));
+    }
+
+    #[test]
+    fn test_closed_windows_are_released() {
+        let coalescer = Coalescer::new(Duration::ZERO);
+
+        assert!(coalescer.add(vec![feature_event("search", 1)]).is_empty());
+        let released = coalescer.add(vec![feature_event("search", 1)]);
+        assert_eq!(released.len(), 1);
+        assert_eq!(coalescer.take_closed().len(), 1);
+    }
+
+    #[test]
+    fn test_spans_are_not_coalesced() {
+        let coalescer = Coalescer::new(Duration::from_secs(60));
+        let mut span = feature_event("search", 1);
+        span.event.event_type = "span".to_string();
+
+        assert_eq!(coalescer.add(vec![span.clone(), span]).len(), 2);
+    }
+}
@@ -0,0 +1,669 @@
+//! Configuration files and environment variables
+//!
+//! Applications can keep their telemetry settings in a `telemetry-kit.toml` file,
+//! which the `telemetry-kit` CLI creates with `init` and reads in `validate` and
+//! `sync`. Loading files requires the `config` feature.
+//!
+//! ```toml
+//! [service]
+//! name = "my-app"
+//! version = "1.2.0"
+//!
+//! [sync]
+//! org_id = "550e8400-e29b-41d4-a716-446655440000"
+//! app_id = "7c9e6679-7425-40de-944b-e07fc1f90ae7"
+//! # Credentials are never written to the file, only the variables holding them
+//! token_env = "TK_TOKEN"
+//! secret_env = "TK_SECRET"
+//!
+//! [auto_sync]
+//! enabled = true
+//! interval = 300
+//!
+//! [privacy]
+//! mode = "strict"
+//! data_retention_days = 30
+//!
+//! [sampling]
+//! default_rate = 1.0
+//!
+//! [[sampling.rules]]
+//! event_type = "feature_used"
+//! name = "autocomplete"
+//! rate = 0.1
+//! ```
+//!
+//! The same settings can come from `TK_*` environment variables, see
+//! [`TelemetryConfig::from_env`]. Builder calls made after
+//! [`TelemetryBuilder::from_config_file`] or [`TelemetryBuilder::from_env`] override
+//! the loaded values, and sampling rules added with
+//! [`TelemetryBuilder::sampling_rule`] are evaluated before the loaded rules.
+
+use crate::builder::TelemetryBuilder;
+use crate::error::{Result, TelemetryError};
+use crate::sampling::{Sampler, SamplingRule};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[cfg(feature = "config")]
+use std::path::Path;
+
+#[cfg(feature = "privacy")]
+use crate::privacy::PrivacyConfig;
+
+/// Default configuration file name, looked up in the working directory by the CLI
+pub const CONFIG_FILE_NAME: &str = "telemetry-kit.toml";
+
+/// Default environment variable holding the sync token
+pub const DEFAULT_TOKEN_ENV: &str = "TK_TOKEN";
+
+/// Default environment variable holding the sync HMAC secret
+pub const DEFAULT_SECRET_ENV: &str = "TK_SECRET";
+
+/// Contents of a `telemetry-kit.toml` file
+///
+/// Every setting is optional; unset settings keep the builder's defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TelemetryConfig {
+    /// Service identity and local storage
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub service: ServiceSettings,
+
+    /// Sync destination and where to find its credentials
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync: Option<SyncSettings>,
+
+    /// Background syncing
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub auto_sync: AutoSyncSettings,
+
+    /// Privacy controls
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub privacy: Option<PrivacySettings>,
+
+    /// Sample rates
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub sampling: SamplingSettings,
+}
+
+/// `[service]` section
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServiceSettings {
+    /// Service name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Service version
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+
+    /// Database path (default: `~/.telemetry-kit/<name>.db`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub db_path: Option<PathBuf>,
+}
+
+/// `[sync]` section
+///
+/// The token and secret are read from the named environment variables when the
+/// configuration is applied, so the file can be committed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SyncSettings {
+    /// Organization ID
+    pub org_id: String,
+
+    /// Application ID
+    pub app_id: String,
+
+    /// API endpoint base URL (default: the hosted service)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+
+    /// Environment variable holding the API token
+    #[serde(default = "default_token_env")]
+    pub token_env: String,
+
+    /// Environment variable holding the HMAC secret
+    #[serde(default = "default_secret_env")]
+    pub secret_env: String,
+
+    /// Events per sync request (default: 100)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_size: Option<usize>,
+
+    /// Maximum retry attempts per request (default: 3)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u32>,
+
+    /// Whether to skip syncing when Do Not Track is set (default: true)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub respect_dnt: Option<bool>,
+}
+
+impl SyncSettings {
+    /// Sync settings reading credentials from `TK_TOKEN` and `TK_SECRET`
+    pub fn new(org_id: impl Into<String>, app_id: impl Into<String>) -> Self {
+        Self {
+            org_id: org_id.into(),
+            app_id: app_id.into(),
+            endpoint: None,
+            token_env: default_token_env(),
+            secret_env: default_secret_env(),
+            batch_size: None,
+            max_retries: None,
+            respect_dnt: None,
+        }
+    }
+}
+
+/// `[auto_sync]` section
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AutoSyncSettings {
+    /// Whether to sync in the background
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+
+    /// Sync interval in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interval: Option<u64>,
+
+    /// Whether to sync on shutdown
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_shutdown: Option<bool>,
+}
+
+/// Privacy preset a `[privacy]` section starts from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PrivacyMode {
+    /// The default privacy configuration
+    #[default]
+    Default,
+    /// GDPR-compliant settings, see `PrivacyConfig::strict`
+    Strict,
+    /// Minimal settings, see `PrivacyConfig::minimal`
+    Minimal,
+}
+
+/// `[privacy]` section
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PrivacySettings {
+    /// Preset the other settings override
+    #[serde(default)]
+    pub mode: PrivacyMode,
+
+    /// Require user consent before tracking
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub consent_required: Option<bool>,
+
+    /// Data retention period in days (0 = forever)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_retention_days: Option<u32>,
+
+    /// Remove usernames from paths
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sanitize_paths: Option<bool>,
+
+    /// Redact email addresses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sanitize_emails: Option<bool>,
+}
+
+/// `[sampling]` section
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SamplingSettings {
+    /// Sample rate for events no rule matches
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_rate: Option<f64>,
+
+    /// Sampling rules, evaluated in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<SamplingRuleSettings>,
+}
+
+/// `[[sampling.rules]]` entry, matching either an event type or a category
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SamplingRuleSettings {
+    /// Event type to match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_type: Option<String>,
+
+    /// Category to match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+
+    /// Command, feature or span name to match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Fraction of installations whose matching events are kept
+    pub rate: f64,
+}
+
+impl SamplingRuleSettings {
+    fn to_rule(&self) -> Result<SamplingRule> {
+        let rule = match (&self.event_type, &self.category) {
+            (Some(event_type), None) => SamplingRule::event_type(event_type),
+            (None, Some(category)) => SamplingRule::category(category),
+            _ => {
+                return Err(TelemetryError::invalid_config(
+                    "sampling.rules",
+                    "Each rule must set exactly one of event_type or category",
+                ))
+            }
+        };
+
+        let rule = match &self.name {
+            Some(name) => rule.name(name),
+            None => rule,
+        };
+        Ok(rule.rate(self.rate))
+    }
+}
+
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+fn default_token_env() -> String {
+    DEFAULT_TOKEN_ENV.to_string()
+}
+
+fn default_secret_env() -> String {
+    DEFAULT_SECRET_ENV.to_string()
+}
+
+impl TelemetryConfig {
+    /// Read a configuration file
+    #[cfg(feature = "config")]
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            TelemetryError::invalid_config(
+                "config_file",
+                &format!("Cannot read {}: {}", path.display(), e),
+            )
+        })?;
+        Self::from_toml(&contents).map_err(|e| match e {
+            TelemetryError::InvalidConfig(reason) => TelemetryError::InvalidConfig(format!(
+                "{} in {}",
+                reason.trim_end(),
+                path.display()
+            )),
+            e => e,
+        })
+    }
+
+    /// Parse the contents of a configuration file
+    #[cfg(feature = "config")]
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        toml::from_str(contents)
+            .map_err(|e| TelemetryError::invalid_config("config_file", e.message()))
+    }
+
+    /// Render as the contents of a configuration file
+    #[cfg(feature = "config")]
+    pub fn to_toml(&self) -> Result<String> {
+        toml::to_string_pretty(self)
+            .map_err(|e| TelemetryError::invalid_config("config_file", &e.to_string()))
+    }
+
+    /// Write a configuration file, replacing any existing one
+    #[cfg(feature = "config")]
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_toml()?).map_err(|e| {
+            TelemetryError::invalid_config(
+                "config_file",
+                &format!("Cannot write {}: {}", path.display(), e),
+            )
+        })
+    }
+
+    /// Read settings from `TK_*` environment variables
+    ///
+    /// | Variable | Setting |
+    /// |----------|---------|
+    /// | `TK_SERVICE_NAME`, `TK_SERVICE_VERSION`, `TK_DB_PATH` | `[service]` |
+    /// | `TK_ORG_ID`, `TK_APP_ID`, `TK_ENDPOINT` | `[sync]`, enabled when both IDs are set |
+    /// | `TK_BATCH_SIZE`, `TK_MAX_RETRIES`, `TK_RESPECT_DNT` | `[sync]` |
+    /// | `TK_TOKEN`, `TK_SECRET` | sync credentials |
+    /// | `TK_AUTO_SYNC`, `TK_SYNC_INTERVAL`, `TK_SYNC_ON_SHUTDOWN` | `[auto_sync]` |
+    /// | `TK_PRIVACY_MODE`, `TK_CONSENT_REQUIRED`, `TK_DATA_RETENTION_DAYS` | `[privacy]` |
+    /// | `TK_SAMPLE_RATE` | `[sampling] default_rate` |
+    ///
+    /// Empty variables are ignored.
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let var = |name: &str| var(name).filter(|value| !value.is_empty());
+
+        let sync = match (var("TK_ORG_ID"), var("TK_APP_ID")) {
+            (Some(org_id), Some(app_id)) => Some(SyncSettings {
+                endpoint: var("TK_ENDPOINT"),
+                batch_size: parse_opt(&var, "TK_BATCH_SIZE")?,
+                max_retries: parse_opt(&var, "TK_MAX_RETRIES")?,
+                respect_dnt: parse_opt(&var, "TK_RESPECT_DNT")?,
+                ..SyncSettings::new(org_id, app_id)
+            }),
+            _ => None,
+        };
+
+        let privacy = PrivacySettings {
+            mode: match var("TK_PRIVACY_MODE") {
+                Some(mode) => parse_var("TK_PRIVACY_MODE", &format!("\"{}\"", mode))?,
+                None => PrivacyMode::Default,
+            },
+            consent_required: parse_opt(&var, "TK_CONSENT_REQUIRED")?,
+            data_retention_days: parse_opt(&var, "TK_DATA_RETENTION_DAYS")?,
+            sanitize_paths: None,
+            sanitize_emails: None,
+        };
+
+        Ok(Self {
+            service: ServiceSettings {
+                name: var("TK_SERVICE_NAME"),
+                version: var("TK_SERVICE_VERSION"),
+                db_path: var("TK_DB_PATH").map(PathBuf::from),
+            },
+            sync,
+            auto_sync: AutoSyncSettings {
+                enabled: parse_opt(&var, "TK_AUTO_SYNC")?,
+                interval: parse_opt(&var, "TK_SYNC_INTERVAL")?,
+                on_shutdown: parse_opt(&var, "TK_SYNC_ON_SHUTDOWN")?,
+            },
+            privacy: (privacy != PrivacySettings::default()).then_some(privacy),
+            sampling: SamplingSettings {
+                default_rate: parse_opt(&var, "TK_SAMPLE_RATE")?,
+                rules: Vec::new(),
+            },
+        })
+    }
+
+    /// Apply these settings to a builder, replacing what they set
+    pub(crate) fn apply(&self, mut builder: TelemetryBuilder) -> Result<TelemetryBuilder> {
+        if let Some(name) = &self.service.name {
+            builder = builder.service_name(name)?;
+        }
+        if let Some(version) = &self.service.version {
+            builder = builder.service_version(version);
+        }
+        if let Some(db_path) = &self.service.db_path {
+            builder = builder.db_path(db_path);
+        }
+
+        #[cfg(feature = "sync")]
+        {
+            if let Some(sync) = &self.sync {
+                builder = builder.sync(sync.to_sync_config()?);
+            }
+            if let Some(enabled) = self.auto_sync.enabled {
+                builder = builder.auto_sync(enabled);
+            }
+            if let Some(interval) = self.auto_sync.interval {
+                builder = builder.sync_interval(interval);
+            }
+            if let Some(on_shutdown) = self.auto_sync.on_shutdown {
+                builder = builder.sync_on_shutdown(on_shutdown);
+            }
+        }
+
+        #[cfg(feature = "privacy")]
+        if let Some(privacy) = &self.privacy {
+            builder = builder.privacy(privacy.to_privacy_config());
+        }
+
+        if let Some(rate) = self.sampling.default_rate {
+            builder = builder.default_sample_rate(rate);
+        }
+        let rules = self
+            .sampling
+            .rules
+            .iter()
+            .map(SamplingRuleSettings::to_rule)
+            .collect::<Result<Vec<_>>>()?;
+        Sampler::new(rules.clone(), self.sampling.default_rate.unwrap_or(1.0))?;
+
+        Ok(builder.loaded_sampling_rules(rules))
+    }
+}
+
+#[cfg(feature = "sync")]
+impl SyncSettings {
+    /// Build the sync configuration, reading the credentials from the environment
+    pub fn to_sync_config(&self) -> Result<crate::sync::SyncConfig> {
+        let credential = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| {
+                    TelemetryError::invalid_config(
+                        "sync",
+                        &format!("Environment variable {} is not set", name),
+                    )
+                })
+        };
+
+        let mut builder = crate::sync::SyncConfig::builder()
+            .org_id(&self.org_id)?
+            .app_id(&self.app_id)?
+            .token(credential(&self.token_env)?)
+            .secret(credential(&self.secret_env)?);
+        if let Some(endpoint) = &self.endpoint {
+            builder = builder.endpoint(endpoint);
+        }
+        if let Some(batch_size) = self.batch_size {
+            builder = builder.batch_size(batch_size);
+        }
+        if let Some(max_retries) = self.max_retries {
+            builder = builder.max_retries(max_retries);
+        }
+        if let Some(respect_dnt) = self.respect_dnt {
+            builder = builder.respect_dnt(respect_dnt);
+        }
+        builder.build()
+    }
+}
+
+#[cfg(feature = "privacy")]
+impl PrivacySettings {
+    /// The privacy configuration these settings describe
+    pub fn to_privacy_config(&self) -> PrivacyConfig {
+        let preset = match self.mode {
+            PrivacyMode::Default => PrivacyConfig::default(),
+            PrivacyMode::Strict => PrivacyConfig::strict(),
+            PrivacyMode::Minimal => PrivacyConfig::minimal(),
+        };
+
+        PrivacyConfig {
+            consent_required: self.consent_required.unwrap_or(preset.consent_required),
+            data_retention_days: self
+                .data_retention_days
+                .unwrap_or(preset.data_retention_days),
+            sanitize_paths: self.sanitize_paths.unwrap_or(preset.sanitize_paths),
+            sanitize_emails: self.sanitize_emails.unwrap_or(preset.sanitize_emails),
+            ..preset
+        }
+    }
+}
+
+/// Parse an optional variable with the same rules as file values
+fn parse_opt<T: serde::de::DeserializeOwned>(
+    var: &impl Fn(&str) -> Option<String>,
+    name: &str,
+) -> Result<Option<T>> {
+    var(name).map(|value| parse_var(name, &value)).transpose()
+}
+
+fn parse_var<T: serde::de::DeserializeOwned>(name: &str, value: &str) -> Result<T> {
+    serde_json::from_str(value).map_err(|_| {
+        TelemetryError::invalid_config(name, &format!("'{}' is not a valid value", value))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn vars(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn test_from_vars() {
+        let config = TelemetryConfig::from_vars(vars(&[
+            ("TK_SERVICE_NAME", "my-app"),
+            ("TK_ORG_ID", "org"),
+            ("TK_APP_ID", "app"),
+            ("TK_AUTO_SYNC", "false"),
+            ("TK_SYNC_INTERVAL", "300"),
+            ("TK_PRIVACY_MODE", "strict"),
+            ("TK_SAMPLE_RATE", "0.5"),
+            ("TK_DB_PATH", ""),
+            ("TK_BATCH_SIZE", "25"),
+            ("TK_RESPECT_DNT", "false"),
+        ]))
+        .unwrap();
+
+        assert_eq!(config.service.name.as_deref(), Some("my-app"));
+        assert_eq!(config.service.db_path, None);
+        assert_eq!(
+            config.sync,
+            Some(SyncSettings {
+                batch_size: Some(25),
+                respect_dnt: Some(false),
+                ..SyncSettings::new("org", "app")
+            })
+        );
+        assert_eq!(config.auto_sync.enabled, Some(false));
+        assert_eq!(config.auto_sync.interval, Some(300));
+        assert_eq!(config.privacy.unwrap().mode, PrivacyMode::Strict);
+        assert_eq!(config.sampling.default_rate, Some(0.5));
+    }
+
+    #[test]
+    fn test_from_vars_invalid_value() {
+        let err = TelemetryConfig::from_vars(vars(&[("TK_SYNC_INTERVAL", "soon")])).unwrap_err();
+        assert!(err.to_string().contains("TK_SYNC_INTERVAL"));
+
+        assert!(TelemetryConfig::from_vars(vars(&[("TK_PRIVACY_MODE", "lax")])).is_err());
+    }
+
+    #[test]
+    fn test_sampling_rule_needs_one_matcher() {
+        let rule = SamplingRuleSettings {
+            event_type: Some("feature_used".to_string()),
+            category: Some("error".to_string()),
+            name: None,
+            rate: 0.5,
+        };
+        assert!(rule.to_rule().is_err());
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_toml_round_trip() {
+        let config = TelemetryConfig::from_toml(
+            r#"
+            [service]
+            name = "my-app"
+
+            [sync]
+            org_id = "org"
+            app_id = "app"
+            secret_env = "MY_SECRET"
+
+            [privacy]
+            mode = "minimal"
+            consent_required = true
+
+            [[sampling.rules]]
+            event_type = "feature_used"
+            name = "autocomplete"
+            rate = 0.1
+            "#,
+        )
+        .unwrap();
+
+        let sync = config.sync.as_ref().unwrap();
+        assert_eq!(sync.token_env, DEFAULT_TOKEN_ENV);
+        assert_eq!(sync.secret_env, "MY_SECRET");
+        assert_eq!(config.sampling.rules.len(), 1);
+
+        let rendered = config.to_toml().unwrap();
+        assert_eq!(TelemetryConfig::from_toml(&rendered).unwrap(), config);
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_unknown_keys_are_rejected() {
+        let err = TelemetryConfig::from_toml("[service]\nnmae = \"typo\"\n").unwrap_err();
+        assert!(err.to_string().contains("nmae"));
+    }
+
+    #[cfg(feature = "config")]
+    #[tokio::test]
+    async fn test_explicit_calls_override_file() {
+        use uuid::Uuid;
+        let unique_name = format!("test-config-{}", Uuid::new_v4());
+        let path = std::env::temp_dir().join(format!("{}.toml", unique_name));
+        std::fs::write(
+            &path,
+            r#"
+            [service]
+            name = "from-file"
+
+            [sampling]
+            default_rate = 0.0
+
+            [[sampling.rules]]
+            event_type = "feature_used"
+            rate = 0.0
+            "#,
+        )
+        .unwrap();
+
+        let telemetry = TelemetryBuilder::from_config_file(&path)
+            .unwrap()
+            .service_name(&unique_name)
+            .unwrap()
+            .db_path(std::env::temp_dir().join(format!("{}.db", unique_name)))
+            .sampling_rule(SamplingRule::event_type("feature_used"))
+            .build()
+            .unwrap();
+
+        telemetry.track_feature("search", |e| e).await.unwrap();
+        telemetry.track_command("build", |e| e).await.unwrap();
+
+        // The explicit rule wins over the file's rule; the file's default rate applies
+        let stats = telemetry.stats().await.unwrap();
+        assert_eq!(stats.total_events, 1);
+    }
+
+    #[cfg(feature = "privacy")]
+    #[test]
+    fn test_privacy_settings_override_preset() {
+        let privacy = PrivacySettings {
+            mode: PrivacyMode::Strict,
+            data_retention_days: Some(7),
+            ..Default::default()
+        }
+        .to_privacy_config();
+
+        assert!(privacy.consent_required);
+        assert_eq!(privacy.data_retention_days, 7);
+    }
+}
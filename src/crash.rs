@@ -0,0 +1,207 @@
+//! Panic hook that records crashes as telemetry events
+//!
+//! Enabled with [`TelemetryBuilder::capture_panics`](crate::TelemetryBuilder::capture_panics).
+//! The crash event is written to the local buffer before the panic continues, so it
+//! is synced on the next run even if the process aborts.
+//!
+//! File paths have the home directory replaced with `~`, and panic messages are only
+//! recorded when enabled with
+//! [`TelemetryBuilder::include_panic_messages`](crate::TelemetryBuilder::include_panic_messages).
+//! Both require the `privacy` feature; without it neither is recorded.
+
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::panic::Location;
+
+/// Maximum number of backtrace frames recorded per crash
+const MAX_FRAMES: usize = 50;
+
+/// Install a panic hook that passes crash data to `record` and then runs the
+/// previously installed hook
+pub(crate) fn install_panic_hook(
+    include_message: bool,
+    record: impl Fn(serde_json::Value) + Send + Sync + 'static,
+) {
+    let previous = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info| {
+        let backtrace = Backtrace::force_capture();
+        record(crash_data(
+            info.payload(),
+            info.location(),
+            &backtrace.to_string(),
+            include_message,
+        ));
+        previous(info);
+    }));
+}
+
+/// Build the data of a `crash` event
+///
+/// The panic payload is free text that may contain user data, so it is only
+/// recorded, sanitized, when `include_message` is set.
+fn crash_data(
+    payload: &(dyn Any + Send),
+    location: Option<&Location<'_>>,
+    backtrace: &str,
+    include_message: bool,
+) -> serde_json::Value {
+    let location = location.map(|l| {
+        serde_json::json!({
+            "file": sanitize_path(l.file()),
+            "line": l.line(),
+            "column": l.column(),
+        })
+    });
+
+    let mut data = serde_json::json!({
+        "location": location,
+        "thread": std::thread::current().name().unwrap_or("<unnamed>"),
+        "backtrace": parse_backtrace(backtrace),
+    });
+
+    if include_message && cfg!(feature = "privacy") {
+        let message = if let Some(message) = payload.downcast_ref::<&str>() {
+            message.to_string()
+        } else if let Some(message) = payload.downcast_ref::<String>() {
+            message.clone()
+        } else {
+            "Box<dyn Any>".to_string()
+        };
+        data["message"] = serde_json::json!(crate::event::sanitize_message(&message));
+    }
+
+    data
+}
+
+/// Split a rendered backtrace into frames, dropping the panic machinery
+///
+/// The standard library renders frames as an index and demangled symbol, optionally
+/// followed by an `at file:line:column` line.
+fn parse_backtrace(backtrace: &str) -> Vec<serde_json::Value> {
+    let mut frames: Vec<(String, Option<String>)> = Vec::new();
+
+    for line in backtrace.lines() {
+        let line = line.trim();
+        if let Some(location) = line.strip_prefix("at ") {
+            if let Some(frame) = frames.last_mut() {
+                frame.1 = sanitize_path(location);
+            }
+        } else if let Some((index, function)) = line.split_once(": ") {
+            if index.chars().all(|c| c.is_ascii_digit()) {
+                frames.push((function.to_string(), None));
+            }
+        }
+    }
+
+    // Frames up to and including the panic entry point belong to the hook and the
+    // standard library
+    let start = frames
+        .iter()
+        .rposition(|(function, _)| is_panic_entry(function))
+        .map(|i| i + 1)
+        .unwrap_or(0);
+
+    frames
+        .into_iter()
+        .skip(start)
+        .take(MAX_FRAMES)
+        .map(|(function, location)| {
+            serde_json::json!({
+                "function": function,
+                "location": location,
+            })
+        })
+        .collect()
+}
+
+/// Check if a frame is part of how the standard library starts a panic
+///
+/// These frames only appear between the panicking code and the hook, never further
+/// down the stack, unlike e.g. `std::panicking::catch_unwind`.
+fn is_panic_entry(function: &str) -> bool {
+    const PREFIXES: &[&str] = &[
+        "std::panicking::begin_panic",
+        "std::panicking::panic_handler",
+        "std::panicking::panic_with_hook",
+        "std::panicking::rust_panic_with_hook",
+        "std::sys::backtrace::__rust_end_short_backtrace",
+        "std::rt::begin_panic",
+        "core::panicking::",
+        "rust_begin_unwind",
+        "__rustc::rust_begin_unwind",
+    ];
+
+    PREFIXES.iter().any(|prefix| function.starts_with(prefix))
+}
+
+/// Replace the home directory with `~` so paths do not reveal user names
+#[cfg(feature = "privacy")]
+fn sanitize_path(path: &str) -> Option<String> {
+    Some(crate::privacy::PrivacyManager::sanitize_path(path))
+}
+
+/// Paths are not recorded without the `privacy` feature
+#[cfg(not(feature = "privacy"))]
+fn sanitize_path(_path: &str) -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BACKTRACE: &str = "   0: std::backtrace::Backtrace::force_capture
+             at /rustc/abc/library/std/src/backtrace.rs:312:9
+   1: telemetry_kit::crash::install_panic_hook::{{closure}}
+   2: std::panicking::rust_panic_with_hook
+   3: core::panicking::panic_fmt
+             at /rustc/abc/library/core/src/panicking.rs:75:14
+   4: my_app::run
+             at ./src/main.rs:10:5
+   5: std::panicking::catch_unwind
+   6: main
+";
+
+    #[test]
+    fn test_parse_backtrace_skips_panic_machinery() {
+        let frames = parse_backtrace(BACKTRACE);
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0]["function"], "my_app::run");
+        assert_eq!(frames[0]["location"], "./src/main.rs:10:5");
+        assert_eq!(frames[1]["function"], "std::panicking::catch_unwind");
+        assert_eq!(frames[2]["function"], "main");
+        assert!(frames[2]["location"].is_null());
+    }
+
+    #[test]
+    fn test_crash_data() {
+        let payload: Box<dyn Any + Send> = Box::new("boom");
+        let data = crash_data(payload.as_ref(), Some(Location::caller()), BACKTRACE, false);
+
+        assert!(data.get("message").is_none());
+        assert!(data["location"]["line"].as_u64().unwrap() > 0);
+        assert_eq!(data["backtrace"].as_array().unwrap().len(), 3);
+    }
+
+    #[cfg(feature = "privacy")]
+    #[test]
+    fn test_crash_message_is_sanitized() {
+        let payload: Box<dyn Any + Send> = Box::new("no account for user@example.com".to_string());
+        let data = crash_data(payload.as_ref(), None, BACKTRACE, true);
+
+        let message = data["message"].as_str().unwrap();
+        assert!(!message.contains("user@example.com"));
+        assert!(message.contains("email_"));
+    }
+
+    #[cfg(feature = "privacy")]
+    #[test]
+    fn test_sanitize_path() {
+        if let Some(home) = dirs::home_dir() {
+            let path = home.join("project/src/main.rs");
+            let sanitized = sanitize_path(path.to_str().unwrap()).unwrap();
+            assert!(sanitized.starts_with('~'));
+        }
+    }
+}
@@ -0,0 +1,487 @@
+//! Detection of the environment an application runs in
+//!
+//! Every optional field of [`Environment`] can be turned off with an
+//! [`EnvironmentConfig`], passed to
+//! [`TelemetryBuilder::environment`](crate::TelemetryBuilder::environment).
+//!
+//! Traffic from CI runners can be tagged or suppressed entirely with a [`CiPolicy`],
+//! passed to [`TelemetryBuilder::ci_policy`](crate::TelemetryBuilder::ci_policy).
+//!
+//! # Example
+//!
+//! ```no_run
+//! use telemetry_kit::environment::{CiPolicy, EnvironmentConfig};
+//! use telemetry_kit::prelude::*;
+//!
+//! # fn main() -> telemetry_kit::Result<()> {
+//! let telemetry = TelemetryKit::builder()
+//!     .service_name("my-app")?
+//!     .environment(EnvironmentConfig {
+//!         ssh: false,
+//!         terminal: false,
+//!         ..Default::default()
+//!     })
+//!     .ci_policy(CiPolicy::Suppress)
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::event::Environment;
+use std::io::IsTerminal;
+use std::path::Path;
+
+/// Which environment details are collected
+///
+/// Everything is collected by default. The operating system family is always
+/// recorded.
+#[derive(Debug, Clone)]
+pub struct EnvironmentConfig {
+    /// OS version, e.g. `22.04` (Linux distributions) or `14.2` (macOS)
+    pub os_version: bool,
+
+    /// Linux distribution ID from `/etc/os-release`, e.g. `ubuntu`
+    pub os_distro: bool,
+
+    /// Kernel release, e.g. `6.5.0-14-generic`
+    pub kernel_version: bool,
+
+    /// System architecture
+    pub arch: bool,
+
+    /// Whether running in CI
+    pub ci: bool,
+
+    /// CI provider, e.g. `github_actions` or `gitlab`
+    pub ci_provider: bool,
+
+    /// Image class of the CI runner, e.g. `ubuntu22` or `self-hosted`
+    pub ci_runner_image: bool,
+
+    /// Shell name from `$SHELL`
+    pub shell: bool,
+
+    /// Container runtime: `docker`, `podman`, `kubernetes` or another `container` value
+    pub container: bool,
+
+    /// Whether running under Windows Subsystem for Linux
+    pub wsl: bool,
+
+    /// Whether running in an SSH session
+    pub ssh: bool,
+
+    /// Whether stdout is a terminal
+    pub tty: bool,
+
+    /// Terminal program from `$TERM_PROGRAM` or `$TERM`
+    pub terminal: bool,
+}
+
+impl Default for EnvironmentConfig {
+    fn default() -> Self {
+        Self {
+            os_version: true,
+            os_distro: true,
+            kernel_version: true,
+            arch: true,
+            ci: true,
+            ci_provider: true,
+            ci_runner_image: true,
+            shell: true,
+            container: true,
+            wsl: true,
+            ssh: true,
+            tty: true,
+            terminal: true,
+        }
+    }
+}
+
+impl EnvironmentConfig {
+    /// Only collect the OS family and architecture
+    pub fn minimal() -> Self {
+        Self {
+            os_version: false,
+            os_distro: false,
+            kernel_version: false,
+            arch: true,
+            ci: false,
+            ci_provider: false,
+            ci_runner_image: false,
+            shell: false,
+            container: false,
+            wsl: false,
+            ssh: false,
+            tty: false,
+            terminal: false,
+        }
+    }
+}
+
+/// Detect the environment, collecting only the enabled details
+pub(crate) fn detect_environment(config: &EnvironmentConfig) -> Environment {
+    let os_release = if config.os_version || config.os_distro {
+        std::fs::read_to_string("/etc/os-release")
+            .ok()
+            .map(|content| parse_os_release(&content))
+    } else {
+        None
+    };
+    let kernel_version = kernel_release();
+    let ci_provider = ci_provider();
+
+    Environment {
+        os: std::env::consts::OS.to_string(),
+        os_version: if config.os_version {
+            os_release
+                .as_ref()
+                .and_then(|r| r.version_id.clone())
+                .or_else(macos_version)
+        } else {
+            None
+        },
+        os_distro: if config.os_distro {
+            os_release.and_then(|r| r.id)
+        } else {
+            None
+        },
+        kernel_version: kernel_version.clone().filter(|_| config.kernel_version),
+        arch: config.arch.then(|| std::env::consts::ARCH.to_string()),
+        ci: config.ci.then(is_ci),
+        ci_provider: ci_provider
+            .filter(|_| config.ci_provider)
+            .map(str::to_string),
+        ci_runner_image: if config.ci_runner_image && ci_provider.is_some() {
+            ci_runner_image_from(env_var)
+        } else {
+            None
+        },
+        shell: if config.shell { detect_shell() } else { None },
+        container: if config.container {
+            detect_container()
+        } else {
+            None
+        },
+        wsl: config.wsl.then(|| is_wsl(kernel_version.as_deref())),
+        ssh: config.ssh.then(is_ssh),
+        tty: config.tty.then(|| std::io::stdout().is_terminal()),
+        terminal: if config.terminal {
+            detect_terminal()
+        } else {
+            None
+        },
+    }
+}
+
+/// Fields of `/etc/os-release` that are recorded
+#[derive(Debug, Default)]
+struct OsRelease {
+    id: Option<String>,
+    version_id: Option<String>,
+}
+
+fn parse_os_release(content: &str) -> OsRelease {
+    let mut release = OsRelease::default();
+
+    for line in content.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value
+            .trim()
+            .trim_matches(|c| c == '"' || c == '\'')
+            .to_string();
+        if value.is_empty() {
+            continue;
+        }
+
+        match key.trim() {
+            "ID" => release.id = Some(value),
+            "VERSION_ID" => release.version_id = Some(value),
+            _ => {}
+        }
+    }
+
+    release
+}
+
+/// Kernel release on Linux
+fn kernel_release() -> Option<String> {
+    std::fs::read_to_string("/proc/sys/kernel/osrelease")
+        .ok()
+        .map(|release| release.trim().to_string())
+        .filter(|release| !release.is_empty())
+}
+
+/// Product version on macOS, read from the system version file
+fn macos_version() -> Option<String> {
+    let plist = std::fs::read_to_string("/System/Library/CoreServices/SystemVersion.plist").ok()?;
+    let after_key = plist.split("<key>ProductVersion</key>").nth(1)?;
+    let value = after_key
+        .split("<string>")
+        .nth(1)?
+        .split("</string>")
+        .next()?;
+    Some(value.trim().to_string())
+}
+
+/// Environment variables identifying CI providers, checked in order
+const CI_PROVIDERS: &[(&str, &str)] = &[
+    ("GITHUB_ACTIONS", "github_actions"),
+    ("GITLAB_CI", "gitlab"),
+    ("BUILDKITE", "buildkite"),
+    ("TF_BUILD", "azure_pipelines"),
+    ("JENKINS_URL", "jenkins"),
+    ("JENKINS_HOME", "jenkins"),
+    ("CIRCLECI", "circleci"),
+    ("TEAMCITY_VERSION", "teamcity"),
+    ("BITBUCKET_BUILD_NUMBER", "bitbucket"),
+    ("TRAVIS", "travis"),
+    ("APPVEYOR", "appveyor"),
+    ("CODEBUILD_BUILD_ID", "aws_codebuild"),
+    ("DRONE", "drone"),
+    ("SEMAPHORE", "semaphore"),
+    ("CIRRUS_CI", "cirrus"),
+    ("WOODPECKER", "woodpecker"),
+    ("NETLIFY", "netlify"),
+    ("VERCEL", "vercel"),
+];
+
+/// How events recorded in CI are handled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CiPolicy {
+    /// Record events like anywhere else (default)
+    #[default]
+    Track,
+
+    /// Record events with a `ci_provider` property, even when the CI details of the
+    /// environment are not collected
+    Tag,
+
+    /// Do not record any events when running in CI
+    Suppress,
+}
+
+/// Detect if running in CI
+pub(crate) fn is_ci() -> bool {
+    ci_provider().is_some()
+}
+
+/// Detect the CI provider
+///
+/// Returns `other` for an unknown provider that sets `CI`.
+pub(crate) fn ci_provider() -> Option<&'static str> {
+    ci_provider_from(env_var)
+}
+
+fn ci_provider_from(var: impl Fn(&str) -> Option<String>) -> Option<&'static str> {
+    if let Some((_, provider)) = CI_PROVIDERS.iter().find(|(name, _)| var(name).is_some()) {
+        return Some(provider);
+    }
+
+    match var("CI").as_deref() {
+        Some("false") | Some("0") | None => None,
+        Some(_) => Some("other"),
+    }
+}
+
+/// Image class of the CI runner: the hosted image name, or `self-hosted`
+fn ci_runner_image_from(var: impl Fn(&str) -> Option<String>) -> Option<String> {
+    if let Some(image) = var("ImageOS") {
+        return Some(image.to_lowercase());
+    }
+
+    let self_hosted = var("RUNNER_ENVIRONMENT").as_deref() == Some("self-hosted")
+        || var("AGENT_ISSELFHOSTED").as_deref() == Some("1");
+    self_hosted.then(|| "self-hosted".to_string())
+}
+
+/// Read a non-empty environment variable
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+/// Detect shell
+fn detect_shell() -> Option<String> {
+    std::env::var("SHELL").ok().and_then(|s| {
+        std::path::Path::new(&s)
+            .file_name()
+            .map(|f| f.to_string_lossy().to_string())
+    })
+}
+
+/// Detect the container runtime, if any
+fn detect_container() -> Option<String> {
+    let cgroup = std::fs::read_to_string("/proc/1/cgroup").unwrap_or_default();
+
+    container_runtime(
+        std::env::var("KUBERNETES_SERVICE_HOST").is_ok(),
+        std::env::var("container").ok().as_deref(),
+        Path::new("/.dockerenv").exists(),
+        Path::new("/run/.containerenv").exists(),
+        &cgroup,
+    )
+}
+
+fn container_runtime(
+    kubernetes_env: bool,
+    container_env: Option<&str>,
+    dockerenv: bool,
+    containerenv: bool,
+    cgroup: &str,
+) -> Option<String> {
+    let runtime = if kubernetes_env || cgroup.contains("kubepods") {
+        "kubernetes"
+    } else if containerenv || container_env == Some("podman") {
+        "podman"
+    } else if dockerenv || cgroup.contains("docker") {
+        "docker"
+    } else {
+        container_env.filter(|c| !c.is_empty())?
+    };
+
+    Some(runtime.to_string())
+}
+
+/// Detect Windows Subsystem for Linux from the kernel release or WSL variables
+fn is_wsl(kernel_release: Option<&str>) -> bool {
+    std::env::var("WSL_DISTRO_NAME").is_ok()
+        || kernel_release.is_some_and(|r| r.to_lowercase().contains("microsoft"))
+}
+
+/// Detect an SSH session
+fn is_ssh() -> bool {
+    std::env::var("SSH_CONNECTION").is_ok()
+        || std::env::var("SSH_CLIENT").is_ok()
+        || std::env::var("SSH_TTY").is_ok()
+}
+
+/// Detect the terminal program
+fn detect_terminal() -> Option<String> {
+    std::env::var("TERM_PROGRAM")
+        .or_else(|_| std::env::var("TERM"))
+        .ok()
+        .filter(|t| !t.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ci_detection() {
+        std::env::remove_var("CI");
+        assert!(!is_ci());
+
+        std::env::set_var("CI", "true");
+        assert!(is_ci());
+
+        std::env::remove_var("CI");
+    }
+
+    fn vars<'a>(vars: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn test_ci_provider() {
+        assert_eq!(ci_provider_from(vars(&[])), None);
+        assert_eq!(ci_provider_from(vars(&[("CI", "false")])), None);
+        assert_eq!(ci_provider_from(vars(&[("CI", "true")])), Some("other"));
+        assert_eq!(
+            ci_provider_from(vars(&[("CI", "true"), ("GITHUB_ACTIONS", "true")])),
+            Some("github_actions")
+        );
+        assert_eq!(
+            ci_provider_from(vars(&[("CI", "true"), ("GITLAB_CI", "true")])),
+            Some("gitlab")
+        );
+        assert_eq!(
+            ci_provider_from(vars(&[("TF_BUILD", "True")])),
+            Some("azure_pipelines")
+        );
+        assert_eq!(
+            ci_provider_from(vars(&[("TEAMCITY_VERSION", "2023.11")])),
+            Some("teamcity")
+        );
+    }
+
+    #[test]
+    fn test_ci_runner_image() {
+        assert_eq!(
+            ci_runner_image_from(vars(&[("ImageOS", "ubuntu22")])).as_deref(),
+            Some("ubuntu22")
+        );
+        assert_eq!(
+            ci_runner_image_from(vars(&[("RUNNER_ENVIRONMENT", "self-hosted")])).as_deref(),
+            Some("self-hosted")
+        );
+        assert_eq!(ci_runner_image_from(vars(&[("GITLAB_CI", "true")])), None);
+    }
+
+    #[test]
+    fn test_parse_os_release() {
+        let release =
+            parse_os_release("NAME=\"Ubuntu\"\nVERSION_ID=\"22.04\"\nID=ubuntu\nID_LIKE=debian\n");
+        assert_eq!(release.id.as_deref(), Some("ubuntu"));
+        assert_eq!(release.version_id.as_deref(), Some("22.04"));
+
+        let rolling = parse_os_release("ID=arch\nBUILD_ID=rolling\n");
+        assert_eq!(rolling.id.as_deref(), Some("arch"));
+        assert!(rolling.version_id.is_none());
+    }
+
+    #[test]
+    fn test_container_runtime() {
+        assert_eq!(container_runtime(false, None, false, false, ""), None);
+        assert_eq!(
+            container_runtime(false, None, true, false, "").as_deref(),
+            Some("docker")
+        );
+        assert_eq!(
+            container_runtime(false, None, false, false, "0::/docker/abc123").as_deref(),
+            Some("docker")
+        );
+        assert_eq!(
+            container_runtime(false, Some("podman"), false, true, "").as_deref(),
+            Some("podman")
+        );
+        assert_eq!(
+            container_runtime(true, None, true, false, "").as_deref(),
+            Some("kubernetes")
+        );
+        assert_eq!(
+            container_runtime(false, Some("systemd-nspawn"), false, false, "").as_deref(),
+            Some("systemd-nspawn")
+        );
+    }
+
+    #[test]
+    fn test_wsl_from_kernel_release() {
+        if std::env::var("WSL_DISTRO_NAME").is_err() {
+            assert!(!is_wsl(Some("6.5.0-14-generic")));
+        }
+        assert!(is_wsl(Some("5.15.133.1-microsoft-standard-WSL2")));
+    }
+
+    #[test]
+    fn test_disabled_fields_are_not_collected() {
+        let environment = detect_environment(&EnvironmentConfig::minimal());
+        assert_eq!(environment.os, std::env::consts::OS);
+        assert!(environment.arch.is_some());
+        assert!(environment.os_version.is_none());
+        assert!(environment.kernel_version.is_none());
+        assert!(environment.ci.is_none());
+        assert!(environment.ci_provider.is_none());
+        assert!(environment.shell.is_none());
+        assert!(environment.container.is_none());
+        assert!(environment.wsl.is_none());
+        assert!(environment.ssh.is_none());
+        assert!(environment.tty.is_none());
+        assert!(environment.terminal.is_none());
+    }
+}
@@ -182,6 +182,25 @@ impl TelemetryError {
         }
     }
 
+    /// Stable name of the error variant, used to classify errors without their messages
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TelemetryError::Database(_) => "Database",
+            #[cfg(feature = "sync")]
+            TelemetryError::Http(_) => "Http",
+            TelemetryError::Json(_) => "Json",
+            TelemetryError::InvalidConfig(_) => "InvalidConfig",
+            TelemetryError::Auth(_) => "Auth",
+            TelemetryError::RateLimitExceeded { .. } => "RateLimitExceeded",
+            TelemetryError::ServerError { .. } => "ServerError",
+            TelemetryError::MaxRetriesExceeded => "MaxRetriesExceeded",
+            TelemetryError::InvalidSchema(_) => "InvalidSchema",
+            TelemetryError::Io(_) => "Io",
+            TelemetryError::MachineId(_) => "MachineId",
+            TelemetryError::Other(_) => "Other",
+        }
+    }
+
     /// Get actionable suggestion based on HTTP status code
     fn server_error_suggestion(status: u16) -> &'static str {
         match status {
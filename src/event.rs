@@ -2,7 +2,7 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use uuid::Uuid;
 
 /// Schema version for events
@@ -58,15 +58,23 @@ pub struct ServiceInfo {
 }
 
 /// Environment/system information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Environment {
     /// Operating system (linux, macos, windows, etc.)
     pub os: String,
 
-    /// OS version
+    /// OS version (22.04, 14.2, etc.)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub os_version: Option<String>,
 
+    /// Linux distribution (ubuntu, fedora, etc.)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub os_distro: Option<String>,
+
+    /// Kernel release
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kernel_version: Option<String>,
+
     /// System architecture (x86_64, aarch64, etc.)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub arch: Option<String>,
@@ -75,13 +83,41 @@ pub struct Environment {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ci: Option<bool>,
 
+    /// CI provider (github_actions, gitlab, jenkins, etc.)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ci_provider: Option<String>,
+
+    /// Image class of the CI runner (ubuntu22, macos14, self-hosted, etc.)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ci_runner_image: Option<String>,
+
     /// Shell type (bash, zsh, etc.)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub shell: Option<String>,
+
+    /// Container runtime (docker, podman, kubernetes, etc.)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
+
+    /// Whether running under Windows Subsystem for Linux
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wsl: Option<bool>,
+
+    /// Whether running in an SSH session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh: Option<bool>,
+
+    /// Whether stdout is a terminal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tty: Option<bool>,
+
+    /// Terminal program (iTerm.app, vscode, xterm-256color, etc.)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub terminal: Option<String>,
 }
 
 /// Event data container
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct EventData {
     /// Event type (command_execution, feature_used, etc.)
     #[serde(rename = "type")]
@@ -93,6 +129,47 @@ pub struct EventData {
 
     /// Event-specific data
     pub data: serde_json::Value,
+
+    /// Properties attached to every event (see `TelemetryKit::set_property`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<serde_json::Map<String, serde_json::Value>>,
+
+    /// Trace identifier shared by every span of a session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
+
+    /// Event ID of the enclosing span, for nested spans
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_event_id: Option<Uuid>,
+
+    /// Fraction of installations this event is recorded for, when sampled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_rate: Option<f64>,
+
+    /// Experiment variants the session was exposed to, by experiment name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub experiments: Option<BTreeMap<String, String>>,
+
+    /// Set when this event stands for several identical events (see [`crate::coalesce`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rollup: Option<Rollup>,
+}
+
+/// Summary of identical events merged into one
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rollup {
+    /// Number of events merged
+    pub count: u64,
+
+    /// Timestamp of the first event, which is also the event's timestamp
+    pub first_timestamp: DateTime<Utc>,
+
+    /// Timestamp of the last event
+    pub last_timestamp: DateTime<Utc>,
+
+    /// Sum of the events' `duration_ms`, when they had one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_duration_ms: Option<u64>,
 }
 
 /// Transmission metadata
@@ -111,6 +188,43 @@ pub struct Metadata {
     pub retry_count: u32,
 }
 
+#[cfg(test)]
+impl Event {
+    /// Event fixture for unit tests
+    pub(crate) fn test_event(event_type: &str, data: serde_json::Value) -> Self {
+        Event {
+            schema_version: SCHEMA_VERSION.to_string(),
+            event_id: Uuid::new_v4(),
+            timestamp: Utc::now(),
+            service: ServiceInfo {
+                name: "test-service".to_string(),
+                version: "1.0.0".to_string(),
+                language: "rust".to_string(),
+                language_version: Some("1.75.0".to_string()),
+            },
+            user_id: "client_test123".to_string(),
+            session_id: Some("sess_test456".to_string()),
+            environment: Environment {
+                os: "linux".to_string(),
+                arch: Some("x86_64".to_string()),
+                ci: Some(false),
+                ..Default::default()
+            },
+            event: EventData {
+                event_type: event_type.to_string(),
+                data,
+                ..Default::default()
+            },
+            metadata: Metadata {
+                sdk_version: "0.1.0".to_string(),
+                transmission_timestamp: Utc::now(),
+                batch_size: 1,
+                retry_count: 0,
+            },
+        }
+    }
+}
+
 /// Builder for command execution events
 #[derive(Debug, Default)]
 pub struct CommandEventBuilder {
@@ -247,17 +361,305 @@ impl FeatureEventBuilder {
     }
 }
 
+/// Builder for error events
+///
+/// Captures the error and its `source()` chain as classified kinds and a stable
+/// fingerprint. Messages are only recorded when explicitly enabled, and are then
+/// sanitized for paths and email addresses.
+#[derive(Debug, Default)]
+pub struct ErrorEventBuilder {
+    chain: Vec<(String, String)>,
+    include_messages: bool,
+    context: Option<String>,
+    handled: Option<bool>,
+}
+
+/// An error that can be tracked
+///
+/// Implemented for every concrete error type, whose type name then classifies the
+/// top of the chain, and for `dyn Error` trait objects, which are classified by
+/// downcasting only.
+pub trait TrackableError {
+    /// The error as a trait object
+    fn as_error(&self) -> &(dyn std::error::Error + 'static);
+
+    /// The concrete type name, when known at compile time
+    fn type_name(&self) -> Option<&'static str>;
+}
+
+impl<E: std::error::Error + 'static> TrackableError for E {
+    fn as_error(&self) -> &(dyn std::error::Error + 'static) {
+        self
+    }
+
+    fn type_name(&self) -> Option<&'static str> {
+        Some(std::any::type_name::<E>())
+    }
+}
+
+impl TrackableError for dyn std::error::Error + 'static {
+    fn as_error(&self) -> &(dyn std::error::Error + 'static) {
+        self
+    }
+
+    fn type_name(&self) -> Option<&'static str> {
+        None
+    }
+}
+
+impl TrackableError for dyn std::error::Error + Send + Sync + 'static {
+    fn as_error(&self) -> &(dyn std::error::Error + 'static) {
+        self
+    }
+
+    fn type_name(&self) -> Option<&'static str> {
+        None
+    }
+}
+
+impl ErrorEventBuilder {
+    /// Capture an error and its chain of sources
+    pub fn new<E: TrackableError + ?Sized>(error: &E) -> Self {
+        let mut chain = Vec::new();
+        let mut current = Some(error.as_error());
+        while let Some(error) = current {
+            chain.push((classify_error(error), error.to_string()));
+            current = error.source();
+        }
+
+        if let (Some((kind, _)), Some(name)) = (chain.first_mut(), error.type_name()) {
+            if kind == UNKNOWN_ERROR_KIND {
+                *kind = short_type_name(name).to_string();
+            }
+        }
+
+        Self {
+            chain,
+            ..Default::default()
+        }
+    }
+
+    /// Capture an error known only by its kind and message
+    #[cfg(feature = "tracing")]
+    pub(crate) fn from_kind(kind: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            chain: vec![(kind.into(), message.into())],
+            ..Default::default()
+        }
+    }
+
+    /// Record sanitized error messages (off by default)
+    ///
+    /// Requires the `privacy` feature; without it messages are never recorded.
+    pub fn include_messages(mut self, include: bool) -> Self {
+        self.include_messages = include;
+        self
+    }
+
+    /// Set where the error happened (e.g. the command or operation name)
+    pub fn context(mut self, context: impl Into<String>) -> Self {
+        self.context = Some(context.into());
+        self
+    }
+
+    /// Set whether the error was handled by the application
+    pub fn handled(mut self, handled: bool) -> Self {
+        self.handled = Some(handled);
+        self
+    }
+
+    /// Stable fingerprint of the error chain, independent of messages
+    pub fn fingerprint(&self) -> String {
+        use sha2::{Digest, Sha256};
+
+        let kinds: Vec<&str> = self.chain.iter().map(|(kind, _)| kind.as_str()).collect();
+        let mut hasher = Sha256::new();
+        hasher.update(kinds.join(">").as_bytes());
+        hex::encode(&hasher.finalize()[..8])
+    }
+
+    /// Build the event data
+    pub fn build(self) -> serde_json::Value {
+        let fingerprint = self.fingerprint();
+        let include_messages = self.include_messages && cfg!(feature = "privacy");
+
+        let chain: Vec<serde_json::Value> = self
+            .chain
+            .iter()
+            .map(|(kind, message)| {
+                let mut entry = serde_json::json!({ "kind": kind });
+                if include_messages {
+                    entry["message"] = serde_json::json!(sanitize_message(message));
+                }
+                entry
+            })
+            .collect();
+
+        let mut data = serde_json::json!({
+            "kind": self.chain.first().map(|(kind, _)| kind.as_str()),
+            "fingerprint": fingerprint,
+            "chain": chain,
+        });
+
+        if let Some(context) = self.context {
+            data["context"] = serde_json::json!(context);
+        }
+
+        if let Some(handled) = self.handled {
+            data["handled"] = serde_json::json!(handled);
+        }
+
+        data
+    }
+}
+
+const UNKNOWN_ERROR_KIND: &str = "Unknown";
+
+/// Classify an error without looking at its message
+///
+/// Known error types map to their variant or kind; anything else is `Unknown`, as
+/// neither `Display` nor `Debug` output is guaranteed to be free of messages.
+fn classify_error(error: &(dyn std::error::Error + 'static)) -> String {
+    if let Some(error) = error.downcast_ref::<crate::error::TelemetryError>() {
+        return format!("TelemetryError::{}", error.kind());
+    }
+
+    if let Some(error) = error.downcast_ref::<std::io::Error>() {
+        return format!("io::Error::{:?}", error.kind());
+    }
+
+    if let Some(error) = error.downcast_ref::<serde_json::Error>() {
+        return format!("serde_json::Error::{:?}", error.classify());
+    }
+
+    UNKNOWN_ERROR_KIND.to_string()
+}
+
+/// Strip the module path from a type name, e.g. `app::config::LoadError` -> `LoadError`
+fn short_type_name(name: &str) -> &str {
+    let base = name.split('<').next().unwrap_or(name);
+    base.rsplit("::").next().unwrap_or(base)
+}
+
+/// Replace the home directory and email addresses in a message, keeping the rest
+#[cfg(feature = "privacy")]
+pub(crate) fn sanitize_message(message: &str) -> String {
+    use crate::privacy::PrivacyManager;
+
+    PrivacyManager::sanitize_path(message)
+        .split_inclusive(char::is_whitespace)
+        .map(|word| {
+            // Surrounding punctuation such as `<...>,` is not part of the address
+            let candidate = word.trim_matches(|c: char| !c.is_alphanumeric());
+            if is_email(candidate) {
+                word.replacen(candidate, &PrivacyManager::sanitize_email(candidate), 1)
+            } else {
+                word.to_string()
+            }
+        })
+        .collect()
+}
+
+/// Check if a word looks like an email address (`local@domain.tld`)
+#[cfg(feature = "privacy")]
+fn is_email(word: &str) -> bool {
+    match word.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !domain.contains('@')
+                && domain
+                    .split_once('.')
+                    .is_some_and(|(name, tld)| !name.is_empty() && !tld.is_empty())
+        }
+        None => false,
+    }
+}
+
+#[cfg(not(feature = "privacy"))]
+pub(crate) fn sanitize_message(_message: &str) -> String {
+    String::new()
+}
+
+/// A strongly typed custom event
+///
+/// Usually implemented with `#[derive(TelemetryEvent)]` (requires the `macros` feature)
+/// and recorded with [`TelemetryKit::track`](crate::TelemetryKit::track).
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use telemetry_kit::prelude::*;
+///
+/// #[derive(TelemetryEvent)]
+/// #[telemetry(event_type = "deploy_started", category = "usage")]
+/// struct DeployStarted {
+///     region: String,
+///     #[telemetry(hash)]
+///     email: String,
+///     #[telemetry(redact)]
+///     token: String,
+///     #[telemetry(skip)]
+///     retries: u32,
+/// }
+/// ```
+pub trait TelemetryEvent {
+    /// Event type recorded on the event
+    fn event_type(&self) -> &'static str;
+
+    /// Event category, if any
+    fn category(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Event data
+    fn to_data(&self) -> serde_json::Value;
+}
+
+/// Placeholder stored in place of fields marked `#[telemetry(redact)]`
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Serialize a field value, recording `null` when serialization fails
+#[doc(hidden)]
+pub fn field_value<T: Serialize + ?Sized>(value: &T) -> serde_json::Value {
+    serde_json::to_value(value).unwrap_or(serde_json::Value::Null)
+}
+
+/// Replace a field value with a short SHA-256 hash of its serialized form
+///
+/// String values are hashed as-is so the same input always yields the same hash.
+#[doc(hidden)]
+pub fn hashed_field_value<T: Serialize + ?Sized>(value: &T) -> serde_json::Value {
+    use sha2::{Digest, Sha256};
+
+    let input = match field_value(value) {
+        serde_json::Value::String(s) => s,
+        other => other.to_string(),
+    };
+
+    let mut hasher = Sha256::new();
+    hasher.update(input.as_bytes());
+    let result = hasher.finalize();
+    serde_json::json!(format!("hash_{}", hex::encode(&result[..8])))
+}
+
 /// Batch of events for transmission
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EventBatch {
     /// Events in this batch
     pub events: Vec<Event>,
+
+    /// Events dropped from a full local buffer since the last report, by category
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dropped_events: BTreeMap<String, u64>,
 }
 
 impl EventBatch {
     /// Create a new event batch
     pub fn new(events: Vec<Event>) -> Self {
-        Self { events }
+        Self {
+            events,
+            dropped_events: BTreeMap::new(),
+        }
     }
 
     /// Get the batch size
@@ -270,3 +672,154 @@ impl EventBatch {
         self.events.is_empty()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Deploy {
+        region: String,
+        email: String,
+    }
+
+    impl TelemetryEvent for Deploy {
+        fn event_type(&self) -> &'static str {
+            "deploy"
+        }
+
+        fn to_data(&self) -> serde_json::Value {
+            serde_json::json!({
+                "region": field_value(&self.region),
+                "email": hashed_field_value(&self.email),
+            })
+        }
+    }
+
+    #[test]
+    fn test_telemetry_event_defaults() {
+        let event = Deploy {
+            region: "eu-west-1".to_string(),
+            email: "user@example.com".to_string(),
+        };
+
+        assert_eq!(event.event_type(), "deploy");
+        assert_eq!(event.category(), None);
+
+        let data = event.to_data();
+        assert_eq!(data["region"], "eu-west-1");
+        assert_ne!(data["email"], "user@example.com");
+    }
+
+    #[derive(Debug)]
+    struct Wrapper(std::io::Error);
+
+    impl std::fmt::Display for Wrapper {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "failed to read /home/alice/config.toml")
+        }
+    }
+
+    impl std::error::Error for Wrapper {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            Some(&self.0)
+        }
+    }
+
+    #[test]
+    fn test_error_event_chain() {
+        let error = Wrapper(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "user@example.com not found",
+        ));
+
+        let data = ErrorEventBuilder::new(&error)
+            .context("load_config")
+            .build();
+        assert_eq!(data["kind"], "Wrapper");
+        assert_eq!(data["chain"][1]["kind"], "io::Error::NotFound");
+        assert_eq!(data["context"], "load_config");
+        assert!(data["chain"][0].get("message").is_none());
+        assert!(data.get("handled").is_none());
+    }
+
+    #[test]
+    fn test_error_fingerprint_ignores_messages() {
+        let first = crate::error::TelemetryError::Other("first".to_string());
+        let second = crate::error::TelemetryError::Other("second".to_string());
+        let other = crate::error::TelemetryError::MaxRetriesExceeded;
+
+        let fingerprint =
+            |e: &crate::error::TelemetryError| ErrorEventBuilder::new(e).fingerprint();
+        assert_eq!(fingerprint(&first), fingerprint(&second));
+        assert_ne!(fingerprint(&first), fingerprint(&other));
+        assert_eq!(
+            ErrorEventBuilder::new(&other).build()["kind"],
+            "TelemetryError::MaxRetriesExceeded"
+        );
+    }
+
+    #[cfg(feature = "privacy")]
+    #[test]
+    fn test_error_messages_are_sanitized() {
+        let error = Wrapper(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "user@example.com not found",
+        ));
+
+        let data = ErrorEventBuilder::new(&error)
+            .include_messages(true)
+            .build();
+        let message = data["chain"][1]["message"].as_str().unwrap();
+        assert!(message.starts_with("email_"));
+        assert!(message.ends_with(" not found"));
+    }
+
+    struct Opaque;
+
+    impl std::fmt::Debug for Opaque {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "Failed to open /home/jane/notes.txt")
+        }
+    }
+
+    impl std::fmt::Display for Opaque {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "Failed to open /home/jane/notes.txt")
+        }
+    }
+
+    impl std::error::Error for Opaque {}
+
+    #[test]
+    fn test_error_kind_ignores_debug_output() {
+        let data = ErrorEventBuilder::new(&Opaque).build();
+        assert_eq!(data["kind"], "Opaque");
+
+        let boxed: Box<dyn std::error::Error + Send + Sync> = Box::new(Opaque);
+        let data = ErrorEventBuilder::new(boxed.as_ref()).build();
+        assert_eq!(data["kind"], "Unknown");
+    }
+
+    #[cfg(feature = "privacy")]
+    #[test]
+    fn test_sanitize_message_keeps_surrounding_text() {
+        let message = sanitize_message("failed to load config for <jane@example.com>, retrying");
+
+        assert!(message.starts_with("failed to load config for <email_"));
+        assert!(message.ends_with(">, retrying"));
+        assert!(!message.contains("jane@example.com"));
+        assert_eq!(
+            sanitize_message("no match for pattern @latest in npm@v10"),
+            "no match for pattern @latest in npm@v10"
+        );
+    }
+
+    #[test]
+    fn test_hashed_field_value_is_stable() {
+        let first = hashed_field_value("user@example.com");
+        let second = hashed_field_value(&"user@example.com".to_string());
+        assert_eq!(first, second);
+        assert!(first.as_str().unwrap().starts_with("hash_"));
+        assert_ne!(hashed_field_value(&1u32), hashed_field_value(&2u32));
+    }
+}
@@ -0,0 +1,326 @@
+//! Pluggable export destinations for buffered events
+//!
+//! An [`Exporter`] receives batches of events read from the local SQLite buffer and
+//! reports which events it accepted or rejected. [`SyncClient`](crate::sync::SyncClient)
+//! is one exporter; applications can register their own with
+//! [`TelemetryBuilder::exporter`](crate::TelemetryBuilder::exporter) and still reuse
+//! buffering, retry and privacy handling.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use telemetry_kit::event::EventBatch;
+//! use telemetry_kit::exporter::{ExportFuture, ExportResult, Exporter};
+//! use telemetry_kit::prelude::*;
+//!
+//! struct FileDrop;
+//!
+//! impl Exporter for FileDrop {
+//!     fn name(&self) -> &str {
+//!         "file-drop"
+//!     }
+//!
+//!     fn export<'a>(&'a self, batch: &'a EventBatch) -> ExportFuture<'a> {
+//!         Box::pin(async move {
+//!             let json = serde_json::to_string(batch)?;
+//!             std::fs::write("/tmp/telemetry-batch.json", json)?;
+//!             Ok(ExportResult::accept_all(batch))
+//!         })
+//!     }
+//! }
+//!
+//! # fn main() -> telemetry_kit::Result<()> {
+//! let telemetry = TelemetryKit::builder()
+//!     .service_name("my-app")?
+//!     .exporter(FileDrop)
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Result;
+use crate::event::EventBatch;
+use crate::storage::EventStorage;
+use crate::sync::DEFAULT_BATCH_SIZE;
+use std::collections::HashSet;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// Boxed future returned by [`Exporter::export`]
+pub type ExportFuture<'a> = Pin<Box<dyn Future<Output = Result<ExportResult>> + Send + 'a>>;
+
+/// A destination for buffered telemetry events
+///
+/// Returning `Err` from [`export`](Exporter::export) means the whole batch failed and
+/// should be retried later. Events listed in [`ExportResult::rejected`] are treated as
+/// permanently refused and will not be sent again.
+pub trait Exporter: Send + Sync {
+    /// Short name used in logs and error messages
+    fn name(&self) -> &str;
+
+    /// Maximum number of events to send in a single batch
+    fn batch_size(&self) -> usize {
+        DEFAULT_BATCH_SIZE
+    }
+
+    /// Send a batch of events
+    fn export<'a>(&'a self, batch: &'a EventBatch) -> ExportFuture<'a>;
+}
+
+impl std::fmt::Debug for dyn Exporter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Exporter")
+            .field("name", &self.name())
+            .finish()
+    }
+}
+
+/// Per-event outcome of an export
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExportResult {
+    /// Events the destination accepted
+    pub accepted: Vec<Uuid>,
+    /// Events the destination permanently refused
+    pub rejected: Vec<RejectedEvent>,
+}
+
+/// An event refused by an exporter
+#[derive(Debug, Clone, PartialEq)]
+pub struct RejectedEvent {
+    /// Event that was refused
+    pub event_id: Uuid,
+    /// Reason given by the destination
+    pub reason: String,
+}
+
+impl ExportResult {
+    /// Mark every event in the batch as accepted
+    pub fn accept_all(batch: &EventBatch) -> Self {
+        Self {
+            accepted: batch.events.iter().map(|e| e.event_id).collect(),
+            rejected: Vec::new(),
+        }
+    }
+
+    /// Number of accepted events
+    pub fn accepted_count(&self) -> usize {
+        self.accepted.len()
+    }
+
+    /// Number of rejected events
+    pub fn rejected_count(&self) -> usize {
+        self.rejected.len()
+    }
+
+    /// Check if the exporter has a final answer for an event
+    fn is_settled(&self, event_id: &Uuid) -> bool {
+        self.accepted.contains(event_id) || self.rejected.iter().any(|r| &r.event_id == event_id)
+    }
+}
+
+/// Read one batch of unsynced events and send it to every exporter
+///
+/// An event is marked as synced once every exporter accepted or rejected it. Events
+/// left unsettled (because an exporter failed) have their retry count incremented and
+/// are sent again on the next run, which may deliver duplicates to exporters that
+/// already succeeded. Counts of events dropped from a full buffer travel with the
+/// batch and are cleared once no exporter failed.
+pub(crate) async fn export_pending(
+    exporters: &[Arc<dyn Exporter>],
+    storage: &Arc<RwLock<EventStorage>>,
+) -> Result<()> {
+    let Some(batch_size) = exporters.iter().map(|e| e.batch_size()).min() else {
+        return Ok(());
+    };
+
+    let storage_read = storage.read().await;
+    let events = storage_read.get_unsynced(batch_size)?;
+    let dropped_events = if events.is_empty() {
+        Default::default()
+    } else {
+        storage_read.dropped_counts()?
+    };
+    drop(storage_read);
+
+    if events.is_empty() {
+        return Ok(());
+    }
+
+    let batch = EventBatch {
+        events,
+        dropped_events,
+    };
+    let mut settled: HashSet<Uuid> = batch.events.iter().map(|e| e.event_id).collect();
+    let mut first_error = None;
+
+    for exporter in exporters {
+        match exporter.export(&batch).await {
+            Ok(result) => settled.retain(|id| result.is_settled(id)),
+            Err(e) => {
+                settled.clear();
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    let (done, pending): (Vec<Uuid>, Vec<Uuid>) = batch
+        .events
+        .iter()
+        .map(|e| e.event_id)
+        .partition(|id| settled.contains(id));
+
+    let storage_write = storage.write().await;
+    if !done.is_empty() {
+        storage_write.mark_synced(&done)?;
+    }
+    if !pending.is_empty() {
+        storage_write.increment_retry(&pending)?;
+    }
+    if first_error.is_none() && !batch.dropped_events.is_empty() {
+        storage_write.clear_dropped(&batch.dropped_events)?;
+    }
+    drop(storage_write);
+
+    match first_error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::TelemetryError;
+    use crate::event::*;
+
+    struct AcceptAll;
+
+    impl Exporter for AcceptAll {
+        fn name(&self) -> &str {
+            "accept-all"
+        }
+
+        fn export<'a>(&'a self, batch: &'a EventBatch) -> ExportFuture<'a> {
+            Box::pin(async move { Ok(ExportResult::accept_all(batch)) })
+        }
+    }
+
+    struct RejectFirst;
+
+    impl Exporter for RejectFirst {
+        fn name(&self) -> &str {
+            "reject-first"
+        }
+
+        fn export<'a>(&'a self, batch: &'a EventBatch) -> ExportFuture<'a> {
+            Box::pin(async move {
+                let mut result = ExportResult::accept_all(batch);
+                let first = result.accepted.remove(0);
+                result.rejected.push(RejectedEvent {
+                    event_id: first,
+                    reason: "invalid".to_string(),
+                });
+                Ok(result)
+            })
+        }
+    }
+
+    struct Failing;
+
+    impl Exporter for Failing {
+        fn name(&self) -> &str {
+            "failing"
+        }
+
+        fn export<'a>(&'a self, _batch: &'a EventBatch) -> ExportFuture<'a> {
+            Box::pin(async move { Err(TelemetryError::Other("unreachable".to_string())) })
+        }
+    }
+
+    fn create_test_event() -> Event {
+        Event::test_event("test_event", serde_json::json!({}))
+    }
+
+    fn storage_with_events(count: usize) -> Arc<RwLock<EventStorage>> {
+        let storage = EventStorage::in_memory().unwrap();
+        for _ in 0..count {
+            storage.insert(&create_test_event()).unwrap();
+        }
+        Arc::new(RwLock::new(storage))
+    }
+
+    #[tokio::test]
+    async fn test_all_exporters_accept() {
+        let storage = storage_with_events(3);
+        let exporters: Vec<Arc<dyn Exporter>> = vec![Arc::new(AcceptAll), Arc::new(AcceptAll)];
+
+        export_pending(&exporters, &storage).await.unwrap();
+        assert_eq!(storage.read().await.unsynced_count().unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_events_are_not_retried() {
+        let storage = storage_with_events(2);
+        let exporters: Vec<Arc<dyn Exporter>> = vec![Arc::new(RejectFirst)];
+
+        export_pending(&exporters, &storage).await.unwrap();
+        assert_eq!(storage.read().await.unsynced_count().unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_failing_exporter_keeps_events() {
+        let storage = storage_with_events(2);
+        let exporters: Vec<Arc<dyn Exporter>> = vec![Arc::new(AcceptAll), Arc::new(Failing)];
+
+        assert!(export_pending(&exporters, &storage).await.is_err());
+        assert_eq!(storage.read().await.unsynced_count().unwrap(), 2);
+    }
+
+    struct Recording(std::sync::Mutex<Vec<EventBatch>>);
+
+    impl Exporter for Recording {
+        fn name(&self) -> &str {
+            "recording"
+        }
+
+        fn export<'a>(&'a self, batch: &'a EventBatch) -> ExportFuture<'a> {
+            self.0.lock().unwrap().push(batch.clone());
+            Box::pin(async move { Ok(ExportResult::accept_all(batch)) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dropped_counts_reported_once() {
+        let storage =
+            EventStorage::in_memory()
+                .unwrap()
+                .with_limits(crate::storage::BufferLimits {
+                    max_events: Some(1),
+                    ..Default::default()
+                });
+        for _ in 0..3 {
+            storage.insert(&create_test_event()).unwrap();
+        }
+        let storage = Arc::new(RwLock::new(storage));
+
+        let recording = Arc::new(Recording(Default::default()));
+        let exporters: Vec<Arc<dyn Exporter>> = vec![recording.clone()];
+        export_pending(&exporters, &storage).await.unwrap();
+        storage.read().await.insert(&create_test_event()).unwrap();
+        export_pending(&exporters, &storage).await.unwrap();
+
+        let batches = recording.0.lock().unwrap();
+        assert_eq!(batches[0].dropped_events["uncategorized"], 2);
+        assert!(batches[1].dropped_events.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_no_exporters() {
+        let storage = storage_with_events(1);
+        export_pending(&[], &storage).await.unwrap();
+        assert_eq!(storage.read().await.unsynced_count().unwrap(), 1);
+    }
+}
@@ -0,0 +1,206 @@
+//! Process-global TelemetryKit instance
+//!
+//! Registering a global instance lets code that has no handle to a [`TelemetryKit`]
+//! record events, most notably functions annotated with `#[instrument]`.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use telemetry_kit::prelude::*;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> telemetry_kit::Result<()> {
+//! let telemetry = TelemetryKit::builder()
+//!     .service_name("my-app")?
+//!     .build()?;
+//!
+//! // Clones share the same storage, so keep one for shutdown
+//! telemetry_kit::set_global(telemetry.clone())?;
+//!
+//! assert!(telemetry_kit::global().is_some());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{Result, TelemetryError};
+use crate::event::{field_value, CommandEventBuilder};
+use crate::telemetry::TelemetryKit;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+static GLOBAL: OnceLock<TelemetryKit> = OnceLock::new();
+
+/// Register the process-global TelemetryKit instance
+///
+/// The global instance can only be set once; later calls return an error and leave
+/// the first instance in place.
+pub fn set_global(telemetry: TelemetryKit) -> Result<()> {
+    GLOBAL.set(telemetry).map_err(|_| {
+        TelemetryError::invalid_config(
+            "global",
+            "A global TelemetryKit instance is already registered",
+        )
+    })
+}
+
+/// Get the process-global TelemetryKit instance, if one has been registered
+pub fn global() -> Option<&'static TelemetryKit> {
+    GLOBAL.get()
+}
+
+/// Decide whether an instrumented call should be recorded
+///
+/// Returns `false` when no global instance is registered or the call is sampled out.
+/// Sampling is keyed on the anonymous user ID like [`sampling`](crate::sampling)
+/// rules, so an installation is consistently in or out of the sample.
+/// Used by the code generated by `#[instrument]`; not part of the public API.
+#[doc(hidden)]
+pub fn should_record(sample: f64) -> bool {
+    global().is_some_and(|telemetry| telemetry.is_sampled_in(INSTRUMENTED_EVENT, sample))
+}
+
+/// Event type recorded for instrumented calls
+const INSTRUMENTED_EVENT: &str = "command_execution";
+
+/// A single call of an instrumented function, recorded once it returns
+///
+/// Used by the code generated by `#[instrument]`; not part of the public API.
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct InstrumentedCall {
+    name: &'static str,
+    category: &'static str,
+    fields: serde_json::Map<String, serde_json::Value>,
+    skip_errors: bool,
+    sample: f64,
+}
+
+impl InstrumentedCall {
+    /// Start recording a call
+    pub fn new(name: &'static str, category: &'static str) -> Self {
+        Self {
+            name,
+            category,
+            fields: serde_json::Map::new(),
+            skip_errors: false,
+            sample: 1.0,
+        }
+    }
+
+    /// Capture a function argument
+    ///
+    /// Values that fail to serialize are recorded as `null`.
+    pub fn field<T: serde::Serialize + ?Sized>(mut self, key: &str, value: &T) -> Self {
+        self.fields.insert(key.to_string(), field_value(value));
+        self
+    }
+
+    /// Do not record calls that returned `Err`
+    pub fn skip_errors(mut self) -> Self {
+        self.skip_errors = true;
+        self
+    }
+
+    /// Set the rate the call was sampled at, recorded on the event
+    pub fn sample(mut self, sample: f64) -> Self {
+        self.sample = sample;
+        self
+    }
+
+    /// Record the call from synchronous code
+    pub fn finish(self, elapsed: Duration, success: Option<bool>) {
+        let sample = self.sample;
+        if let Some((telemetry, category, data)) = self.into_event(elapsed, success) {
+            let _ = telemetry.track_event_sampled_detached(
+                INSTRUMENTED_EVENT,
+                Some(category),
+                data,
+                sample,
+            );
+        }
+    }
+
+    /// Record the call from async code
+    pub async fn finish_async(self, elapsed: Duration, success: Option<bool>) {
+        let sample = self.sample;
+        if let Some((telemetry, category, data)) = self.into_event(elapsed, success) {
+            let _ = telemetry
+                .track_event_sampled(INSTRUMENTED_EVENT, Some(category), data, sample)
+                .await;
+        }
+    }
+
+    fn into_event(
+        self,
+        elapsed: Duration,
+        success: Option<bool>,
+    ) -> Option<(&'static TelemetryKit, &'static str, serde_json::Value)> {
+        if self.skip_errors && success == Some(false) {
+            return None;
+        }
+
+        let telemetry = global()?;
+
+        let mut event = CommandEventBuilder::new(self.name).duration_ms(elapsed.as_millis() as u64);
+        if let Some(success) = success {
+            event = event.success(success);
+        }
+
+        let mut data = event.build();
+        if !self.fields.is_empty() {
+            // Sanitized along with the rest of the event data before storage
+            data["fields"] = serde_json::Value::Object(self.fields);
+        }
+
+        Some((telemetry, self.category, data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_set_global_once() {
+        let unique_name = format!("test-global-{}", Uuid::new_v4());
+        let db_path = std::env::temp_dir().join(format!("{}.db", unique_name));
+        let telemetry = TelemetryKit::builder()
+            .service_name(&unique_name)
+            .unwrap()
+            .db_path(&db_path)
+            .build()
+            .unwrap();
+
+        let _ = set_global(telemetry.clone());
+        assert!(global().is_some());
+        assert!(set_global(telemetry).is_err());
+
+        assert!(should_record(1.0));
+        assert!(!should_record(0.0));
+
+        InstrumentedCall::new("instrumented", "usage")
+            .field("region", "eu-west-1")
+            .finish_async(Duration::from_millis(5), Some(true))
+            .await;
+        InstrumentedCall::new("instrumented_sync", "usage").finish(Duration::from_millis(5), None);
+        // Sampling is deterministic for an installation and the rate is recorded
+        let sampled_in = should_record(0.5);
+        assert!((0..10).all(|_| should_record(0.5) == sampled_in));
+        InstrumentedCall::new("sampled", "usage")
+            .sample(0.5)
+            .finish_async(Duration::from_millis(5), Some(true))
+            .await;
+
+        let events = crate::storage::EventStorage::new(&db_path)
+            .unwrap()
+            .get_unsynced(10)
+            .unwrap();
+        assert_eq!(events[0].event.sample_rate, None);
+        let sampled = events.iter().find(|e| e.event.data["command"] == "sampled");
+        assert_eq!(
+            sampled.map(|e| e.event.sample_rate),
+            sampled_in.then_some(Some(0.5))
+        );
+    }
+}
@@ -38,12 +38,45 @@
 //!
 //! The SDK automatically syncs events to telemetry-kit.dev using HMAC-SHA256
 //! authentication. Events are buffered locally in SQLite and synced in batches.
+//!
+//! With the `otlp` feature, the same buffer can also be exported as OpenTelemetry
+//! log records to any OTLP/HTTP collector (see [`otlp`]).
+//!
+//! Applications without a tokio runtime can enable the `blocking` feature and use
+//! `TelemetryBuilder::build_blocking` (see the `blocking` module). tokio is still
+//! compiled in and runs a private runtime on a worker thread.
+//!
+//! Services already instrumented with `tracing` can record selected spans and
+//! `ERROR` events through a subscriber layer (requires the `tracing` feature; see the
+//! `tracing` module).
+//!
+//! Settings can also be loaded from a `telemetry-kit.toml` file (requires the
+//! `config` feature) or `TK_*` environment variables (see [`config`]).
+//!
+//! ## Instrumentation
+//!
+//! Register an instance with [`set_global`] and functions annotated with
+//! `#[instrument]` (requires the `macros` feature) record a `command_execution`
+//! event with their name, duration and, for `Result` returns, success.
+//!
+//! Custom events can be strongly typed with `#[derive(TelemetryEvent)]` and recorded
+//! with [`TelemetryKit::track`].
+//!
+//! [`TelemetryKit::span`] returns a guard that records a timed `span` event when
+//! dropped; nested spans reference their parent (see [`span`]).
 
 #![warn(missing_docs)]
 #![warn(rustdoc::missing_crate_level_docs)]
 
+pub mod coalesce;
+pub mod config;
+pub mod environment;
 pub mod error;
 pub mod event;
+pub mod metrics;
+pub mod processor;
+pub mod sampling;
+pub mod span;
 pub mod storage;
 pub mod user;
 
@@ -53,22 +86,52 @@ pub mod sync;
 #[cfg(feature = "sync")]
 pub mod auto_sync;
 
+#[cfg(feature = "sync")]
+pub mod exporter;
+
+#[cfg(feature = "sync")]
+pub mod upload;
+
+#[cfg(feature = "blocking")]
+pub mod blocking;
+
 #[cfg(feature = "privacy")]
 pub mod privacy;
 
+#[cfg(feature = "otlp")]
+pub mod otlp;
+
+#[cfg(feature = "testing")]
+pub mod testing;
+
+#[cfg(feature = "tracing")]
+pub mod tracing;
+
 #[cfg(feature = "napi-bindings")]
 pub mod bindings;
 
 mod builder;
+mod crash;
+mod global;
+mod session;
 mod telemetry;
 
 pub use builder::TelemetryBuilder;
 pub use error::{Result, TelemetryError};
+pub use global::{global, set_global};
 pub use telemetry::TelemetryKit;
 
 // Re-export procedural macros
 #[cfg(feature = "macros")]
-pub use telemetry_kit_macros::instrument;
+pub use telemetry_kit_macros::{instrument, TelemetryEvent};
+
+/// Support code for the procedural macros; not part of the public API
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::event::{field_value, hashed_field_value, REDACTED};
+    pub use crate::global::{should_record, InstrumentedCall};
+    pub use serde_json;
+}
 
 /// Prelude module for convenient imports
 ///
@@ -79,10 +142,11 @@ pub mod prelude {
     pub use crate::builder::TelemetryBuilder;
     pub use crate::error::{Result, TelemetryError};
     pub use crate::event::*;
+    pub use crate::global::{global, set_global};
     pub use crate::telemetry::TelemetryKit;
 
     #[cfg(feature = "macros")]
-    pub use crate::instrument;
+    pub use crate::{instrument, TelemetryEvent};
 }
 
 #[cfg(test)]
@@ -0,0 +1,323 @@
+//! Local metrics: counters, gauges and histograms
+//!
+//! Metrics are aggregated in memory and written to the local buffer as a single
+//! `metrics_summary` event per flush interval, so recording a value never touches
+//! SQLite and only aggregates ever leave the process.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use telemetry_kit::prelude::*;
+//!
+//! # async fn example(telemetry: &TelemetryKit) -> telemetry_kit::Result<()> {
+//! let cache_hits = telemetry.counter("cache_hits");
+//! cache_hits.add(1);
+//!
+//! telemetry.gauge("queue_depth").set(42.0);
+//! telemetry.histogram("request_ms").record(12.5);
+//!
+//! // Summaries are flushed on the metrics interval and on shutdown,
+//! // or explicitly:
+//! telemetry.flush_metrics().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Default interval between metrics summaries (seconds)
+pub const DEFAULT_METRICS_INTERVAL: u64 = 60;
+
+/// Default histogram bucket upper bounds, suited to durations in milliseconds
+pub const DEFAULT_BUCKETS: &[f64] = &[
+    1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0, 10000.0,
+];
+
+/// A monotonically increasing count, reported as the delta since the last flush
+#[derive(Debug, Clone, Default)]
+pub struct Counter {
+    value: Arc<AtomicU64>,
+}
+
+impl Counter {
+    /// Add to the counter
+    pub fn add(&self, value: u64) {
+        self.value.fetch_add(value, Ordering::Relaxed);
+    }
+
+    /// Add one to the counter
+    pub fn increment(&self) {
+        self.add(1);
+    }
+
+    fn take(&self) -> u64 {
+        self.value.swap(0, Ordering::Relaxed)
+    }
+}
+
+/// A value that can go up and down, reported as its last value
+///
+/// A gauge is only reported in the intervals in which it was set.
+#[derive(Debug, Clone, Default)]
+pub struct Gauge {
+    data: Arc<Mutex<GaugeData>>,
+}
+
+#[derive(Debug, Default)]
+struct GaugeData {
+    value: f64,
+    /// Whether the value was set since the last flush
+    changed: bool,
+}
+
+impl Gauge {
+    /// Set the current value
+    pub fn set(&self, value: f64) {
+        if let Ok(mut data) = self.data.lock() {
+            data.value = value;
+            data.changed = true;
+        }
+    }
+
+    fn take(&self) -> Option<f64> {
+        let mut data = self.data.lock().ok()?;
+        if !std::mem::take(&mut data.changed) {
+            return None;
+        }
+
+        Some(data.value)
+    }
+}
+
+/// A distribution of values, reported as count, sum, min, max and bucket counts
+#[derive(Debug, Clone, Default)]
+pub struct Histogram {
+    data: Arc<Mutex<HistogramData>>,
+}
+
+#[derive(Debug, Default)]
+struct HistogramData {
+    count: u64,
+    sum: f64,
+    min: f64,
+    max: f64,
+    /// One count per entry of [`DEFAULT_BUCKETS`], plus one for larger values
+    bucket_counts: Vec<u64>,
+}
+
+impl Histogram {
+    /// Record a value
+    pub fn record(&self, value: f64) {
+        let Ok(mut data) = self.data.lock() else {
+            return;
+        };
+
+        if data.count == 0 {
+            data.min = value;
+            data.max = value;
+            data.bucket_counts = vec![0; DEFAULT_BUCKETS.len() + 1];
+        } else {
+            data.min = data.min.min(value);
+            data.max = data.max.max(value);
+        }
+        data.count += 1;
+        data.sum += value;
+
+        let bucket = DEFAULT_BUCKETS
+            .iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(DEFAULT_BUCKETS.len());
+        data.bucket_counts[bucket] += 1;
+    }
+
+    /// Record a duration in milliseconds
+    pub fn record_duration(&self, duration: Duration) {
+        self.record(duration.as_secs_f64() * 1000.0);
+    }
+
+    fn take(&self) -> Option<serde_json::Value> {
+        let mut data = self.data.lock().ok()?;
+        if data.count == 0 {
+            return None;
+        }
+
+        let data = std::mem::take(&mut *data);
+        Some(serde_json::json!({
+            "count": data.count,
+            "sum": data.sum,
+            "min": data.min,
+            "max": data.max,
+            "bucket_bounds": DEFAULT_BUCKETS,
+            "bucket_counts": data.bucket_counts,
+        }))
+    }
+}
+
+/// In-memory store of every metric created through a TelemetryKit instance
+#[derive(Debug)]
+pub(crate) struct MetricsRegistry {
+    counters: Mutex<HashMap<String, Counter>>,
+    gauges: Mutex<HashMap<String, Gauge>>,
+    histograms: Mutex<HashMap<String, Histogram>>,
+    last_flush: Mutex<Instant>,
+}
+
+impl Default for MetricsRegistry {
+    fn default() -> Self {
+        Self {
+            counters: Mutex::default(),
+            gauges: Mutex::default(),
+            histograms: Mutex::default(),
+            last_flush: Mutex::new(Instant::now()),
+        }
+    }
+}
+
+impl MetricsRegistry {
+    /// Get or create a counter
+    pub(crate) fn counter(&self, name: &str) -> Counter {
+        get_or_create(&self.counters, name)
+    }
+
+    /// Get or create a gauge
+    pub(crate) fn gauge(&self, name: &str) -> Gauge {
+        get_or_create(&self.gauges, name)
+    }
+
+    /// Get or create a histogram
+    pub(crate) fn histogram(&self, name: &str) -> Histogram {
+        get_or_create(&self.histograms, name)
+    }
+
+    /// Build the summary for the current interval and reset every metric
+    ///
+    /// Returns `None` when nothing was recorded.
+    pub(crate) fn take_summary(&self) -> Option<serde_json::Value> {
+        let elapsed = {
+            let mut last_flush = self.last_flush.lock().ok()?;
+            let elapsed = last_flush.elapsed();
+            *last_flush = Instant::now();
+            elapsed
+        };
+
+        let counters: serde_json::Map<String, serde_json::Value> = self
+            .counters
+            .lock()
+            .ok()?
+            .iter()
+            .filter_map(|(name, counter)| match counter.take() {
+                0 => None,
+                value => Some((name.clone(), value.into())),
+            })
+            .collect();
+
+        let gauges: serde_json::Map<String, serde_json::Value> = self
+            .gauges
+            .lock()
+            .ok()?
+            .iter()
+            .filter_map(|(name, gauge)| Some((name.clone(), gauge.take()?.into())))
+            .collect();
+
+        let histograms: serde_json::Map<String, serde_json::Value> = self
+            .histograms
+            .lock()
+            .ok()?
+            .iter()
+            .filter_map(|(name, histogram)| Some((name.clone(), histogram.take()?)))
+            .collect();
+
+        if counters.is_empty() && gauges.is_empty() && histograms.is_empty() {
+            return None;
+        }
+
+        Some(serde_json::json!({
+            "interval_ms": elapsed.as_millis() as u64,
+            "counters": counters,
+            "gauges": gauges,
+            "histograms": histograms,
+        }))
+    }
+}
+
+fn get_or_create<T: Clone + Default>(metrics: &Mutex<HashMap<String, T>>, name: &str) -> T {
+    match metrics.lock() {
+        Ok(mut metrics) => metrics.entry(name.to_string()).or_default().clone(),
+        // A poisoned registry still hands out a working, unregistered metric
+        Err(_) => T::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_registry_has_no_summary() {
+        let registry = MetricsRegistry::default();
+        registry.counter("unused");
+        assert!(registry.take_summary().is_none());
+    }
+
+    #[test]
+    fn test_counter_reports_delta() {
+        let registry = MetricsRegistry::default();
+        let hits = registry.counter("cache_hits");
+        hits.add(2);
+        registry.counter("cache_hits").increment();
+
+        let summary = registry.take_summary().unwrap();
+        assert_eq!(summary["counters"]["cache_hits"], 3);
+
+        // Counters reset after each flush
+        assert!(registry.take_summary().is_none());
+    }
+
+    #[test]
+    fn test_gauge_keeps_last_value() {
+        let registry = MetricsRegistry::default();
+        let depth = registry.gauge("queue_depth");
+        depth.set(3.0);
+        depth.set(7.5);
+
+        assert_eq!(
+            registry.take_summary().unwrap()["gauges"]["queue_depth"],
+            7.5
+        );
+
+        // Gauges that were not set since the last flush are not reported
+        assert!(registry.take_summary().is_none());
+
+        depth.set(7.5);
+        assert_eq!(
+            registry.take_summary().unwrap()["gauges"]["queue_depth"],
+            7.5
+        );
+    }
+
+    #[test]
+    fn test_histogram_summary() {
+        let registry = MetricsRegistry::default();
+        let latency = registry.histogram("request_ms");
+        latency.record(3.0);
+        latency.record(40.0);
+        latency.record_duration(Duration::from_secs(20));
+
+        let summary = registry.take_summary().unwrap();
+        let histogram = &summary["histograms"]["request_ms"];
+        assert_eq!(histogram["count"], 3);
+        assert_eq!(histogram["min"], 3.0);
+        assert_eq!(histogram["max"], 20000.0);
+
+        let counts = histogram["bucket_counts"].as_array().unwrap();
+        assert_eq!(counts.len(), DEFAULT_BUCKETS.len() + 1);
+        assert_eq!(counts[1], 1); // <= 5
+        assert_eq!(counts[4], 1); // <= 50
+        assert_eq!(counts[DEFAULT_BUCKETS.len()], 1); // overflow
+
+        assert!(registry.take_summary().is_none());
+    }
+}
@@ -0,0 +1,227 @@
+//! OTLP exporter configuration
+
+use crate::error::{Result, TelemetryError};
+use crate::sync::{DEFAULT_BATCH_SIZE, MAX_BATCH_SIZE};
+use std::collections::HashMap;
+
+/// Default OTLP/HTTP collector endpoint
+pub const DEFAULT_OTLP_ENDPOINT: &str = "http://localhost:4318";
+
+/// Path of the OTLP/HTTP logs signal
+pub const LOGS_PATH: &str = "/v1/logs";
+
+/// Wire encoding used for OTLP/HTTP requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OtlpProtocol {
+    /// Binary protobuf (`application/x-protobuf`)
+    #[default]
+    HttpProtobuf,
+    /// JSON protobuf encoding (`application/json`)
+    HttpJson,
+}
+
+impl OtlpProtocol {
+    /// Content type sent with requests for this protocol
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            OtlpProtocol::HttpProtobuf => "application/x-protobuf",
+            OtlpProtocol::HttpJson => "application/json",
+        }
+    }
+}
+
+/// OTLP exporter configuration
+#[derive(Debug, Clone)]
+pub struct OtlpConfig {
+    /// Collector base URL (e.g., `http://localhost:4318`)
+    pub endpoint: String,
+
+    /// Wire encoding
+    pub protocol: OtlpProtocol,
+
+    /// Extra HTTP headers (e.g., collector API keys)
+    pub headers: HashMap<String, String>,
+
+    /// Request timeout in seconds
+    pub timeout_secs: u64,
+
+    /// Batch size (1-1000)
+    pub batch_size: usize,
+
+    /// Maximum retry attempts
+    pub max_retries: u32,
+}
+
+impl Default for OtlpConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: DEFAULT_OTLP_ENDPOINT.to_string(),
+            protocol: OtlpProtocol::default(),
+            headers: HashMap::new(),
+            timeout_secs: 10,
+            batch_size: DEFAULT_BATCH_SIZE,
+            max_retries: 5,
+        }
+    }
+}
+
+impl OtlpConfig {
+    /// Create a new OTLP configuration builder
+    pub fn builder() -> OtlpConfigBuilder {
+        OtlpConfigBuilder::new()
+    }
+
+    /// Get the full logs URL
+    ///
+    /// If the endpoint already ends with `/v1/logs` it is used as-is.
+    pub fn logs_url(&self) -> String {
+        let endpoint = self.endpoint.trim_end_matches('/');
+        if endpoint.ends_with(LOGS_PATH) {
+            endpoint.to_string()
+        } else {
+            format!("{}{}", endpoint, LOGS_PATH)
+        }
+    }
+
+    /// Validate the configuration
+    pub fn validate(&self) -> Result<()> {
+        if !self.endpoint.starts_with("http://") && !self.endpoint.starts_with("https://") {
+            return Err(TelemetryError::invalid_config(
+                "endpoint",
+                &format!(
+                    "'{}' must be an http:// or https:// URL (e.g., http://localhost:4318)",
+                    self.endpoint
+                ),
+            ));
+        }
+
+        if self.batch_size == 0 || self.batch_size > MAX_BATCH_SIZE {
+            return Err(TelemetryError::invalid_config(
+                "batch_size",
+                &format!(
+                    "Must be between 1 and {} (got {})",
+                    MAX_BATCH_SIZE, self.batch_size
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Builder for OTLP configuration
+#[derive(Debug, Default)]
+pub struct OtlpConfigBuilder {
+    endpoint: Option<String>,
+    protocol: Option<OtlpProtocol>,
+    headers: HashMap<String, String>,
+    timeout_secs: Option<u64>,
+    batch_size: Option<usize>,
+    max_retries: Option<u32>,
+}
+
+impl OtlpConfigBuilder {
+    /// Create a new builder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the collector endpoint (default: `http://localhost:4318`)
+    pub fn endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = Some(endpoint.into());
+        self
+    }
+
+    /// Set the wire encoding (default: protobuf)
+    pub fn protocol(mut self, protocol: OtlpProtocol) -> Self {
+        self.protocol = Some(protocol);
+        self
+    }
+
+    /// Add an HTTP header sent with every export request
+    pub fn header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(key.into(), value.into());
+        self
+    }
+
+    /// Set request timeout in seconds (default: 10)
+    pub fn timeout_secs(mut self, timeout: u64) -> Self {
+        self.timeout_secs = Some(timeout);
+        self
+    }
+
+    /// Set batch size (1-1000)
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = Some(batch_size);
+        self
+    }
+
+    /// Set maximum retry attempts
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = Some(max_retries);
+        self
+    }
+
+    /// Build the configuration
+    pub fn build(self) -> Result<OtlpConfig> {
+        let defaults = OtlpConfig::default();
+        let config = OtlpConfig {
+            endpoint: self.endpoint.unwrap_or(defaults.endpoint),
+            protocol: self.protocol.unwrap_or(defaults.protocol),
+            headers: self.headers,
+            timeout_secs: self.timeout_secs.unwrap_or(defaults.timeout_secs),
+            batch_size: self.batch_size.unwrap_or(defaults.batch_size),
+            max_retries: self.max_retries.unwrap_or(defaults.max_retries),
+        };
+
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_defaults() {
+        let config = OtlpConfig::builder().build().unwrap();
+        assert_eq!(config.endpoint, DEFAULT_OTLP_ENDPOINT);
+        assert_eq!(config.protocol, OtlpProtocol::HttpProtobuf);
+        assert_eq!(config.batch_size, DEFAULT_BATCH_SIZE);
+    }
+
+    #[test]
+    fn test_logs_url() {
+        let config = OtlpConfig::builder()
+            .endpoint("http://collector:4318/")
+            .build()
+            .unwrap();
+        assert_eq!(config.logs_url(), "http://collector:4318/v1/logs");
+
+        let config = OtlpConfig::builder()
+            .endpoint("https://otel.example.com/v1/logs")
+            .build()
+            .unwrap();
+        assert_eq!(config.logs_url(), "https://otel.example.com/v1/logs");
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(OtlpConfig::builder()
+            .endpoint("localhost:4318")
+            .build()
+            .is_err());
+        assert!(OtlpConfig::builder().batch_size(0).build().is_err());
+        assert!(OtlpConfig::builder().batch_size(5000).build().is_err());
+    }
+
+    #[test]
+    fn test_content_type() {
+        assert_eq!(
+            OtlpProtocol::HttpProtobuf.content_type(),
+            "application/x-protobuf"
+        );
+        assert_eq!(OtlpProtocol::HttpJson.content_type(), "application/json");
+    }
+}
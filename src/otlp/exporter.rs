@@ -0,0 +1,252 @@
+//! OTLP/HTTP exporter for pushing events to an OpenTelemetry collector
+
+use super::{config::OtlpConfig, config::OtlpProtocol, transform::to_export_request};
+use crate::error::{Result, TelemetryError};
+use crate::event::EventBatch;
+use crate::exporter::{ExportFuture, ExportResult, Exporter};
+use crate::sync::RetryStrategy;
+use opentelemetry_proto::tonic::collector::logs::v1::ExportLogsServiceResponse;
+use prost::Message;
+use reqwest::{header::HeaderMap, Client as HttpClient, StatusCode};
+use std::time::Duration;
+
+/// Log records a collector refused in an otherwise successful export
+#[derive(Debug, Default, PartialEq)]
+struct PartialSuccess {
+    rejected: u64,
+    message: Option<String>,
+}
+
+/// Exporter that sends buffered events to an OTLP/HTTP logs endpoint
+pub struct OtlpExporter {
+    config: OtlpConfig,
+    http_client: HttpClient,
+    retry_strategy: RetryStrategy,
+}
+
+impl OtlpExporter {
+    /// Create a new OTLP exporter
+    pub fn new(config: OtlpConfig) -> Result<Self> {
+        config.validate()?;
+
+        let http_client = HttpClient::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()?;
+
+        let retry_strategy = RetryStrategy::new(config.max_retries, 1000);
+
+        Ok(Self {
+            config,
+            http_client,
+            retry_strategy,
+        })
+    }
+
+    /// Export a batch of events as OTLP log records
+    ///
+    /// Returns the number of log records the collector reported as rejected, which
+    /// is also logged as a warning with the collector's message. Per the OTLP spec a
+    /// partial success is not retried. Retryable failures (429, 502, 503, 504) are
+    /// retried with exponential backoff.
+    pub async fn export(&self, batch: &EventBatch) -> Result<u64> {
+        let mut retry_count = 0;
+
+        loop {
+            match self.try_export(batch).await {
+                Ok(partial) => {
+                    if partial.rejected > 0 {
+                        eprintln!(
+                            "OTLP collector rejected {} of {} log records: {}",
+                            partial.rejected,
+                            batch.events.len(),
+                            partial.message.as_deref().unwrap_or("no reason given")
+                        );
+                    }
+                    return Ok(partial.rejected);
+                }
+                Err(e) if e.is_retryable() && self.retry_strategy.should_retry(retry_count) => {
+                    let delay = self.retry_strategy.delay_for(retry_count);
+                    tokio::time::sleep(delay).await;
+                    retry_count += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Attempt to export events (single attempt, no retry)
+    async fn try_export(&self, batch: &EventBatch) -> Result<PartialSuccess> {
+        if batch.is_empty() {
+            return Ok(PartialSuccess::default());
+        }
+
+        let request = to_export_request(&batch.events);
+        let body = match self.config.protocol {
+            OtlpProtocol::HttpProtobuf => request.encode_to_vec(),
+            OtlpProtocol::HttpJson => serde_json::to_vec(&request)?,
+        };
+
+        let mut headers = HeaderMap::new();
+        for (key, value) in &self.config.headers {
+            let name = key.parse::<reqwest::header::HeaderName>().map_err(|e| {
+                TelemetryError::invalid_config("headers", &format!("'{}': {}", key, e))
+            })?;
+            let value = value.parse().map_err(|e| {
+                TelemetryError::invalid_config("headers", &format!("'{}': {}", key, e))
+            })?;
+            headers.insert(name, value);
+        }
+        headers.insert(
+            "Content-Type",
+            self.config.protocol.content_type().parse().unwrap(),
+        );
+
+        let response = self
+            .http_client
+            .post(self.config.logs_url())
+            .headers(headers)
+            .body(body)
+            .send()
+            .await?;
+
+        let status = response.status();
+
+        match status {
+            StatusCode::OK => {
+                let bytes = response.bytes().await?;
+                Ok(self.partial_success(&bytes))
+            }
+
+            StatusCode::TOO_MANY_REQUESTS => {
+                let retry_after = response
+                    .headers()
+                    .get("Retry-After")
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(60);
+                Err(TelemetryError::RateLimitExceeded { retry_after })
+            }
+
+            StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT => {
+                let error_text = response.text().await.unwrap_or_default();
+                Err(TelemetryError::ServerError {
+                    status: status.as_u16(),
+                    message: error_text,
+                })
+            }
+
+            _ => {
+                // Per the OTLP spec, any other failure is not retryable
+                let error_text = response.text().await.unwrap_or_default();
+                Err(TelemetryError::Other(format!(
+                    "OTLP export failed with status {}: {}",
+                    status, error_text
+                )))
+            }
+        }
+    }
+
+    /// Decode the partial success from a collector response
+    ///
+    /// An empty or undecodable body is treated as full success.
+    fn partial_success(&self, bytes: &[u8]) -> PartialSuccess {
+        if bytes.is_empty() {
+            return PartialSuccess::default();
+        }
+
+        let (rejected, message) = match self.config.protocol {
+            OtlpProtocol::HttpProtobuf => ExportLogsServiceResponse::decode(bytes)
+                .ok()
+                .and_then(|r| r.partial_success)
+                .map(|p| (Some(p.rejected_log_records), Some(p.error_message)))
+                .unwrap_or_default(),
+            // Read the fields directly: collectors omit empty fields and may encode
+            // int64 values as strings, which the generated types do not accept
+            OtlpProtocol::HttpJson => match serde_json::from_slice::<serde_json::Value>(bytes) {
+                Ok(response) => {
+                    let partial = &response["partialSuccess"];
+                    let rejected = match &partial["rejectedLogRecords"] {
+                        serde_json::Value::String(count) => count.parse().ok(),
+                        count => count.as_i64(),
+                    };
+                    let message = partial["errorMessage"].as_str().map(str::to_string);
+                    (rejected, message)
+                }
+                Err(_) => (None, None),
+            },
+        };
+
+        PartialSuccess {
+            rejected: rejected.unwrap_or(0).max(0) as u64,
+            message: message.filter(|m| !m.is_empty()),
+        }
+    }
+
+    /// Get the OTLP configuration
+    pub fn config(&self) -> &OtlpConfig {
+        &self.config
+    }
+}
+
+impl Exporter for OtlpExporter {
+    fn name(&self) -> &str {
+        "otlp"
+    }
+
+    fn batch_size(&self) -> usize {
+        self.config.batch_size
+    }
+
+    fn export<'a>(&'a self, batch: &'a EventBatch) -> ExportFuture<'a> {
+        Box::pin(async move {
+            // Collectors only report how many records were rejected, not which ones,
+            // and per the OTLP spec a partial success must not be retried, so the
+            // whole batch is settled. The rejection is logged by `export`.
+            OtlpExporter::export(self, batch).await?;
+            Ok(ExportResult::accept_all(batch))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exporter_creation() {
+        let exporter = OtlpExporter::new(OtlpConfig::default());
+        assert!(exporter.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_empty_batch() {
+        let exporter = OtlpExporter::new(OtlpConfig::default()).unwrap();
+        let partial = exporter.try_export(&EventBatch::new(vec![])).await.unwrap();
+        assert_eq!(partial, PartialSuccess::default());
+    }
+
+    #[test]
+    fn test_partial_success_json() {
+        let config = OtlpConfig::builder()
+            .protocol(OtlpProtocol::HttpJson)
+            .build()
+            .unwrap();
+        let exporter = OtlpExporter::new(config).unwrap();
+
+        let body = br#"{"partialSuccess":{"rejectedLogRecords":2,"errorMessage":"bad"}}"#;
+        assert_eq!(
+            exporter.partial_success(body),
+            PartialSuccess {
+                rejected: 2,
+                message: Some("bad".to_string()),
+            }
+        );
+        let body = br#"{"partialSuccess":{"rejectedLogRecords":"3"}}"#;
+        assert_eq!(exporter.partial_success(body).rejected, 3);
+        assert_eq!(exporter.partial_success(body).message, None);
+        assert_eq!(exporter.partial_success(b"{}"), PartialSuccess::default());
+        assert_eq!(exporter.partial_success(b""), PartialSuccess::default());
+    }
+}
@@ -0,0 +1,15 @@
+//! OpenTelemetry (OTLP) export for telemetry-kit
+//!
+//! This module maps buffered events onto OTLP log records and ships them to an
+//! OpenTelemetry collector over OTLP/HTTP, using either protobuf or JSON encoding.
+//!
+//! Events are read from the same SQLite buffer used by the sync protocol, so an
+//! application can send to telemetry-kit.dev, an OTel collector, or both.
+
+mod config;
+mod exporter;
+mod transform;
+
+pub use config::{OtlpConfig, OtlpConfigBuilder, OtlpProtocol};
+pub use exporter::OtlpExporter;
+pub use transform::{to_export_request, to_resource_attributes};
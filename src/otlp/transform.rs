@@ -0,0 +1,373 @@
+//! Mapping from telemetry-kit events to OTLP log records
+
+use crate::event::Event;
+use opentelemetry_proto::tonic::collector::logs::v1::ExportLogsServiceRequest;
+use opentelemetry_proto::tonic::common::v1::{
+    any_value, AnyValue, ArrayValue, InstrumentationScope, KeyValue, KeyValueList,
+};
+use opentelemetry_proto::tonic::logs::v1::{LogRecord, ResourceLogs, ScopeLogs, SeverityNumber};
+use opentelemetry_proto::tonic::resource::v1::Resource;
+
+const SDK_NAME: &str = "telemetry-kit";
+const SDK_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Build an OTLP logs export request from a slice of events
+///
+/// Events sharing the same service and environment are grouped under a single
+/// `ResourceLogs` entry, preserving the order in which they were buffered.
+pub fn to_export_request(events: &[Event]) -> ExportLogsServiceRequest {
+    let mut resource_logs: Vec<ResourceLogs> = Vec::new();
+
+    for event in events {
+        let attributes = to_resource_attributes(event);
+        let record = to_log_record(event);
+
+        let existing = resource_logs.iter_mut().find(|rl| {
+            rl.resource
+                .as_ref()
+                .map(|r| r.attributes == attributes)
+                .unwrap_or(false)
+        });
+
+        match existing {
+            Some(rl) => rl.scope_logs[0].log_records.push(record),
+            None => resource_logs.push(ResourceLogs {
+                resource: Some(Resource {
+                    attributes,
+                    ..Default::default()
+                }),
+                scope_logs: vec![ScopeLogs {
+                    scope: Some(InstrumentationScope {
+                        name: SDK_NAME.to_string(),
+                        version: SDK_VERSION.to_string(),
+                        ..Default::default()
+                    }),
+                    log_records: vec![record],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }),
+        }
+    }
+
+    ExportLogsServiceRequest { resource_logs }
+}
+
+/// Resource attributes describing the service and environment of an event
+///
+/// Uses OpenTelemetry semantic convention names where one exists and the
+/// `telemetry_kit.` namespace otherwise.
+pub fn to_resource_attributes(event: &Event) -> Vec<KeyValue> {
+    let mut attributes = vec![
+        string_kv("service.name", &event.service.name),
+        string_kv("service.version", &event.service.version),
+        string_kv("telemetry.sdk.name", SDK_NAME),
+        string_kv("telemetry.sdk.language", "rust"),
+        string_kv("telemetry.sdk.version", SDK_VERSION),
+        string_kv("process.runtime.name", &event.service.language),
+    ];
+
+    if let Some(version) = &event.service.language_version {
+        attributes.push(string_kv("process.runtime.version", version));
+    }
+
+    attributes.push(string_kv("os.type", &event.environment.os));
+
+    if let Some(os_version) = &event.environment.os_version {
+        attributes.push(string_kv("os.version", os_version));
+    }
+
+    if let Some(arch) = &event.environment.arch {
+        attributes.push(string_kv("host.arch", arch));
+    }
+
+    if let Some(ci) = event.environment.ci {
+        attributes.push(kv("telemetry_kit.ci", any_value::Value::BoolValue(ci)));
+    }
+
+    if let Some(provider) = &event.environment.ci_provider {
+        attributes.push(string_kv("telemetry_kit.ci_provider", provider));
+    }
+
+    if let Some(image) = &event.environment.ci_runner_image {
+        attributes.push(string_kv("telemetry_kit.ci_runner_image", image));
+    }
+
+    if let Some(shell) = &event.environment.shell {
+        attributes.push(string_kv("telemetry_kit.shell", shell));
+    }
+
+    if let Some(distro) = &event.environment.os_distro {
+        attributes.push(string_kv("telemetry_kit.os_distro", distro));
+    }
+
+    if let Some(kernel) = &event.environment.kernel_version {
+        attributes.push(string_kv("telemetry_kit.kernel_version", kernel));
+    }
+
+    if let Some(container) = &event.environment.container {
+        attributes.push(string_kv("telemetry_kit.container", container));
+    }
+
+    let flags = [
+        ("telemetry_kit.wsl", event.environment.wsl),
+        ("telemetry_kit.ssh", event.environment.ssh),
+        ("telemetry_kit.tty", event.environment.tty),
+    ];
+    for (key, value) in flags {
+        if let Some(value) = value {
+            attributes.push(kv(key, any_value::Value::BoolValue(value)));
+        }
+    }
+
+    if let Some(terminal) = &event.environment.terminal {
+        attributes.push(string_kv("telemetry_kit.terminal", terminal));
+    }
+
+    attributes
+}
+
+/// Convert a single event into an OTLP log record
+fn to_log_record(event: &Event) -> LogRecord {
+    let severity = match event.event.category.as_deref() {
+        Some("error") => SeverityNumber::Error,
+        _ => SeverityNumber::Info,
+    };
+
+    let mut attributes = vec![
+        string_kv("telemetry_kit.event_id", &event.event_id.to_string()),
+        string_kv("telemetry_kit.event.type", &event.event.event_type),
+        string_kv("telemetry_kit.schema_version", &event.schema_version),
+        string_kv("telemetry_kit.user_id", &event.user_id),
+    ];
+
+    if let Some(category) = &event.event.category {
+        attributes.push(string_kv("telemetry_kit.event.category", category));
+    }
+
+    if let Some(session_id) = &event.session_id {
+        attributes.push(string_kv("session.id", session_id));
+    }
+
+    if let Some(context) = &event.event.context {
+        for (key, value) in context {
+            attributes.push(KeyValue {
+                key: format!("telemetry_kit.context.{}", key),
+                value: Some(json_to_any_value(value)),
+            });
+        }
+    }
+
+    if let Some(trace_id) = &event.event.trace_id {
+        attributes.push(string_kv("telemetry_kit.trace_id", trace_id));
+    }
+
+    if let Some(sample_rate) = event.event.sample_rate {
+        attributes.push(kv(
+            "telemetry_kit.sample_rate",
+            any_value::Value::DoubleValue(sample_rate),
+        ));
+    }
+
+    if let Some(parent_event_id) = &event.event.parent_event_id {
+        attributes.push(string_kv(
+            "telemetry_kit.parent_event_id",
+            &parent_event_id.to_string(),
+        ));
+    }
+
+    if let Some(experiments) = &event.event.experiments {
+        for (experiment, variant) in experiments {
+            attributes.push(string_kv(
+                &format!("telemetry_kit.experiment.{}", experiment),
+                variant,
+            ));
+        }
+    }
+
+    if let Some(rollup) = &event.event.rollup {
+        attributes.push(kv(
+            "telemetry_kit.rollup.count",
+            any_value::Value::IntValue(rollup.count as i64),
+        ));
+        attributes.push(string_kv(
+            "telemetry_kit.rollup.last_timestamp",
+            &rollup.last_timestamp.to_rfc3339(),
+        ));
+        if let Some(total_duration_ms) = rollup.total_duration_ms {
+            attributes.push(kv(
+                "telemetry_kit.rollup.total_duration_ms",
+                any_value::Value::IntValue(total_duration_ms as i64),
+            ));
+        }
+    }
+
+    LogRecord {
+        time_unix_nano: unix_nanos(event.timestamp),
+        observed_time_unix_nano: unix_nanos(chrono::Utc::now()),
+        severity_number: severity as i32,
+        severity_text: severity
+            .as_str_name()
+            .trim_start_matches("SEVERITY_NUMBER_")
+            .to_string(),
+        event_name: event.event.event_type.clone(),
+        body: Some(json_to_any_value(&event.event.data)),
+        attributes,
+        ..Default::default()
+    }
+}
+
+/// Convert arbitrary JSON into an OTLP `AnyValue`
+fn json_to_any_value(value: &serde_json::Value) -> AnyValue {
+    let value = match value {
+        serde_json::Value::Null => None,
+        serde_json::Value::Bool(b) => Some(any_value::Value::BoolValue(*b)),
+        serde_json::Value::Number(n) => Some(match n.as_i64() {
+            Some(i) => any_value::Value::IntValue(i),
+            None => any_value::Value::DoubleValue(n.as_f64().unwrap_or_default()),
+        }),
+        serde_json::Value::String(s) => Some(any_value::Value::StringValue(s.clone())),
+        serde_json::Value::Array(items) => Some(any_value::Value::ArrayValue(ArrayValue {
+            values: items.iter().map(json_to_any_value).collect(),
+        })),
+        serde_json::Value::Object(map) => Some(any_value::Value::KvlistValue(KeyValueList {
+            values: map
+                .iter()
+                .map(|(key, value)| KeyValue {
+                    key: key.clone(),
+                    value: Some(json_to_any_value(value)),
+                })
+                .collect(),
+        })),
+    };
+
+    AnyValue { value }
+}
+
+fn kv(key: &str, value: any_value::Value) -> KeyValue {
+    KeyValue {
+        key: key.to_string(),
+        value: Some(AnyValue { value: Some(value) }),
+    }
+}
+
+fn string_kv(key: &str, value: &str) -> KeyValue {
+    kv(key, any_value::Value::StringValue(value.to_string()))
+}
+
+fn unix_nanos(timestamp: chrono::DateTime<chrono::Utc>) -> u64 {
+    timestamp.timestamp_nanos_opt().unwrap_or_default().max(0) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::*;
+
+    fn create_test_event(service: &str, category: Option<&str>) -> Event {
+        let mut event = Event::test_event(
+            "command_execution",
+            serde_json::json!({
+                "command": "build",
+                "duration_ms": 1234,
+                "flags": ["--release"],
+                "success": true
+            }),
+        );
+        event.service.name = service.to_string();
+        event.event.category = category.map(|c| c.to_string());
+        event
+    }
+
+    fn attribute<'a>(attributes: &'a [KeyValue], key: &str) -> Option<&'a any_value::Value> {
+        attributes
+            .iter()
+            .find(|kv| kv.key == key)
+            .and_then(|kv| kv.value.as_ref())
+            .and_then(|v| v.value.as_ref())
+    }
+
+    #[test]
+    fn test_resource_attributes() {
+        let event = create_test_event("my-cli", Some("usage"));
+        let attributes = to_resource_attributes(&event);
+
+        assert_eq!(
+            attribute(&attributes, "service.name"),
+            Some(&any_value::Value::StringValue("my-cli".to_string()))
+        );
+        assert_eq!(
+            attribute(&attributes, "host.arch"),
+            Some(&any_value::Value::StringValue("x86_64".to_string()))
+        );
+        assert_eq!(
+            attribute(&attributes, "telemetry_kit.ci"),
+            Some(&any_value::Value::BoolValue(false))
+        );
+        assert!(attribute(&attributes, "os.version").is_none());
+    }
+
+    #[test]
+    fn test_log_record_mapping() {
+        let event = create_test_event("my-cli", Some("usage"));
+        let record = to_log_record(&event);
+
+        assert_eq!(record.event_name, "command_execution");
+        assert_eq!(record.severity_number, SeverityNumber::Info as i32);
+        assert_eq!(record.severity_text, "INFO");
+        assert_eq!(
+            attribute(&record.attributes, "telemetry_kit.event.category"),
+            Some(&any_value::Value::StringValue("usage".to_string()))
+        );
+        assert_eq!(
+            attribute(&record.attributes, "session.id"),
+            Some(&any_value::Value::StringValue("sess_test456".to_string()))
+        );
+
+        match record.body.and_then(|b| b.value) {
+            Some(any_value::Value::KvlistValue(list)) => {
+                assert_eq!(
+                    attribute(&list.values, "duration_ms"),
+                    Some(&any_value::Value::IntValue(1234))
+                );
+                assert_eq!(
+                    attribute(&list.values, "success"),
+                    Some(&any_value::Value::BoolValue(true))
+                );
+            }
+            other => panic!("expected kvlist body, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_error_category_severity() {
+        let event = create_test_event("my-cli", Some("error"));
+        let record = to_log_record(&event);
+        assert_eq!(record.severity_number, SeverityNumber::Error as i32);
+    }
+
+    #[test]
+    fn test_groups_by_resource() {
+        let events = vec![
+            create_test_event("service-a", None),
+            create_test_event("service-b", None),
+            create_test_event("service-a", None),
+        ];
+
+        let request = to_export_request(&events);
+        assert_eq!(request.resource_logs.len(), 2);
+        assert_eq!(request.resource_logs[0].scope_logs[0].log_records.len(), 2);
+        assert_eq!(request.resource_logs[1].scope_logs[0].log_records.len(), 1);
+    }
+
+    #[test]
+    fn test_json_encoding() {
+        let events = vec![create_test_event("my-cli", None)];
+        let request = to_export_request(&events);
+        let json = serde_json::to_value(&request).unwrap();
+
+        let record = &json["resourceLogs"][0]["scopeLogs"][0]["logRecords"][0];
+        assert_eq!(record["eventName"], "command_execution");
+        assert!(record["timeUnixNano"].is_string());
+    }
+}
@@ -204,7 +204,7 @@ impl PrivacyManager {
     /// Sanitize a path by removing username components
     pub fn sanitize_path(path: &str) -> String {
         if let Some(home) = dirs::home_dir() {
-            if let Some(home_str) = home.to_str() {
+            if let Some(home_str) = home.to_str().filter(|h| !h.is_empty()) {
                 return path.replace(home_str, "~");
             }
         }
@@ -0,0 +1,160 @@
+//! Event processors that run before events are stored
+//!
+//! An [`EventProcessor`] can enrich, rewrite or drop each event before it reaches the
+//! local buffer. Processors registered with
+//! [`TelemetryBuilder::processor`](crate::TelemetryBuilder::processor) run in the order
+//! they were added. When privacy settings are configured, the built-in privacy
+//! sanitizer runs last, so data added by processors is sanitized too.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use telemetry_kit::event::Event;
+//! use telemetry_kit::processor::EventProcessor;
+//! use telemetry_kit::prelude::*;
+//!
+//! /// Record events under their current names
+//! struct RenameLegacyEvents;
+//!
+//! impl EventProcessor for RenameLegacyEvents {
+//!     fn name(&self) -> &str {
+//!         "rename-legacy-events"
+//!     }
+//!
+//!     fn process(&self, mut event: Event) -> Option<Event> {
+//!         if event.event.event_type == "cmd_run" {
+//!             event.event.event_type = "command_execution".to_string();
+//!         }
+//!         Some(event)
+//!     }
+//! }
+//!
+//! # fn main() -> telemetry_kit::Result<()> {
+//! let telemetry = TelemetryKit::builder()
+//!     .service_name("my-app")?
+//!     .processor(RenameLegacyEvents)
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::event::Event;
+
+#[cfg(feature = "privacy")]
+use crate::privacy::PrivacyManager;
+
+#[cfg(feature = "privacy")]
+use std::sync::Arc;
+
+/// A step in the pipeline between tracking an event and storing it
+pub trait EventProcessor: Send + Sync {
+    /// Short name used in logs and error messages
+    fn name(&self) -> &str;
+
+    /// Process an event, returning `None` to drop it
+    fn process(&self, event: Event) -> Option<Event>;
+}
+
+impl std::fmt::Debug for dyn EventProcessor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventProcessor")
+            .field("name", &self.name())
+            .finish()
+    }
+}
+
+/// Built-in processor applying the path and email sanitization of a [`PrivacyManager`]
+/// to event data and properties
+#[cfg(feature = "privacy")]
+pub(crate) struct PrivacySanitizer(pub(crate) Arc<PrivacyManager>);
+
+#[cfg(feature = "privacy")]
+impl EventProcessor for PrivacySanitizer {
+    fn name(&self) -> &str {
+        "privacy"
+    }
+
+    fn process(&self, mut event: Event) -> Option<Event> {
+        self.0.sanitize_data(&mut event.event.data);
+
+        if let Some(context) = event.event.context.take() {
+            let mut context = serde_json::Value::Object(context);
+            self.0.sanitize_data(&mut context);
+            if let serde_json::Value::Object(context) = context {
+                event.event.context = Some(context);
+            }
+        }
+
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::EventStorage;
+    use uuid::Uuid;
+
+    struct AddBuildInfo;
+
+    impl EventProcessor for AddBuildInfo {
+        fn name(&self) -> &str {
+            "build-info"
+        }
+
+        fn process(&self, mut event: Event) -> Option<Event> {
+            let context = event.event.context.get_or_insert_with(Default::default);
+            context.insert("build_dir".to_string(), "/home/ci/user@example.com".into());
+            Some(event)
+        }
+    }
+
+    struct DropInternalEvents;
+
+    impl EventProcessor for DropInternalEvents {
+        fn name(&self) -> &str {
+            "drop-internal"
+        }
+
+        fn process(&self, event: Event) -> Option<Event> {
+            (!event.event.event_type.starts_with("internal_")).then_some(event)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_processors_run_in_order_before_sanitizer() {
+        let unique_name = format!("test-processor-{}", Uuid::new_v4());
+        let db_path = std::env::temp_dir().join(format!("{}.db", unique_name));
+        let telemetry = crate::TelemetryKit::builder()
+            .service_name(&unique_name)
+            .unwrap()
+            .db_path(&db_path)
+            .sanitize_emails(true)
+            .processor(DropInternalEvents)
+            .processor(AddBuildInfo)
+            .build()
+            .unwrap();
+
+        telemetry
+            .track_custom("internal_debug", serde_json::json!({}))
+            .await
+            .unwrap();
+        telemetry
+            .track_custom("deploy", serde_json::json!({}))
+            .await
+            .unwrap();
+
+        let events = EventStorage::new(&db_path)
+            .unwrap()
+            .get_unsynced(10)
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event.event_type, "deploy");
+
+        // Added by a processor, then sanitized by the privacy processor
+        let build_dir = events[0].event.context.as_ref().unwrap()["build_dir"]
+            .as_str()
+            .unwrap();
+        assert!(build_dir.starts_with("email_"));
+    }
+}
@@ -0,0 +1,274 @@
+//! Deterministic event sampling
+//!
+//! Sampling rules reduce the volume of high-frequency events. Decisions are keyed on
+//! the anonymous user ID and event type, so a given installation is consistently in
+//! or out of the sample, and the rate is recorded on kept events so counts can be
+//! re-weighted on the server.
+//!
+//! A sample rate the remote configuration sets for an event type overrides local
+//! rules. Its global default rate only replaces the local default rate, so events
+//! matched by a local rule, such as the error rule below, keep the rule's rate.
+//!
+//! The same bucketing assigns experiment variants with
+//! [`TelemetryKit::variant`](crate::TelemetryKit::variant).
+//!
+//! # Example
+//!
+//! ```no_run
+//! use telemetry_kit::prelude::*;
+//! use telemetry_kit::sampling::SamplingRule;
+//!
+//! # fn example() -> telemetry_kit::Result<()> {
+//! let telemetry = TelemetryKit::builder()
+//!     .service_name("my-app")?
+//!     // Errors are always kept, even with a lower default rate
+//!     .sampling_rule(SamplingRule::category("error"))
+//!     // Keep 10% of installations' cache lookups
+//!     .sampling_rule(SamplingRule::event_type("feature_used").name("cache_lookup").rate(0.1))
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{Result, TelemetryError};
+use sha2::{Digest, Sha256};
+
+/// A sample rate applied to matching events
+///
+/// A rule matches when every condition set on it matches. Rules are evaluated in
+/// the order they were added and the first match wins.
+#[derive(Debug, Clone)]
+pub struct SamplingRule {
+    event_type: Option<String>,
+    category: Option<String>,
+    name: Option<String>,
+    rate: f64,
+}
+
+impl SamplingRule {
+    /// Match events of a type (e.g. `feature_used`), keeping all of them by default
+    pub fn event_type(event_type: impl Into<String>) -> Self {
+        Self {
+            event_type: Some(event_type.into()),
+            category: None,
+            name: None,
+            rate: 1.0,
+        }
+    }
+
+    /// Match events of a category (e.g. `error`), keeping all of them by default
+    pub fn category(category: impl Into<String>) -> Self {
+        Self {
+            event_type: None,
+            category: Some(category.into()),
+            name: None,
+            rate: 1.0,
+        }
+    }
+
+    /// Only match events with this command, feature or span name
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Set the fraction of installations whose matching events are kept (0.0 to 1.0)
+    pub fn rate(mut self, rate: f64) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    fn matches(&self, event_type: &str, category: Option<&str>, data: &serde_json::Value) -> bool {
+        self.event_type.as_deref().map_or(true, |t| t == event_type)
+            && self
+                .category
+                .as_deref()
+                .map_or(true, |c| Some(c) == category)
+            && self
+                .name
+                .as_deref()
+                .map_or(true, |n| event_name(data) == Some(n))
+    }
+}
+
+/// Sampling rules of a TelemetryKit instance
+#[derive(Debug)]
+pub(crate) struct Sampler {
+    rules: Vec<SamplingRule>,
+    default_rate: f64,
+}
+
+impl Sampler {
+    pub(crate) fn new(rules: Vec<SamplingRule>, default_rate: f64) -> Result<Self> {
+        let rates = std::iter::once(default_rate).chain(rules.iter().map(|r| r.rate));
+        for rate in rates {
+            if !(0.0..=1.0).contains(&rate) {
+                return Err(TelemetryError::invalid_config(
+                    "sample_rate",
+                    &format!("{} is not between 0.0 and 1.0", rate),
+                ));
+            }
+        }
+
+        Ok(Self {
+            rules,
+            default_rate,
+        })
+    }
+
+    /// Decide whether to keep an event, sampling it at no more than `max_rate`
+    ///
+    /// `default_rate` replaces the configured default rate for events no rule
+    /// matches. Returns the sample rate the event was kept at, or `None` if it is
+    /// dropped.
+    pub(crate) fn decide(
+        &self,
+        user_id: &str,
+        event_type: &str,
+        category: Option<&str>,
+        data: &serde_json::Value,
+        default_rate: Option<f64>,
+        max_rate: f64,
+    ) -> Option<f64> {
+        let rate = self
+            .rules
+            .iter()
+            .find(|rule| rule.matches(event_type, category, data))
+            .map_or(default_rate.unwrap_or(self.default_rate), |rule| rule.rate);
+
+        self.decide_at(user_id, event_type, rate.min(max_rate))
+    }
+
+    /// Decide whether to keep an event at a given rate, bypassing the rules
+    pub(crate) fn decide_at(&self, user_id: &str, event_type: &str, rate: f64) -> Option<f64> {
+        if rate >= 1.0 || bucket(user_id, event_type) < rate {
+            Some(rate)
+        } else {
+            None
+        }
+    }
+}
+
+/// Name of the command, feature or span an event is about
+fn event_name(data: &serde_json::Value) -> Option<&str> {
+    ["command", "feature", "name"]
+        .iter()
+        .find_map(|key| data.get(key).and_then(|v| v.as_str()))
+}
+
+/// Stable index of the variant assigned to an installation in an experiment
+///
+/// `count` must not be zero.
+pub(crate) fn variant_index(user_id: &str, experiment: &str, count: usize) -> usize {
+    let position = bucket(user_id, &format!("experiment:{}", experiment));
+    ((position * count as f64) as usize).min(count - 1)
+}
+
+/// Stable position of an installation in `[0, 1)` for a key such as an event type
+fn bucket(user_id: &str, key: &str) -> f64 {
+    let mut hasher = Sha256::new();
+    hasher.update(user_id.as_bytes());
+    hasher.update(b":");
+    hasher.update(key.as_bytes());
+    let hash = hasher.finalize();
+
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&hash[..8]);
+    (u64::from_be_bytes(bytes) >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let sampler = Sampler::new(
+            vec![
+                SamplingRule::event_type("feature_used")
+                    .name("cache_lookup")
+                    .rate(0.0),
+                SamplingRule::category("error"),
+            ],
+            0.0,
+        )
+        .unwrap();
+
+        let lookup = json!({ "feature": "cache_lookup" });
+        let login = json!({ "feature": "login" });
+        assert_eq!(
+            sampler.decide("client_a", "feature_used", None, &lookup, None, 1.0),
+            None
+        );
+        assert_eq!(
+            sampler.decide("client_a", "feature_used", None, &login, None, 1.0),
+            None
+        );
+        assert_eq!(
+            sampler.decide("client_a", "error", Some("error"), &json!({}), None, 1.0),
+            Some(1.0)
+        );
+    }
+
+    #[test]
+    fn test_default_rate_override_only_applies_without_a_rule() {
+        let sampler = Sampler::new(vec![SamplingRule::category("error")], 1.0).unwrap();
+        let data = json!({});
+
+        assert_eq!(
+            sampler.decide("client_a", "custom", None, &data, Some(0.0), 1.0),
+            None
+        );
+        assert_eq!(
+            sampler.decide("client_a", "error", Some("error"), &data, Some(0.0), 1.0),
+            Some(1.0)
+        );
+    }
+
+    #[test]
+    fn test_decisions_are_deterministic_per_user() {
+        let sampler = Sampler::new(
+            vec![SamplingRule::event_type("feature_used").rate(0.5)],
+            1.0,
+        )
+        .unwrap();
+        let data = json!({ "feature": "cache_lookup" });
+
+        let kept = (0..1000)
+            .filter(|i| {
+                let user_id = format!("client_{}", i);
+                let first = sampler.decide(&user_id, "feature_used", None, &data, None, 1.0);
+                assert_eq!(
+                    first,
+                    sampler.decide(&user_id, "feature_used", None, &data, None, 1.0)
+                );
+                first.is_some()
+            })
+            .count();
+
+        assert!((400..600).contains(&kept), "kept {}", kept);
+    }
+
+    #[test]
+    fn test_variants_are_evenly_assigned() {
+        let mut counts = [0usize; 3];
+        for i in 0..3000 {
+            let user_id = format!("client_{}", i);
+            let index = variant_index(&user_id, "new_resolver", 3);
+            assert_eq!(index, variant_index(&user_id, "new_resolver", 3));
+            counts[index] += 1;
+        }
+
+        for count in counts {
+            assert!((850..1150).contains(&count), "counts {:?}", counts);
+        }
+        assert_eq!(variant_index("client_a", "new_resolver", 1), 0);
+    }
+
+    #[test]
+    fn test_invalid_rate() {
+        assert!(Sampler::new(vec![SamplingRule::category("error").rate(1.5)], 1.0).is_err());
+        assert!(Sampler::new(Vec::new(), -0.1).is_err());
+    }
+}
@@ -0,0 +1,204 @@
+//! Session state: the current session ID, its trace ID, active experiment variants
+//! and activity counters
+//!
+//! A session lasts for the lifetime of the process unless an inactivity timeout is
+//! configured, in which case the first event after the timeout starts a new one.
+
+use crate::user::generate_session_id;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Identifiers and experiment variants attached to events of a session
+#[derive(Debug, Clone)]
+pub(crate) struct SessionIds {
+    pub(crate) session_id: String,
+    pub(crate) trace_id: String,
+    pub(crate) experiments: BTreeMap<String, String>,
+}
+
+impl SessionIds {
+    fn generate() -> Self {
+        Self {
+            session_id: generate_session_id(),
+            trace_id: Uuid::new_v4().simple().to_string(),
+            experiments: BTreeMap::new(),
+        }
+    }
+}
+
+/// Summary of a finished session, recorded as a `session_end` event
+#[derive(Debug)]
+pub(crate) struct SessionSummary {
+    pub(crate) ids: SessionIds,
+    duration: Duration,
+    event_count: u64,
+}
+
+impl SessionSummary {
+    /// Data of the `session_end` event
+    pub(crate) fn to_data(&self, reason: &str) -> serde_json::Value {
+        serde_json::json!({
+            "duration_ms": self.duration.as_millis() as u64,
+            "event_count": self.event_count,
+            "reason": reason,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Session {
+    ids: SessionIds,
+    started: Instant,
+    last_activity: Instant,
+    event_count: u64,
+    ended: bool,
+}
+
+impl Session {
+    fn start() -> Self {
+        let now = Instant::now();
+        Self {
+            ids: SessionIds::generate(),
+            started: now,
+            last_activity: now,
+            event_count: 0,
+            ended: false,
+        }
+    }
+
+    fn summary(&self, until: Instant) -> SessionSummary {
+        SessionSummary {
+            ids: self.ids.clone(),
+            duration: until.saturating_duration_since(self.started),
+            event_count: self.event_count,
+        }
+    }
+}
+
+/// Tracks the current session of a TelemetryKit instance
+#[derive(Debug)]
+pub(crate) struct SessionTracker {
+    current: Mutex<Session>,
+    timeout: Option<Duration>,
+}
+
+impl SessionTracker {
+    pub(crate) fn new(timeout: Option<Duration>) -> Self {
+        Self {
+            current: Mutex::new(Session::start()),
+            timeout,
+        }
+    }
+
+    /// Identifiers of the current session, without recording activity
+    pub(crate) fn current(&self) -> SessionIds {
+        match self.current.lock() {
+            Ok(session) => session.ids.clone(),
+            Err(poisoned) => poisoned.into_inner().ids.clone(),
+        }
+    }
+
+    /// Record an event in the current session
+    ///
+    /// If the session has been inactive for longer than the timeout, a new session is
+    /// started first and the summary of the expired one is returned alongside it.
+    pub(crate) fn touch(&self) -> (SessionIds, Option<SessionSummary>) {
+        let mut session = match self.current.lock() {
+            Ok(session) => session,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        let now = Instant::now();
+        let expired = match self.timeout {
+            Some(timeout) if now.duration_since(session.last_activity) > timeout => {
+                // The session ended with its last activity, not when we noticed
+                let summary = (!session.ended).then(|| session.summary(session.last_activity));
+                *session = Session::start();
+                summary
+            }
+            _ => None,
+        };
+
+        session.last_activity = now;
+        session.event_count += 1;
+        (session.ids.clone(), expired)
+    }
+
+    /// Attach an experiment variant to the remaining events of the current session
+    pub(crate) fn set_variant(&self, experiment: String, variant: String) {
+        let mut session = match self.current.lock() {
+            Ok(session) => session,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        session.ids.experiments.insert(experiment, variant);
+    }
+
+    /// End the current session
+    ///
+    /// Returns `None` if it was already ended.
+    #[cfg_attr(not(feature = "sync"), allow(dead_code))]
+    pub(crate) fn end(&self) -> Option<SessionSummary> {
+        let mut session = match self.current.lock() {
+            Ok(session) => session,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        if session.ended {
+            return None;
+        }
+        session.ended = true;
+        Some(session.summary(Instant::now()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_without_timeout_never_rotates() {
+        let tracker = SessionTracker::new(None);
+        let ids = tracker.current();
+
+        for _ in 0..3 {
+            let (current, expired) = tracker.touch();
+            assert_eq!(current.session_id, ids.session_id);
+            assert!(expired.is_none());
+        }
+
+        let summary = tracker.end().unwrap();
+        assert_eq!(summary.to_data("shutdown")["event_count"], 3);
+        assert!(tracker.end().is_none());
+    }
+
+    #[test]
+    fn test_session_rotates_after_inactivity() {
+        let tracker = SessionTracker::new(Some(Duration::from_millis(20)));
+        let (first, _) = tracker.touch();
+
+        std::thread::sleep(Duration::from_millis(50));
+        let (second, expired) = tracker.touch();
+
+        let expired = expired.unwrap();
+        assert_ne!(first.session_id, second.session_id);
+        assert_ne!(first.trace_id, second.trace_id);
+        assert_eq!(expired.ids.session_id, first.session_id);
+        assert_eq!(expired.to_data("inactivity")["event_count"], 1);
+    }
+
+    #[test]
+    fn test_variants_last_for_the_session() {
+        let tracker = SessionTracker::new(Some(Duration::from_millis(20)));
+        tracker.set_variant("new_resolver".to_string(), "b".to_string());
+
+        let (current, _) = tracker.touch();
+        assert_eq!(current.experiments["new_resolver"], "b");
+
+        std::thread::sleep(Duration::from_millis(50));
+        let (rotated, _) = tracker.touch();
+        assert!(rotated.experiments.is_empty());
+    }
+}
@@ -0,0 +1,158 @@
+//! RAII spans that time a unit of work
+//!
+//! A [`Span`] records a `span` event (category `performance`) with its duration and
+//! outcome when it is dropped. Spans created with [`Span::child`] carry the event ID
+//! of their parent, and every span of a session shares the same trace ID, so the
+//! time spent in each step of a command can be reconstructed.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use telemetry_kit::prelude::*;
+//!
+//! # fn resolve_deps() -> std::io::Result<()> { Ok(()) }
+//! # fn compile() {}
+//! # fn example(telemetry: &TelemetryKit) {
+//! let mut build = telemetry.span("build");
+//! build.set_field("target", "release");
+//!
+//! {
+//!     let mut deps = build.child("resolve_deps");
+//!     deps.set_success(resolve_deps().is_ok());
+//! } // `resolve_deps` is recorded here
+//!
+//! compile();
+//! // `build` is recorded when it goes out of scope
+//! # }
+//! ```
+
+use crate::telemetry::TelemetryKit;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::time::Instant;
+use uuid::Uuid;
+
+/// A timed unit of work, recorded when dropped
+///
+/// Unless set with [`set_success`](Span::set_success), a span is successful when
+/// it ends normally and failed when it is dropped during a panic.
+#[must_use = "a span is recorded when it is dropped; bind it to a variable"]
+pub struct Span {
+    kit: TelemetryKit,
+    name: String,
+    event_id: Uuid,
+    parent_event_id: Option<Uuid>,
+    started_at: DateTime<Utc>,
+    start: Instant,
+    success: Option<bool>,
+    fields: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Span {
+    pub(crate) fn new(kit: TelemetryKit, name: String, parent_event_id: Option<Uuid>) -> Self {
+        Self {
+            kit,
+            name,
+            event_id: Uuid::new_v4(),
+            parent_event_id,
+            started_at: Utc::now(),
+            start: Instant::now(),
+            success: None,
+            fields: serde_json::Map::new(),
+        }
+    }
+
+    /// Event ID the span will be recorded with
+    pub fn id(&self) -> Uuid {
+        self.event_id
+    }
+
+    /// Start a nested span
+    pub fn child(&self, name: impl Into<String>) -> Span {
+        Span::new(self.kit.clone(), name.into(), Some(self.event_id))
+    }
+
+    /// Set whether the work succeeded
+    pub fn set_success(&mut self, success: bool) {
+        self.success = Some(success);
+    }
+
+    /// Attach a field to the span
+    pub fn set_field<T: Serialize + ?Sized>(&mut self, key: impl Into<String>, value: &T) {
+        self.fields
+            .insert(key.into(), crate::event::field_value(value));
+    }
+
+    /// End the span now instead of when it goes out of scope
+    pub fn end(self) {}
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let mut data = serde_json::json!({
+            "name": self.name,
+            "duration_ms": self.start.elapsed().as_millis() as u64,
+            "success": self.success.unwrap_or(!std::thread::panicking()),
+        });
+
+        if !self.fields.is_empty() {
+            data["fields"] = serde_json::Value::Object(std::mem::take(&mut self.fields));
+        }
+
+        if let Err(e) =
+            self.kit
+                .track_span_detached(self.event_id, self.parent_event_id, self.started_at, data)
+        {
+            eprintln!("Failed to record span: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::storage::EventStorage;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_nested_spans() {
+        let unique_name = format!("test-span-{}", Uuid::new_v4());
+        let db_path = std::env::temp_dir().join(format!("{}.db", unique_name));
+        let telemetry = crate::TelemetryKit::builder()
+            .service_name(&unique_name)
+            .unwrap()
+            .db_path(&db_path)
+            .auto_sync(false)
+            .build()
+            .unwrap();
+
+        let mut build = telemetry.span("build");
+        build.set_field("target", "release");
+        let build_id = build.id();
+        {
+            let mut deps = build.child("resolve_deps");
+            deps.set_success(false);
+        }
+        build.end();
+
+        let events = EventStorage::new(&db_path)
+            .unwrap()
+            .get_unsynced(10)
+            .unwrap();
+        assert_eq!(events.len(), 2);
+
+        let (deps, build) = (&events[0], &events[1]);
+        assert_eq!(deps.event.data["name"], "resolve_deps");
+        assert_eq!(deps.event.data["success"], false);
+        assert_eq!(deps.event.parent_event_id, Some(build_id));
+
+        assert_eq!(build.event_id, build_id);
+        assert_eq!(build.event.event_type, "span");
+        assert_eq!(build.event.data["success"], true);
+        assert_eq!(build.event.data["fields"]["target"], "release");
+        assert!(build.event.parent_event_id.is_none());
+
+        assert!(deps.event.trace_id.is_some());
+        assert_eq!(deps.event.trace_id, build.event.trace_id);
+        assert!(build.timestamp <= deps.timestamp);
+    }
+}
@@ -1,14 +1,88 @@
 //! SQLite storage for buffering events
+//!
+//! The buffer is unbounded by default. With [`BufferLimits`] it is capped by event
+//! count and/or size: already-synced events are removed first, then unsynced events
+//! according to the [`OverflowPolicy`]. Dropped unsynced events are counted by
+//! category and reported with the next synced batch.
 
 use crate::error::Result;
 use crate::event::Event;
 use chrono::Utc;
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
+/// Category recorded for dropped events without one
+const UNCATEGORIZED: &str = "uncategorized";
+
+/// Drop priority of an event's category, lowest dropped first
+///
+/// Must match [`priority`].
+const PRIORITY_SQL: &str =
+    "CASE WHEN category IN ('performance', 'metrics') THEN 0 WHEN category = 'error' THEN 2 ELSE 1 END";
+
+/// Drop priority of a category: spans and metrics summaries are dropped before
+/// everything else, errors and crashes last
+fn priority(category: Option<&str>) -> i64 {
+    match category {
+        Some("performance" | "metrics") => 0,
+        Some("error") => 2,
+        _ => 1,
+    }
+}
+
+/// What to drop when the buffer is full
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Drop the oldest unsynced events to make room (default)
+    #[default]
+    DropOldest,
+    /// Keep the buffered events and drop new ones
+    DropNewest,
+    /// Drop the oldest events of the lowest-priority category first
+    ///
+    /// Spans and metrics summaries go first and errors and crashes last; a new event
+    /// is dropped instead if everything buffered has a higher priority.
+    DropLowPriority,
+}
+
+/// Bounds on the local event buffer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BufferLimits {
+    /// Maximum number of buffered events
+    pub max_events: Option<usize>,
+    /// Maximum total size of buffered events in bytes (serialized JSON)
+    pub max_bytes: Option<u64>,
+    /// What to drop when a limit is reached
+    pub overflow_policy: OverflowPolicy,
+}
+
+impl BufferLimits {
+    /// Check whether a buffer of this many events and bytes is over the limits
+    fn exceeded_by(&self, events: usize, bytes: u64) -> bool {
+        self.max_events.is_some_and(|max| events > max)
+            || self.max_bytes.is_some_and(|max| bytes > max)
+    }
+
+    fn is_unbounded(&self) -> bool {
+        self.max_events.is_none() && self.max_bytes.is_none()
+    }
+}
+
+/// Buffered event that may be deleted to make room
+struct Victim {
+    id: i64,
+    size: u64,
+    category: Option<String>,
+    priority: i64,
+    synced: bool,
+}
+
 /// SQLite storage for buffering telemetry events
 pub struct EventStorage {
     conn: Connection,
+    limits: BufferLimits,
+    created: bool,
 }
 
 // SAFETY: EventStorage is always used behind Arc<RwLock<>> which ensures
@@ -30,8 +104,12 @@ impl EventStorage {
 
         let conn = Connection::open(&path)?;
 
-        let storage = Self { conn };
-        storage.initialize_schema()?;
+        let mut storage = Self {
+            conn,
+            limits: BufferLimits::default(),
+            created: false,
+        };
+        storage.created = storage.initialize_schema()?;
 
         Ok(storage)
     }
@@ -39,13 +117,39 @@ impl EventStorage {
     /// Create an in-memory storage (for testing)
     pub fn in_memory() -> Result<Self> {
         let conn = Connection::open_in_memory()?;
-        let storage = Self { conn };
-        storage.initialize_schema()?;
+        let mut storage = Self {
+            conn,
+            limits: BufferLimits::default(),
+            created: false,
+        };
+        storage.created = storage.initialize_schema()?;
         Ok(storage)
     }
 
-    /// Initialize the database schema
-    fn initialize_schema(&self) -> Result<()> {
+    /// Bound the buffer; limits apply from the next insert
+    pub fn with_limits(mut self, limits: BufferLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Current buffer limits
+    pub fn limits(&self) -> BufferLimits {
+        self.limits
+    }
+
+    /// Whether the database was created when this storage was opened
+    pub fn is_new(&self) -> bool {
+        self.created
+    }
+
+    /// Initialize the database schema, returning whether the database was new
+    fn initialize_schema(&self) -> Result<bool> {
+        let created: bool = self.conn.query_row(
+            "SELECT COUNT(*) = 0 FROM sqlite_master WHERE type = 'table' AND name = 'events'",
+            [],
+            |row| row.get(0),
+        )?;
+
         self.conn.execute_batch(
             r#"
             CREATE TABLE IF NOT EXISTS events (
@@ -54,30 +158,200 @@ impl EventStorage {
                 event_data TEXT NOT NULL,
                 created_at INTEGER NOT NULL,
                 synced_at INTEGER,
-                retry_count INTEGER DEFAULT 0
+                retry_count INTEGER DEFAULT 0,
+                category TEXT,
+                size INTEGER NOT NULL DEFAULT 0
             );
 
             CREATE INDEX IF NOT EXISTS idx_synced_at ON events(synced_at);
             CREATE INDEX IF NOT EXISTS idx_created_at ON events(created_at);
+
+            CREATE TABLE IF NOT EXISTS dropped_events (
+                category TEXT PRIMARY KEY,
+                count INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS metadata (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );
             "#,
         )?;
 
+        // Databases created before buffer limits lack the category and size columns
+        let has_size: bool = self.conn.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('events') WHERE name = 'size'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_size {
+            self.conn.execute_batch(
+                r#"
+                ALTER TABLE events ADD COLUMN category TEXT;
+                ALTER TABLE events ADD COLUMN size INTEGER NOT NULL DEFAULT 0;
+                UPDATE events SET
+                    category = json_extract(event_data, '$.event.category'),
+                    size = length(CAST(event_data AS BLOB));
+                "#,
+            )?;
+        }
+
+        Ok(created)
+    }
+
+    /// Read a value from the metadata table
+    pub fn get_metadata(&self, key: &str) -> Result<Option<String>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT value FROM metadata WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Write a value to the metadata table, replacing any previous value
+    pub fn set_metadata(&self, key: &str, value: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO metadata (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![key, value],
+        )?;
         Ok(())
     }
 
     /// Insert an event into the storage
+    ///
+    /// If the buffer is bounded and full, events are dropped according to the
+    /// [`OverflowPolicy`]; this may be the new event itself.
     pub fn insert(&self, event: &Event) -> Result<()> {
         let event_json = serde_json::to_string(event)?;
         let created_at = Utc::now().timestamp();
+        let category = event.event.category.as_deref();
+        let size = event_json.len() as u64;
+
+        if !self.make_room(category, size)? {
+            return self.record_dropped(category);
+        }
+
+        self.conn.execute(
+            "INSERT INTO events (event_id, event_data, created_at, category, size) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![event.event_id.to_string(), event_json, created_at, category, size],
+        )?;
+
+        Ok(())
+    }
+
+    /// Delete buffered events until one more of `size` bytes fits
+    ///
+    /// Returns `false` if the new event should be dropped instead.
+    fn make_room(&self, category: Option<&str>, size: u64) -> Result<bool> {
+        if self.limits.is_unbounded() {
+            return Ok(true);
+        }
+        if self.limits.exceeded_by(1, size) {
+            return Ok(false);
+        }
 
+        let (mut count, mut bytes) = self.conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(size), 0) FROM events",
+            [],
+            |row| Ok((row.get::<_, usize>(0)?, row.get::<_, u64>(1)?)),
+        )?;
+
+        while self.limits.exceeded_by(count + 1, bytes + size) {
+            let Some(victim) = self.next_victim()? else {
+                return Ok(false);
+            };
+
+            if !victim.synced {
+                let keep_buffered = match self.limits.overflow_policy {
+                    OverflowPolicy::DropOldest => false,
+                    OverflowPolicy::DropNewest => true,
+                    OverflowPolicy::DropLowPriority => victim.priority > priority(category),
+                };
+                if keep_buffered {
+                    return Ok(false);
+                }
+                self.record_dropped(victim.category.as_deref())?;
+            }
+
+            self.conn
+                .execute("DELETE FROM events WHERE id = ?1", params![victim.id])?;
+            count -= 1;
+            bytes = bytes.saturating_sub(victim.size);
+        }
+
+        Ok(true)
+    }
+
+    /// Next event to delete: synced events first, then by the overflow policy
+    fn next_victim(&self) -> Result<Option<Victim>> {
+        let order = match self.limits.overflow_policy {
+            OverflowPolicy::DropLowPriority => "priority, id",
+            OverflowPolicy::DropOldest | OverflowPolicy::DropNewest => "id",
+        };
+        let query = format!(
+            "SELECT id, size, category, {} AS priority, synced_at IS NOT NULL AS synced
+             FROM events ORDER BY synced DESC, {} LIMIT 1",
+            PRIORITY_SQL, order
+        );
+
+        let victim = self
+            .conn
+            .query_row(&query, [], |row| {
+                Ok(Victim {
+                    id: row.get(0)?,
+                    size: row.get(1)?,
+                    category: row.get(2)?,
+                    priority: row.get(3)?,
+                    synced: row.get(4)?,
+                })
+            })
+            .optional()?;
+
+        Ok(victim)
+    }
+
+    /// Count a dropped unsynced event
+    fn record_dropped(&self, category: Option<&str>) -> Result<()> {
         self.conn.execute(
-            "INSERT INTO events (event_id, event_data, created_at) VALUES (?1, ?2, ?3)",
-            params![event.event_id.to_string(), event_json, created_at],
+            "INSERT INTO dropped_events (category, count) VALUES (?1, 1)
+             ON CONFLICT(category) DO UPDATE SET count = count + 1",
+            params![category.unwrap_or(UNCATEGORIZED)],
         )?;
 
         Ok(())
     }
 
+    /// Counts of dropped events by category that have not been reported yet
+    pub fn dropped_counts(&self) -> Result<BTreeMap<String, u64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT category, count FROM dropped_events WHERE count > 0")?;
+
+        let counts = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<std::result::Result<BTreeMap<_, _>, _>>()?;
+
+        Ok(counts)
+    }
+
+    /// Subtract reported counts, keeping events dropped since they were read
+    pub fn clear_dropped(&self, reported: &BTreeMap<String, u64>) -> Result<()> {
+        for (category, count) in reported {
+            self.conn.execute(
+                "UPDATE dropped_events SET count = MAX(count - ?2, 0) WHERE category = ?1",
+                params![category, count],
+            )?;
+        }
+        self.conn
+            .execute("DELETE FROM dropped_events WHERE count = 0", [])?;
+
+        Ok(())
+    }
+
     /// Get unsynced events (up to a limit)
     pub fn get_unsynced(&self, limit: usize) -> Result<Vec<Event>> {
         let mut stmt = self.conn.prepare(
@@ -100,6 +374,27 @@ impl EventStorage {
         Ok(parsed_events)
     }
 
+    /// Get every stored event, synced or not, oldest first
+    pub fn get_all(&self) -> Result<Vec<Event>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT event_data FROM events ORDER BY created_at ASC, rowid ASC")?;
+
+        let events = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        events
+            .iter()
+            .map(|event_json| Ok(serde_json::from_str(event_json)?))
+            .collect()
+    }
+
+    /// Delete every stored event
+    pub fn clear(&self) -> Result<usize> {
+        Ok(self.conn.execute("DELETE FROM events", [])?)
+    }
+
     /// Mark events as synced
     pub fn mark_synced(&self, event_ids: &[uuid::Uuid]) -> Result<()> {
         let synced_at = Utc::now().timestamp();
@@ -185,37 +480,9 @@ mod tests {
     use uuid::Uuid;
 
     fn create_test_event() -> Event {
-        Event {
-            schema_version: SCHEMA_VERSION.to_string(),
-            event_id: Uuid::new_v4(),
-            timestamp: Utc::now(),
-            service: ServiceInfo {
-                name: "test-service".to_string(),
-                version: "1.0.0".to_string(),
-                language: "rust".to_string(),
-                language_version: Some("1.75.0".to_string()),
-            },
-            user_id: "client_test123".to_string(),
-            session_id: Some("sess_test456".to_string()),
-            environment: Environment {
-                os: "linux".to_string(),
-                os_version: None,
-                arch: Some("x86_64".to_string()),
-                ci: Some(false),
-                shell: None,
-            },
-            event: EventData {
-                event_type: "test_event".to_string(),
-                category: Some("test".to_string()),
-                data: serde_json::json!({"test": true}),
-            },
-            metadata: Metadata {
-                sdk_version: "0.1.0".to_string(),
-                transmission_timestamp: Utc::now(),
-                batch_size: 1,
-                retry_count: 0,
-            },
-        }
+        let mut event = Event::test_event("test_event", serde_json::json!({"test": true}));
+        event.event.category = Some("test".to_string());
+        event
     }
 
     #[test]
@@ -255,4 +522,195 @@ mod tests {
         // Note: We can't easily verify the retry count increased
         // without adding a method to retrieve it, but the function runs without error
     }
+
+    fn event_with_category(category: Option<&str>) -> Event {
+        let mut event = create_test_event();
+        event.event.category = category.map(str::to_string);
+        event
+    }
+
+    fn limited(max_events: usize, overflow_policy: OverflowPolicy) -> EventStorage {
+        EventStorage::in_memory()
+            .unwrap()
+            .with_limits(BufferLimits {
+                max_events: Some(max_events),
+                max_bytes: None,
+                overflow_policy,
+            })
+    }
+
+    #[test]
+    fn test_drop_oldest() {
+        let storage = limited(2, OverflowPolicy::DropOldest);
+        let events: Vec<Event> = (0..3).map(|_| create_test_event()).collect();
+        for event in &events {
+            storage.insert(event).unwrap();
+        }
+
+        let ids: Vec<Uuid> = storage
+            .get_unsynced(10)
+            .unwrap()
+            .iter()
+            .map(|e| e.event_id)
+            .collect();
+        assert_eq!(ids, vec![events[1].event_id, events[2].event_id]);
+        assert_eq!(storage.dropped_counts().unwrap()["test"], 1);
+    }
+
+    #[test]
+    fn test_drop_newest() {
+        let storage = limited(2, OverflowPolicy::DropNewest);
+        let events: Vec<Event> = (0..3).map(|_| create_test_event()).collect();
+        for event in &events {
+            storage.insert(event).unwrap();
+        }
+
+        let ids: Vec<Uuid> = storage
+            .get_unsynced(10)
+            .unwrap()
+            .iter()
+            .map(|e| e.event_id)
+            .collect();
+        assert_eq!(ids, vec![events[0].event_id, events[1].event_id]);
+        assert_eq!(storage.dropped_counts().unwrap()["test"], 1);
+    }
+
+    #[test]
+    fn test_drop_low_priority() {
+        let storage = limited(2, OverflowPolicy::DropLowPriority);
+        storage.insert(&event_with_category(Some("error"))).unwrap();
+        storage
+            .insert(&event_with_category(Some("performance")))
+            .unwrap();
+
+        // A usage event replaces the span
+        storage.insert(&event_with_category(Some("usage"))).unwrap();
+        // A metrics summary is dropped rather than replacing anything more important
+        storage
+            .insert(&event_with_category(Some("metrics")))
+            .unwrap();
+
+        let categories: Vec<Option<String>> = storage
+            .get_unsynced(10)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.event.category)
+            .collect();
+        assert_eq!(
+            categories,
+            vec![Some("error".to_string()), Some("usage".to_string())]
+        );
+
+        let dropped = storage.dropped_counts().unwrap();
+        assert_eq!(dropped["performance"], 1);
+        assert_eq!(dropped["metrics"], 1);
+    }
+
+    #[test]
+    fn test_synced_events_are_removed_first() {
+        let storage = limited(2, OverflowPolicy::DropNewest);
+        let synced = create_test_event();
+        storage.insert(&synced).unwrap();
+        storage.mark_synced(&[synced.event_id]).unwrap();
+        storage.insert(&create_test_event()).unwrap();
+        storage.insert(&create_test_event()).unwrap();
+
+        assert_eq!(storage.total_count().unwrap(), 2);
+        assert_eq!(storage.unsynced_count().unwrap(), 2);
+        assert!(storage.dropped_counts().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_max_bytes() {
+        let event = create_test_event();
+        let size = serde_json::to_string(&event).unwrap().len() as u64;
+        let storage = EventStorage::in_memory()
+            .unwrap()
+            .with_limits(BufferLimits {
+                max_events: None,
+                max_bytes: Some(size * 2 + size / 2),
+                overflow_policy: OverflowPolicy::DropOldest,
+            });
+
+        for _ in 0..5 {
+            storage.insert(&create_test_event()).unwrap();
+        }
+        assert_eq!(storage.unsynced_count().unwrap(), 2);
+        assert_eq!(storage.dropped_counts().unwrap()["test"], 3);
+    }
+
+    #[test]
+    fn test_clear_dropped_keeps_newer_drops() {
+        let storage = limited(1, OverflowPolicy::DropNewest);
+        storage.insert(&create_test_event()).unwrap();
+        storage.insert(&create_test_event()).unwrap();
+
+        let reported = storage.dropped_counts().unwrap();
+        storage.insert(&event_with_category(None)).unwrap();
+        storage.clear_dropped(&reported).unwrap();
+
+        let dropped = storage.dropped_counts().unwrap();
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(dropped[UNCATEGORIZED], 1);
+    }
+
+    #[test]
+    fn test_metadata() {
+        let path = std::env::temp_dir().join(format!("test-storage-{}.db", Uuid::new_v4()));
+
+        let storage = EventStorage::new(&path).unwrap();
+        assert!(storage.is_new());
+        assert_eq!(storage.get_metadata("key").unwrap(), None);
+
+        storage.set_metadata("key", "first").unwrap();
+        storage.set_metadata("key", "second").unwrap();
+        drop(storage);
+
+        let storage = EventStorage::new(&path).unwrap();
+        assert!(!storage.is_new());
+        assert_eq!(
+            storage.get_metadata("key").unwrap().as_deref(),
+            Some("second")
+        );
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_schema_upgrade() {
+        let path = std::env::temp_dir().join(format!("test-storage-{}.db", Uuid::new_v4()));
+        let event = create_test_event();
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE events (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    event_id TEXT UNIQUE NOT NULL,
+                    event_data TEXT NOT NULL,
+                    created_at INTEGER NOT NULL,
+                    synced_at INTEGER,
+                    retry_count INTEGER DEFAULT 0
+                );",
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO events (event_id, event_data, created_at) VALUES (?1, ?2, 0)",
+                params![
+                    event.event_id.to_string(),
+                    serde_json::to_string(&event).unwrap()
+                ],
+            )
+            .unwrap();
+        }
+
+        let storage = EventStorage::new(&path).unwrap().with_limits(BufferLimits {
+            max_events: Some(1),
+            max_bytes: None,
+            overflow_policy: OverflowPolicy::DropOldest,
+        });
+        storage.insert(&create_test_event()).unwrap();
+        assert_eq!(storage.dropped_counts().unwrap()["test"], 1);
+
+        let _ = std::fs::remove_file(&path);
+    }
 }
@@ -6,6 +6,7 @@ use sha2::Sha256;
 type HmacSha256 = Hmac<Sha256>;
 
 /// HMAC authentication helper
+#[derive(Clone)]
 pub struct HmacAuth {
     secret: String,
 }
@@ -55,6 +56,29 @@ impl HmacAuth {
         let expected = self.sign(timestamp, nonce, body);
         constant_time_eq(signature.as_bytes(), expected.as_bytes())
     }
+
+    /// Verify the signature of a server response
+    ///
+    /// Message format: `{timestamp}:{body}`
+    ///
+    /// # Arguments
+    /// * `timestamp` - Unix timestamp in seconds, from the `X-Timestamp` header
+    /// * `body` - Response body
+    /// * `signature` - Signature from the `X-Signature` header
+    ///
+    /// # Returns
+    /// `true` if signature is valid, `false` otherwise
+    pub fn verify_response(&self, timestamp: &str, body: &str, signature: &str) -> bool {
+        let message = format!("{}:{}", timestamp, body);
+
+        let mut mac = HmacSha256::new_from_slice(self.secret.as_bytes())
+            .expect("HMAC can take key of any size");
+
+        mac.update(message.as_bytes());
+
+        let expected = hex::encode(mac.finalize().into_bytes());
+        constant_time_eq(signature.as_bytes(), expected.as_bytes())
+    }
 }
 
 /// Constant-time comparison to prevent timing attacks
@@ -120,6 +144,21 @@ mod tests {
         assert_eq!(sig1, sig2);
     }
 
+    #[test]
+    fn test_response_verification() {
+        let auth = HmacAuth::new("test_secret_key");
+        let timestamp = "1732003200";
+        let body = r#"{"enabled":false}"#;
+
+        let mut mac = HmacSha256::new_from_slice(b"test_secret_key").unwrap();
+        mac.update(format!("{}:{}", timestamp, body).as_bytes());
+        let signature = hex::encode(mac.finalize().into_bytes());
+
+        assert!(auth.verify_response(timestamp, body, &signature));
+        assert!(!auth.verify_response(timestamp, r#"{"enabled":true}"#, &signature));
+        assert!(!HmacAuth::new("other_secret").verify_response(timestamp, body, &signature));
+    }
+
     #[test]
     fn test_constant_time_eq() {
         assert!(constant_time_eq(b"hello", b"hello"));
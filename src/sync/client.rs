@@ -1,24 +1,34 @@
 //! Sync client for pushing events to telemetry-kit.dev
 
 use super::{
-    auth::HmacAuth, config::SyncConfig, retry::RetryStrategy, ErrorResponse, SyncResponse,
+    auth::HmacAuth, config::SyncConfig, retry::RetryStrategy, ErrorResponse, RemoteConfig,
+    RemoteConfigCache, SyncResponse,
 };
 use crate::error::{Result, TelemetryError};
 use crate::event::EventBatch;
+use crate::exporter::{ExportFuture, ExportResult, Exporter, RejectedEvent};
 use chrono::Utc;
 use reqwest::{header::HeaderMap, Client as HttpClient, StatusCode};
+use std::sync::Arc;
 use std::time::Duration;
 use uuid::Uuid;
 
 const SDK_VERSION: &str = env!("CARGO_PKG_VERSION");
 const SCHEMA_VERSION: &str = "1.0.0";
 
+/// Maximum age of a signed remote configuration response, in seconds
+const MAX_RESPONSE_AGE_SECS: i64 = 600;
+
 /// Sync client for pushing events to the server
+///
+/// Clones share the HTTP connection pool and the remote configuration cache.
+#[derive(Clone)]
 pub struct SyncClient {
     config: SyncConfig,
     auth: HmacAuth,
     http_client: HttpClient,
     retry_strategy: RetryStrategy,
+    remote_config: Arc<RemoteConfigCache>,
 }
 
 impl SyncClient {
@@ -36,9 +46,16 @@ impl SyncClient {
             auth,
             http_client,
             retry_strategy,
+            remote_config: Arc::default(),
         })
     }
 
+    /// Share a remote configuration cache with `TelemetryKit`
+    pub(crate) fn with_remote_config_cache(mut self, cache: Arc<RemoteConfigCache>) -> Self {
+        self.remote_config = cache;
+        self
+    }
+
     /// Sync a batch of events to the server
     ///
     /// This method handles:
@@ -173,6 +190,126 @@ impl SyncClient {
     pub fn config(&self) -> &SyncConfig {
         &self.config
     }
+
+    /// The latest remote configuration, fetched or loaded from the cache
+    pub fn remote_config(&self) -> Option<RemoteConfig> {
+        self.remote_config.with(Clone::clone)
+    }
+
+    pub(crate) fn remote_config_cache(&self) -> &RemoteConfigCache {
+        &self.remote_config
+    }
+
+    /// Fetch the remote configuration for a version of the app
+    ///
+    /// The response signature is verified with the API secret before the
+    /// configuration is cached and applied to subsequent events.
+    pub async fn fetch_remote_config(&self, service_version: &str) -> Result<RemoteConfig> {
+        let response = self
+            .http_client
+            .get(self.config.remote_config_url())
+            .query(&[("version", service_version)])
+            .bearer_auth(&self.config.token)
+            .header(
+                "X-SDK-Version",
+                format!("telemetry-kit-rust/{}", SDK_VERSION),
+            )
+            .header("X-Clacks-Overhead", "GNU Terry Pratchett")
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(TelemetryError::ServerError {
+                status: status.as_u16(),
+                message: error_text,
+            });
+        }
+
+        let header = |name: &str| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        };
+        let (Some(timestamp), Some(signature)) = (header("X-Timestamp"), header("X-Signature"))
+        else {
+            return Err(TelemetryError::Auth(
+                "Remote config response is not signed".to_string(),
+            ));
+        };
+        let body = response.text().await?;
+
+        if !self.auth.verify_response(&timestamp, &body, &signature) {
+            return Err(TelemetryError::Auth(
+                "Invalid remote config signature".to_string(),
+            ));
+        }
+
+        let age = timestamp
+            .parse::<i64>()
+            .map(|t| (Utc::now().timestamp() - t).abs())
+            .unwrap_or(i64::MAX);
+        if age > MAX_RESPONSE_AGE_SECS {
+            return Err(TelemetryError::Auth(
+                "Remote config signature has expired".to_string(),
+            ));
+        }
+
+        let config: RemoteConfig = serde_json::from_str(&body)?;
+        config.validate()?;
+        self.remote_config.store(config.clone());
+        Ok(config)
+    }
+
+    /// Fetch the remote configuration when enabled and the cached one is due for a refresh
+    ///
+    /// Failures are ignored: the cached configuration stays in use.
+    pub(crate) async fn refresh_remote_config(&self) {
+        if !self.config.remote_config || !self.remote_config.begin_refresh() {
+            return;
+        }
+
+        if let Some(version) = self.remote_config.service_version() {
+            let _ = self.fetch_remote_config(version).await;
+        }
+    }
+}
+
+impl Exporter for SyncClient {
+    fn name(&self) -> &str {
+        "telemetry-kit"
+    }
+
+    fn batch_size(&self) -> usize {
+        self.config.batch_size
+    }
+
+    fn export<'a>(&'a self, batch: &'a EventBatch) -> ExportFuture<'a> {
+        Box::pin(async move {
+            match self.sync(batch.clone()).await? {
+                SyncResponse::Success { .. } => Ok(ExportResult::accept_all(batch)),
+                SyncResponse::Partial { errors, .. } => {
+                    let rejected: Vec<RejectedEvent> = errors
+                        .into_iter()
+                        .map(|e| RejectedEvent {
+                            event_id: e.event_id,
+                            reason: format!("{}: {}", e.error, e.message),
+                        })
+                        .collect();
+                    let accepted = batch
+                        .events
+                        .iter()
+                        .map(|e| e.event_id)
+                        .filter(|id| !rejected.iter().any(|r| &r.event_id == id))
+                        .collect();
+                    Ok(ExportResult { accepted, rejected })
+                }
+            }
+        })
+    }
 }
 
 /// Check if DNT (Do Not Track) is enabled
@@ -209,6 +346,18 @@ mod tests {
         assert!(client.is_ok());
     }
 
+    #[test]
+    fn test_clones_share_remote_config() {
+        let client = SyncClient::new(create_test_config()).unwrap();
+        let clone = client.clone();
+
+        client.remote_config_cache().store(RemoteConfig {
+            enabled: false,
+            ..Default::default()
+        });
+        assert_eq!(clone.remote_config().map(|c| c.enabled), Some(false));
+    }
+
     #[test]
     fn test_empty_batch() {
         let rt = tokio::runtime::Runtime::new().unwrap();
@@ -44,6 +44,9 @@ pub struct SyncConfig {
 
     /// Enable DNT (Do Not Track) check
     pub respect_dnt: bool,
+
+    /// Fetch the remote configuration (kill switch, sample rates) alongside syncs
+    pub remote_config: bool,
 }
 
 impl SyncConfig {
@@ -60,6 +63,14 @@ impl SyncConfig {
         )
     }
 
+    /// Get the full remote configuration URL
+    pub fn remote_config_url(&self) -> String {
+        format!(
+            "{}/v1/config/{}/{}",
+            self.endpoint, self.org_id, self.app_id
+        )
+    }
+
     /// Validate the configuration
     pub fn validate(&self) -> Result<()> {
         if self.token.is_empty() {
@@ -102,6 +113,7 @@ pub struct SyncConfigBuilder {
     max_retries: Option<u32>,
     sync_interval_secs: Option<u64>,
     respect_dnt: Option<bool>,
+    remote_config: Option<bool>,
 }
 
 impl SyncConfigBuilder {
@@ -196,6 +208,12 @@ impl SyncConfigBuilder {
         self
     }
 
+    /// Enable/disable fetching the remote configuration (default: enabled)
+    pub fn remote_config(mut self, enabled: bool) -> Self {
+        self.remote_config = Some(enabled);
+        self
+    }
+
     /// Build the configuration
     pub fn build(self) -> Result<SyncConfig> {
         let config = SyncConfig {
@@ -218,6 +236,7 @@ impl SyncConfigBuilder {
             max_retries: self.max_retries.unwrap_or(5),
             sync_interval_secs: self.sync_interval_secs.unwrap_or(3600), // 1 hour default
             respect_dnt: self.respect_dnt.unwrap_or(true),
+            remote_config: self.remote_config.unwrap_or(true),
         };
 
         config.validate()?;
@@ -244,6 +263,7 @@ mod tests {
         assert_eq!(config.endpoint, DEFAULT_ENDPOINT);
         assert_eq!(config.batch_size, DEFAULT_BATCH_SIZE);
         assert!(config.respect_dnt);
+        assert!(config.remote_config);
     }
 
     #[test]
@@ -278,6 +298,11 @@ mod tests {
         assert!(url.contains("/v1/ingest/"));
         assert!(url.contains("550e8400-e29b-41d4-a716-446655440000"));
         assert!(url.contains("7c9e6679-7425-40de-944b-e07fc1f90ae7"));
+
+        let url = config.remote_config_url();
+        assert!(url.ends_with(
+            "/v1/config/550e8400-e29b-41d4-a716-446655440000/7c9e6679-7425-40de-944b-e07fc1f90ae7"
+        ));
     }
 
     #[test]
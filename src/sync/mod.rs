@@ -6,13 +6,17 @@
 mod auth;
 mod client;
 mod config;
+mod remote_config;
 mod retry;
 
 pub use auth::HmacAuth;
 pub use client::SyncClient;
-pub use config::{SyncConfig, SyncConfigBuilder};
+pub use config::{SyncConfig, SyncConfigBuilder, DEFAULT_BATCH_SIZE, MAX_BATCH_SIZE};
+pub use remote_config::RemoteConfig;
 pub use retry::RetryStrategy;
 
+pub(crate) use remote_config::RemoteConfigCache;
+
 use serde::{Deserialize, Serialize};
 
 /// Response from the sync endpoint
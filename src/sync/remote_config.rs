@@ -0,0 +1,241 @@
+//! Remote configuration served by the ingestion server
+//!
+//! Lets a release be turned down or off without shipping a new binary. The server
+//! serves a kill switch, disabled event types, sample rates and a sync interval per
+//! app and version. [`SyncClient`](super::SyncClient) fetches it alongside syncs,
+//! verifies its signature with the API secret, and caches it next to the event
+//! database so it also applies from the next start.
+
+use crate::error::{Result, TelemetryError};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+/// How long a fetched configuration is used before it is fetched again
+const REFRESH_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Settings served by the ingestion server for an app and version
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoteConfig {
+    /// Global kill switch: no events are recorded while `false`
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+
+    /// Sample rate for event types without a rate of their own (0.0 to 1.0)
+    ///
+    /// Only applies to events no local sampling rule matches.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_sample_rate: Option<f64>,
+
+    /// Sample rates by event type (0.0 to 1.0), overriding local sampling rules
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub sample_rates: HashMap<String, f64>,
+
+    /// Event types that are not recorded
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disabled_event_types: Vec<String>,
+
+    /// Seconds between automatic syncs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync_interval_secs: Option<u64>,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+impl Default for RemoteConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            default_sample_rate: None,
+            sample_rates: HashMap::new(),
+            disabled_event_types: Vec::new(),
+            sync_interval_secs: None,
+        }
+    }
+}
+
+impl RemoteConfig {
+    /// Whether events of this type may be recorded
+    pub fn allows(&self, event_type: &str) -> bool {
+        self.enabled && !self.disabled_event_types.iter().any(|t| t == event_type)
+    }
+
+    /// Sample rate for events of this type, if the server sets one
+    pub fn sample_rate(&self, event_type: &str) -> Option<f64> {
+        self.sample_rates
+            .get(event_type)
+            .copied()
+            .or(self.default_sample_rate)
+    }
+
+    /// Check the values are usable
+    pub fn validate(&self) -> Result<()> {
+        let rates = self
+            .default_sample_rate
+            .iter()
+            .chain(self.sample_rates.values());
+        for rate in rates {
+            if !(0.0..=1.0).contains(rate) {
+                return Err(TelemetryError::InvalidSchema(format!(
+                    "remote config sample rate {} is not between 0.0 and 1.0",
+                    rate
+                )));
+            }
+        }
+
+        if self.sync_interval_secs == Some(0) {
+            return Err(TelemetryError::InvalidSchema(
+                "remote config sync interval must be at least 1 second".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Latest remote configuration, shared by the sync client and `TelemetryKit`
+#[derive(Debug, Default)]
+pub(crate) struct RemoteConfigCache {
+    state: RwLock<CacheState>,
+    service_version: Option<String>,
+    path: Option<PathBuf>,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    config: Option<RemoteConfig>,
+    fetched_at: Option<Instant>,
+}
+
+impl RemoteConfigCache {
+    /// Create a cache for a service version, loading the configuration saved at `path`
+    pub(crate) fn new(service_version: impl Into<String>, path: PathBuf) -> Self {
+        let config = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<RemoteConfig>(&json).ok())
+            .filter(|config| config.validate().is_ok());
+
+        Self {
+            state: RwLock::new(CacheState {
+                config,
+                fetched_at: None,
+            }),
+            service_version: Some(service_version.into()),
+            path: Some(path),
+        }
+    }
+
+    /// Version the configuration is fetched for, when known
+    pub(crate) fn service_version(&self) -> Option<&str> {
+        self.service_version.as_deref()
+    }
+
+    /// Apply a function to the current configuration, if any
+    pub(crate) fn with<R>(&self, f: impl FnOnce(&RemoteConfig) -> R) -> Option<R> {
+        match self.state.read() {
+            Ok(state) => state.config.as_ref().map(f),
+            Err(poisoned) => poisoned.into_inner().config.as_ref().map(f),
+        }
+    }
+
+    /// Record a fetch attempt, returning `false` if the last one is recent enough
+    pub(crate) fn begin_refresh(&self) -> bool {
+        let mut state = match self.state.write() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        if state
+            .fetched_at
+            .is_some_and(|fetched_at| fetched_at.elapsed() < REFRESH_INTERVAL)
+        {
+            return false;
+        }
+        state.fetched_at = Some(Instant::now());
+        true
+    }
+
+    /// Replace the configuration and save it for the next start
+    pub(crate) fn store(&self, config: RemoteConfig) {
+        if let Some(path) = &self.path {
+            if let Ok(json) = serde_json::to_string(&config) {
+                // Best effort: the configuration still applies to this process
+                let _ = std::fs::write(path, json);
+            }
+        }
+
+        match self.state.write() {
+            Ok(mut state) => state.config = Some(config),
+            Err(poisoned) => poisoned.into_inner().config = Some(config),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_remote_config_rules() {
+        let config: RemoteConfig = serde_json::from_str(
+            r#"{
+                "default_sample_rate": 0.5,
+                "sample_rates": { "feature_used": 0.1 },
+                "disabled_event_types": ["span"]
+            }"#,
+        )
+        .unwrap();
+
+        assert!(config.enabled);
+        assert!(config.allows("feature_used"));
+        assert!(!config.allows("span"));
+        assert_eq!(config.sample_rate("feature_used"), Some(0.1));
+        assert_eq!(config.sample_rate("command_execution"), Some(0.5));
+
+        let killed = RemoteConfig {
+            enabled: false,
+            ..Default::default()
+        };
+        assert!(!killed.allows("feature_used"));
+    }
+
+    #[test]
+    fn test_invalid_remote_config() {
+        let config = RemoteConfig {
+            default_sample_rate: Some(2.0),
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+
+        let config = RemoteConfig {
+            sync_interval_secs: Some(0),
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_cache_persists_config() {
+        let path = std::env::temp_dir().join(format!("test-remote-config-{}.json", Uuid::new_v4()));
+        let cache = RemoteConfigCache::new("1.0.0", path.clone());
+        assert!(cache.with(|config| config.enabled).is_none());
+
+        assert!(cache.begin_refresh());
+        assert!(!cache.begin_refresh());
+
+        cache.store(RemoteConfig {
+            enabled: false,
+            ..Default::default()
+        });
+
+        let reloaded = RemoteConfigCache::new("1.0.0", path.clone());
+        assert_eq!(reloaded.with(|config| config.enabled), Some(false));
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -1,21 +1,39 @@
 //! Main telemetry SDK
 
 use crate::builder::TelemetryBuilder;
+use crate::coalesce::Coalescer;
+use crate::environment::{ci_provider, CiPolicy};
 use crate::error::{Result, TelemetryError};
 use crate::event::{
-    CommandEventBuilder, Environment, Event, EventBatch, EventData, FeatureEventBuilder, Metadata,
-    ServiceInfo, SCHEMA_VERSION,
+    CommandEventBuilder, Environment, ErrorEventBuilder, Event, EventData, FeatureEventBuilder,
+    Metadata, ServiceInfo, TelemetryEvent, TrackableError, SCHEMA_VERSION,
 };
-use crate::storage::EventStorage;
-use crate::user::{generate_session_id, generate_user_id};
-use chrono::Utc;
+use crate::metrics::{Counter, Gauge, Histogram, MetricsRegistry};
+use crate::processor::EventProcessor;
+use crate::sampling::{variant_index, Sampler};
+use crate::session::{SessionIds, SessionTracker};
+use crate::span::Span;
+use crate::storage::{BufferLimits, EventStorage};
+use crate::user::generate_user_id;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
 #[cfg(feature = "sync")]
-use crate::sync::{SyncClient, SyncConfig};
+use crate::sync::{RemoteConfigCache, SyncClient, SyncConfig};
+
+#[cfg(feature = "sync")]
+use std::path::Path;
+
+#[cfg(feature = "sync")]
+use crate::exporter::{export_pending, Exporter};
+
+#[cfg(feature = "sync")]
+use crate::upload;
 
 #[cfg(feature = "sync")]
 use crate::auto_sync::{AutoSyncConfig, AutoSyncTask};
@@ -26,9 +44,21 @@ use tokio::sync::Mutex;
 #[cfg(feature = "privacy")]
 use crate::privacy::{PrivacyConfig, PrivacyManager};
 
+#[cfg(feature = "privacy")]
+use crate::processor::PrivacySanitizer;
+
+#[cfg(feature = "otlp")]
+use crate::otlp::{OtlpConfig, OtlpExporter};
+
 const SDK_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Metadata key holding the service version seen by the last run
+const LAST_VERSION_KEY: &str = "last_service_version";
+
 /// Main telemetry SDK
+///
+/// Cloning is cheap: clones share the same storage, exporters and session.
+#[derive(Clone)]
 pub struct TelemetryKit {
     inner: Arc<TelemetryKitInner>,
 }
@@ -37,18 +67,31 @@ struct TelemetryKitInner {
     service_name: String,
     service_version: String,
     user_id: String,
-    session_id: String,
+    session: SessionTracker,
+    session_tracking: bool,
+    sampler: Sampler,
+    processors: Vec<Arc<dyn EventProcessor>>,
     environment: Environment,
+    ci_suppressed: bool,
+    /// Database file, or `None` when events are kept in memory
+    db_path: Option<PathBuf>,
+    buffer_limits: BufferLimits,
     storage: Arc<RwLock<EventStorage>>,
+    coalescer: Option<Arc<Coalescer>>,
+    metrics: MetricsRegistry,
+    properties: std::sync::RwLock<serde_json::Map<String, serde_json::Value>>,
+
+    #[cfg(feature = "sync")]
+    exporters: Arc<[Arc<dyn Exporter>]>,
 
     #[cfg(feature = "sync")]
-    sync_client: Option<SyncClient>,
+    sync_client: Option<Arc<SyncClient>>,
 
     #[cfg(feature = "sync")]
     auto_sync_task: Option<Arc<Mutex<AutoSyncTask>>>,
 
     #[cfg(feature = "privacy")]
-    privacy_manager: Option<PrivacyManager>,
+    privacy_manager: Option<Arc<PrivacyManager>>,
 }
 
 impl TelemetryKit {
@@ -58,41 +101,85 @@ impl TelemetryKit {
     }
 
     /// Create a new TelemetryKit instance (internal)
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         service_name: String,
         service_version: String,
-        db_path: PathBuf,
+        db_path: Option<PathBuf>,
+        metrics_interval: Duration,
+        session_tracking: bool,
+        session_timeout: Option<Duration>,
+        lifecycle_tracking: bool,
+        sampler: Sampler,
+        processors: Vec<Arc<dyn EventProcessor>>,
+        environment: Environment,
+        ci_policy: CiPolicy,
+        default_properties: serde_json::Map<String, serde_json::Value>,
+        buffer_limits: BufferLimits,
+        coalesce_window: Option<Duration>,
         #[cfg(feature = "sync")] sync_config: Option<SyncConfig>,
+        #[cfg(feature = "sync")] custom_exporters: Vec<Arc<dyn Exporter>>,
         #[cfg(feature = "sync")] auto_sync_enabled: bool,
         #[cfg(feature = "sync")] auto_sync_config: AutoSyncConfig,
         #[cfg(feature = "privacy")] privacy_config: Option<PrivacyConfig>,
+        #[cfg(feature = "otlp")] otlp_config: Option<OtlpConfig>,
     ) -> Result<Self> {
         let user_id = generate_user_id()?;
-        let session_id = generate_session_id();
-        let environment = detect_environment();
-        let storage = EventStorage::new(db_path)?;
+        let storage = match &db_path {
+            Some(db_path) => EventStorage::new(db_path)?,
+            None => EventStorage::in_memory()?,
+        };
+        let storage = storage.with_limits(buffer_limits);
+        let lifecycle = match lifecycle_tracking {
+            true => lifecycle_event(&storage, &service_version)?,
+            false => None,
+        };
         let storage_arc = Arc::new(RwLock::new(storage));
+        let coalescer = coalesce_window.map(|window| Arc::new(Coalescer::new(window)));
 
+        // The sync client also fetches the remote configuration, cached next to the database
         #[cfg(feature = "sync")]
-        let sync_client = if let Some(config) = sync_config {
-            Some(SyncClient::new(config)?)
-        } else {
-            None
+        let sync_client = match sync_config {
+            Some(config) => {
+                let mut client = SyncClient::new(config)?;
+                if let Some(db_path) = &db_path {
+                    let cache = RemoteConfigCache::new(
+                        service_version.as_str(),
+                        db_path.with_extension("remote-config.json"),
+                    );
+                    client = client.with_remote_config_cache(Arc::new(cache));
+                }
+                Some(Arc::new(client))
+            }
+            None => None,
         };
 
-        // Start auto-sync task if enabled and sync is configured
+        // Collect export destinations: built-in ones first, then custom exporters
         #[cfg(feature = "sync")]
-        let auto_sync_task = if auto_sync_enabled {
-            if let Some(client) = sync_client.as_ref() {
-                let task = AutoSyncTask::start(
-                    Arc::new(client.clone()),
-                    storage_arc.clone(),
-                    auto_sync_config,
-                );
-                Some(Arc::new(Mutex::new(task)))
-            } else {
-                None
+        let exporters: Arc<[Arc<dyn Exporter>]> = {
+            let mut exporters: Vec<Arc<dyn Exporter>> = Vec::new();
+            if let Some(client) = &sync_client {
+                exporters.push(client.clone());
+            }
+            #[cfg(feature = "otlp")]
+            if let Some(config) = otlp_config {
+                exporters.push(Arc::new(OtlpExporter::new(config)?));
             }
+            exporters.extend(custom_exporters);
+            exporters.into()
+        };
+
+        // Start auto-sync task if enabled and at least one exporter is configured
+        #[cfg(feature = "sync")]
+        let auto_sync_task = if auto_sync_enabled && !exporters.is_empty() {
+            let task = AutoSyncTask::start_with_hooks(
+                exporters.clone(),
+                storage_arc.clone(),
+                auto_sync_config,
+                sync_client.clone(),
+                coalescer.clone(),
+            );
+            Some(Arc::new(Mutex::new(task)))
         } else {
             None
         };
@@ -100,18 +187,45 @@ impl TelemetryKit {
         // Create privacy manager if privacy config is provided
         #[cfg(feature = "privacy")]
         let privacy_manager = if let Some(config) = privacy_config {
-            Some(PrivacyManager::new(config, &service_name)?)
+            Some(Arc::new(PrivacyManager::new(config, &service_name)?))
         } else {
             None
         };
 
+        // The privacy sanitizer runs after user processors so their output is sanitized
+        #[cfg_attr(not(feature = "privacy"), allow(unused_mut))]
+        let mut processors = processors;
+        #[cfg(feature = "privacy")]
+        if let Some(privacy_manager) = &privacy_manager {
+            processors.push(Arc::new(PrivacySanitizer(privacy_manager.clone())));
+        }
+
+        let ci_provider = ci_provider();
+        let mut properties = serde_json::Map::new();
+        if let (CiPolicy::Tag, Some(provider)) = (ci_policy, ci_provider) {
+            properties.insert("ci_provider".to_string(), provider.into());
+        }
+        // Applied before the first session and lifecycle events are recorded
+        properties.extend(default_properties);
+
         let inner = Arc::new(TelemetryKitInner {
             service_name,
             service_version,
             user_id,
-            session_id,
+            session: SessionTracker::new(session_timeout),
+            session_tracking,
+            sampler,
+            processors,
             environment,
+            ci_suppressed: ci_policy == CiPolicy::Suppress && ci_provider.is_some(),
+            db_path,
+            buffer_limits,
             storage: storage_arc,
+            coalescer,
+            metrics: MetricsRegistry::default(),
+            properties: std::sync::RwLock::new(properties),
+            #[cfg(feature = "sync")]
+            exporters,
             #[cfg(feature = "sync")]
             sync_client,
             #[cfg(feature = "sync")]
@@ -120,7 +234,34 @@ impl TelemetryKit {
             privacy_manager,
         });
 
-        Ok(Self { inner })
+        // Flush metrics summaries in the background when a runtime is available.
+        // The task only holds a weak reference, so it stops once the SDK is dropped.
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            let weak = Arc::downgrade(&inner);
+            handle.spawn(async move {
+                let mut interval = tokio::time::interval(metrics_interval);
+                interval.tick().await;
+                loop {
+                    interval.tick().await;
+                    let Some(inner) = weak.upgrade() else {
+                        break;
+                    };
+                    if let Err(e) = (TelemetryKit { inner }).flush_metrics().await {
+                        eprintln!("Metrics flush error: {}", e);
+                    }
+                }
+            });
+        }
+
+        let telemetry = Self { inner };
+        if session_tracking {
+            telemetry.start_session()?;
+        }
+        if let Some((event_type, data)) = lifecycle {
+            telemetry.track_event_detached(event_type, Some("lifecycle"), data)?;
+        }
+
+        Ok(telemetry)
     }
 
     /// Track a command execution
@@ -176,6 +317,117 @@ impl TelemetryKit {
             .await
     }
 
+    /// Track an error and its chain of sources
+    ///
+    /// Records the type of each error in the `source()` chain and a stable
+    /// fingerprint, but never the error messages.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use telemetry_kit::prelude::*;
+    /// # async fn example(telemetry: &TelemetryKit) -> Result<()> {
+    /// if let Err(e) = std::fs::read("config.toml") {
+    ///     let error = TelemetryError::from(e);
+    ///     telemetry.track_error(&error).await?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn track_error<E: TrackableError + ?Sized>(&self, error: &E) -> Result<()> {
+        self.track_error_with(error, |event| event).await
+    }
+
+    /// Track an error, customizing the event
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use telemetry_kit::prelude::*;
+    /// # async fn example(telemetry: &TelemetryKit, error: &TelemetryError) -> Result<()> {
+    /// telemetry.track_error_with(error, |event| {
+    ///     event
+    ///         .context("sync")
+    ///         .handled(true)
+    ///         // Messages are sanitized for paths and email addresses
+    ///         .include_messages(true)
+    /// }).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn track_error_with<E, F>(&self, error: &E, builder_fn: F) -> Result<()>
+    where
+        E: TrackableError + ?Sized,
+        F: FnOnce(ErrorEventBuilder) -> ErrorEventBuilder,
+    {
+        let event_data = builder_fn(ErrorEventBuilder::new(error)).build();
+
+        self.track_event("error", Some("error"), event_data).await
+    }
+
+    /// Track that an experiment or feature flag variant was shown
+    ///
+    /// Records an `experiment_exposure` event and attaches the variant to the
+    /// `experiments` of every subsequent event in the session.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use telemetry_kit::prelude::*;
+    /// # async fn example(telemetry: &TelemetryKit) -> Result<()> {
+    /// let variant = telemetry.variant("new_resolver", &["control", "parallel"]);
+    /// telemetry.track_exposure("new_resolver", variant).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn track_exposure(
+        &self,
+        experiment: impl Into<String>,
+        variant: impl Into<String>,
+    ) -> Result<()> {
+        let (experiment, variant) = (experiment.into(), variant.into());
+        let data = serde_json::json!({ "experiment": experiment, "variant": variant });
+        let result = self
+            .track_event("experiment_exposure", Some("experiment"), data)
+            .await;
+
+        // Set after tracking, so the variant survives a session rotated by this event
+        self.inner.session.set_variant(experiment, variant);
+        result
+    }
+
+    /// Track an exposure from synchronous code without awaiting
+    #[cfg(feature = "blocking")]
+    pub(crate) fn track_exposure_detached(
+        &self,
+        experiment: String,
+        variant: String,
+    ) -> Result<()> {
+        let data = serde_json::json!({ "experiment": experiment, "variant": variant });
+        let result = self.track_event_detached("experiment_exposure", Some("experiment"), data);
+        self.inner.session.set_variant(experiment, variant);
+        result
+    }
+
+    /// Pick the variant of an experiment for this installation
+    ///
+    /// Variants are assigned evenly and deterministically from the anonymous user
+    /// ID, so an installation always gets the same variant of an experiment. Does
+    /// not record anything; call [`track_exposure`](Self::track_exposure) when the
+    /// variant is used.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `variants` is empty.
+    pub fn variant<'a>(&self, experiment: &str, variants: &[&'a str]) -> &'a str {
+        assert!(
+            !variants.is_empty(),
+            "experiment '{}' has no variants",
+            experiment
+        );
+        variants[variant_index(&self.inner.user_id, experiment, variants.len())]
+    }
+
     /// Track a custom event
     pub async fn track_custom(
         &self,
@@ -185,30 +437,477 @@ impl TelemetryKit {
         self.track_event(event_type, None, data).await
     }
 
-    /// Internal method to track an event
-    async fn track_event(
+    /// Track a strongly typed event
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// # use telemetry_kit::prelude::*;
+    /// #[derive(TelemetryEvent)]
+    /// #[telemetry(category = "usage")]
+    /// struct ProjectCreated {
+    ///     template: String,
+    /// }
+    ///
+    /// # async fn example(telemetry: &TelemetryKit) -> Result<()> {
+    /// telemetry.track(ProjectCreated { template: "web".to_string() }).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn track<E: TelemetryEvent>(&self, event: E) -> Result<()> {
+        self.track_event(event.event_type(), event.category(), event.to_data())
+            .await
+    }
+
+    /// Set a property attached to every subsequent event
+    ///
+    /// Properties are recorded in the event `context` and go through the same
+    /// sanitization as event data. Setting an existing key replaces its value.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use telemetry_kit::prelude::*;
+    /// # fn example(telemetry: &TelemetryKit) {
+    /// telemetry.set_property("edition", "enterprise");
+    /// telemetry.set_property("plugin_count", &4);
+    /// # }
+    /// ```
+    pub fn set_property<T: Serialize + ?Sized>(&self, key: impl Into<String>, value: &T) {
+        if let Ok(mut properties) = self.inner.properties.write() {
+            properties.insert(key.into(), crate::event::field_value(value));
+        }
+    }
+
+    /// Stop attaching a property to events
+    pub fn remove_property(&self, key: &str) {
+        if let Ok(mut properties) = self.inner.properties.write() {
+            properties.remove(key);
+        }
+    }
+
+    /// Start a span that records its duration and outcome when dropped
+    ///
+    /// Use [`Span::child`] for nested spans. See [`crate::span`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use telemetry_kit::prelude::*;
+    /// # fn resolve_deps() {}
+    /// # fn example(telemetry: &TelemetryKit) {
+    /// let mut build = telemetry.span("build");
+    /// {
+    ///     let _deps = build.child("resolve_deps");
+    ///     resolve_deps();
+    /// }
+    /// build.set_success(true);
+    /// # }
+    /// ```
+    pub fn span(&self, name: impl Into<String>) -> Span {
+        Span::new(self.clone(), name.into(), None)
+    }
+
+    /// Get or create a counter
+    ///
+    /// Counters are aggregated in memory and reported as the delta since the last
+    /// metrics flush. See [`crate::metrics`].
+    pub fn counter(&self, name: &str) -> Counter {
+        self.inner.metrics.counter(name)
+    }
+
+    /// Get or create a gauge, reported as its last value on each metrics flush
+    pub fn gauge(&self, name: &str) -> Gauge {
+        self.inner.metrics.gauge(name)
+    }
+
+    /// Get or create a histogram, reported as a summary on each metrics flush
+    pub fn histogram(&self, name: &str) -> Histogram {
+        self.inner.metrics.histogram(name)
+    }
+
+    /// Write the current metrics summary to the local buffer now
+    ///
+    /// Summaries are also written automatically on the metrics interval (when running
+    /// inside a tokio runtime) and on shutdown. Nothing is written if no metric changed.
+    pub async fn flush_metrics(&self) -> Result<()> {
+        match self.inner.metrics.take_summary() {
+            Some(summary) => {
+                self.track_event("metrics_summary", Some("metrics"), summary)
+                    .await
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Write the current metrics summary without awaiting
+    #[cfg(feature = "blocking")]
+    pub(crate) fn flush_metrics_detached(&self) -> Result<()> {
+        match self.inner.metrics.take_summary() {
+            Some(summary) => self.track_event_detached("metrics_summary", Some("metrics"), summary),
+            None => Ok(()),
+        }
+    }
+
+    /// Internal method to track an event
+    pub(crate) async fn track_event(
+        &self,
+        event_type: impl Into<String>,
+        category: Option<&str>,
+        data: serde_json::Value,
+    ) -> Result<()> {
+        let events = self.prepare_events(event_type, category, data, 1.0)?;
+
+        // Auto-sync task will pick up the event on next interval (if enabled)
+        self.insert(events).await
+    }
+
+    /// Track an event sampled at no more than `max_rate`, as `#[instrument]` calls are
+    pub(crate) async fn track_event_sampled(
+        &self,
+        event_type: impl Into<String>,
+        category: Option<&str>,
+        data: serde_json::Value,
+        max_rate: f64,
+    ) -> Result<()> {
+        let events = self.prepare_events(event_type, category, data, max_rate)?;
+        self.insert(events).await
+    }
+
+    /// Run events through the processors and the coalescer and store them
+    async fn insert(&self, events: Vec<Event>) -> Result<()> {
+        let events = self.coalesce(self.process(events));
+        if events.is_empty() {
+            return Ok(());
+        }
+
+        let storage = self.inner.storage.write().await;
+        events.iter().try_for_each(|event| storage.insert(event))
+    }
+
+    /// Track an event from synchronous code without awaiting
+    ///
+    /// The event is written immediately when the storage lock is free or no tokio
+    /// runtime is running; otherwise the write is handed off to a spawned task.
+    pub(crate) fn track_event_detached(
+        &self,
+        event_type: impl Into<String>,
+        category: Option<&str>,
+        data: serde_json::Value,
+    ) -> Result<()> {
+        self.track_event_sampled_detached(event_type, category, data, 1.0)
+    }
+
+    /// Track an event sampled at no more than `max_rate` without awaiting
+    pub(crate) fn track_event_sampled_detached(
+        &self,
+        event_type: impl Into<String>,
+        category: Option<&str>,
+        data: serde_json::Value,
+        max_rate: f64,
+    ) -> Result<()> {
+        let events = self.prepare_events(event_type, category, data, max_rate)?;
+        self.insert_detached(events)
+    }
+
+    /// Record a finished span without awaiting
+    pub(crate) fn track_span_detached(
+        &self,
+        event_id: Uuid,
+        parent_event_id: Option<Uuid>,
+        started_at: DateTime<Utc>,
+        data: serde_json::Value,
+    ) -> Result<()> {
+        let Some(rate) = self.sample("span", Some("performance"), &data, 1.0) else {
+            return Ok(());
+        };
+
+        let (session, mut events) = self.touch_session()?;
+
+        if let Some(mut event) = self.prepare_event(&session, "span", Some("performance"), data)? {
+            event.event.sample_rate = (rate < 1.0).then_some(rate);
+            event.event_id = event_id;
+            event.timestamp = started_at;
+            event.event.trace_id = Some(session.trace_id);
+            event.event.parent_event_id = parent_event_id;
+            events.push(event);
+        }
+
+        self.insert_detached(events)
+    }
+
+    /// Run events through the processors and the coalescer and store them, handing
+    /// the write off to a task if the lock is busy
+    fn insert_detached(&self, events: Vec<Event>) -> Result<()> {
+        let events = self.coalesce(self.process(events));
+        if events.is_empty() {
+            return Ok(());
+        }
+
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                if let Ok(storage) = self.inner.storage.try_write() {
+                    return events.iter().try_for_each(|event| storage.insert(event));
+                }
+
+                let storage = self.inner.storage.clone();
+                handle.spawn(async move {
+                    let storage = storage.write().await;
+                    for event in &events {
+                        if let Err(e) = storage.insert(event) {
+                            eprintln!("Failed to store telemetry event: {}", e);
+                        }
+                    }
+                });
+                Ok(())
+            }
+            Err(_) => {
+                let storage = self.inner.storage.blocking_write();
+                events.iter().try_for_each(|event| storage.insert(event))
+            }
+        }
+    }
+
+    /// Record a `session_start` event for the current session
+    fn start_session(&self) -> Result<()> {
+        let session = self.inner.session.current();
+        let data = serde_json::json!({ "reason": "startup" });
+        let events = self.prepare_event(&session, "session_start", Some("session"), data)?;
+        self.insert_detached(events.into_iter().collect())
+    }
+
+    /// End the current session, recording a `session_end` event if session
+    /// tracking is enabled
+    #[cfg(feature = "sync")]
+    pub(crate) async fn end_session(&self) -> Result<()> {
+        if !self.inner.session_tracking {
+            return Ok(());
+        }
+
+        let Some(summary) = self.inner.session.end() else {
+            return Ok(());
+        };
+
+        let data = summary.to_data("shutdown");
+        let event = self.prepare_event(&summary.ids, "session_end", Some("session"), data)?;
+        self.insert(event.into_iter().collect()).await
+    }
+
+    /// Record activity in the current session
+    ///
+    /// Returns the session to attach to the event, preceded by `session_end` and
+    /// `session_start` events when the previous session timed out.
+    fn touch_session(&self) -> Result<(SessionIds, Vec<Event>)> {
+        let (session, expired) = self.inner.session.touch();
+        let mut events = Vec::new();
+
+        if let Some(expired) = expired.filter(|_| self.inner.session_tracking) {
+            let data = expired.to_data("inactivity");
+            events.extend(self.prepare_event(
+                &expired.ids,
+                "session_end",
+                Some("session"),
+                data,
+            )?);
+
+            let data = serde_json::json!({ "reason": "inactivity" });
+            events.extend(self.prepare_event(&session, "session_start", Some("session"), data)?);
+        }
+
+        Ok((session, events))
+    }
+
+    /// Record crashes from panics in this process
+    ///
+    /// The hook only holds a weak reference, so it stops recording once every
+    /// clone of this instance is dropped.
+    pub(crate) fn install_panic_hook(&self, include_messages: bool) {
+        let weak = Arc::downgrade(&self.inner);
+        crate::crash::install_panic_hook(include_messages, move |data| {
+            if let Some(inner) = weak.upgrade() {
+                if let Err(e) = (TelemetryKit { inner }).record_crash(data) {
+                    eprintln!("Failed to record crash: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Synchronously persist a crash event
+    ///
+    /// Panics can happen while the shared storage is locked or inside an async
+    /// task, where blocking on the lock is not possible. In that case the event is
+    /// written through a separate SQLite connection.
+    fn record_crash(&self, data: serde_json::Value) -> Result<()> {
+        // Held events would be lost if the process aborts
+        let mut events = match &self.inner.coalescer {
+            Some(coalescer) => coalescer.take_all(),
+            None => Vec::new(),
+        };
+        events.extend(self.process(self.prepare_events("crash", Some("error"), data, 1.0)?));
+
+        match self.inner.storage.try_write() {
+            Ok(storage) => events.iter().try_for_each(|event| storage.insert(event)),
+            Err(_) => {
+                let Some(db_path) = &self.inner.db_path else {
+                    return Err(TelemetryError::Other(
+                        "In-memory storage is locked".to_string(),
+                    ));
+                };
+                let storage = EventStorage::new(db_path)?.with_limits(self.inner.buffer_limits);
+                events.iter().try_for_each(|event| storage.insert(event))
+            }
+        }
+    }
+
+    /// Pass events through the processor chain, in order, dropping filtered events
+    fn process(&self, events: Vec<Event>) -> Vec<Event> {
+        events
+            .into_iter()
+            .filter_map(|event| {
+                self.inner
+                    .processors
+                    .iter()
+                    .try_fold(event, |event, processor| processor.process(event))
+            })
+            .collect()
+    }
+
+    /// Hold back events for coalescing, returning the events to store now
+    fn coalesce(&self, events: Vec<Event>) -> Vec<Event> {
+        match &self.inner.coalescer {
+            Some(coalescer) => coalescer.add(events),
+            None => events,
+        }
+    }
+
+    /// Shared event storage
+    #[cfg(feature = "testing")]
+    pub(crate) fn storage(&self) -> &RwLock<EventStorage> {
+        &self.inner.storage
+    }
+
+    /// Store every event held for coalescing, closing open windows early
+    pub(crate) async fn flush_rollups(&self) -> Result<()> {
+        let Some(coalescer) = &self.inner.coalescer else {
+            return Ok(());
+        };
+
+        let events = coalescer.take_all();
+        let storage = self.inner.storage.write().await;
+        events.iter().try_for_each(|event| storage.insert(event))
+    }
+
+    /// Apply sampling rules, then build an event in the current session, preceded by
+    /// session lifecycle events if the session rotated
+    fn prepare_events(
+        &self,
+        event_type: impl Into<String>,
+        category: Option<&str>,
+        data: serde_json::Value,
+        max_rate: f64,
+    ) -> Result<Vec<Event>> {
+        let event_type = event_type.into();
+        let Some(rate) = self.sample(&event_type, category, &data, max_rate) else {
+            return Ok(Vec::new());
+        };
+
+        let (session, mut events) = self.touch_session()?;
+        if let Some(mut event) = self.prepare_event(&session, event_type, category, data)? {
+            event.event.sample_rate = (rate < 1.0).then_some(rate);
+            events.push(event);
+        }
+        Ok(events)
+    }
+
+    /// Decide whether to keep an event, returning the rate it was kept at
+    ///
+    /// A remote sample rate for the event type overrides local sampling rules, while
+    /// the remote default rate only applies to events no local rule matches. The rate
+    /// is capped at `max_rate`, the rate the caller already sampled at.
+    fn sample(
+        &self,
+        event_type: &str,
+        category: Option<&str>,
+        data: &serde_json::Value,
+        max_rate: f64,
+    ) -> Option<f64> {
+        let sampler = &self.inner.sampler;
+        let user_id = &self.inner.user_id;
+
+        #[cfg(feature = "sync")]
+        if let Some((type_rate, default_rate)) = self.remote_config(|config| {
+            (
+                config.sample_rates.get(event_type).copied(),
+                config.default_sample_rate,
+            )
+        }) {
+            return match type_rate {
+                Some(rate) => sampler.decide_at(user_id, event_type, rate.min(max_rate)),
+                None => sampler.decide(user_id, event_type, category, data, default_rate, max_rate),
+            };
+        }
+
+        sampler.decide(user_id, event_type, category, data, None, max_rate)
+    }
+
+    /// Check whether this installation is in the sample of an event type at a rate
+    ///
+    /// Uses the same bucketing as sampling rules, so the decision is stable and
+    /// agrees with the one made when the event is tracked.
+    pub(crate) fn is_sampled_in(&self, event_type: &str, rate: f64) -> bool {
+        self.inner
+            .sampler
+            .decide_at(&self.inner.user_id, event_type, rate)
+            .is_some()
+    }
+
+    /// Apply a function to the cached remote configuration, if there is one
+    #[cfg(feature = "sync")]
+    fn remote_config<R>(&self, f: impl FnOnce(&crate::sync::RemoteConfig) -> R) -> Option<R> {
+        self.inner
+            .sync_client
+            .as_ref()?
+            .remote_config_cache()
+            .with(f)
+    }
+
+    /// Check consent and the remote kill switch, then build the full event
+    ///
+    /// Returns `None` when the event must not be tracked. Events are sanitized later,
+    /// by the privacy processor.
+    fn prepare_event(
         &self,
+        session: &SessionIds,
         event_type: impl Into<String>,
         category: Option<&str>,
         data: serde_json::Value,
-    ) -> Result<()> {
+    ) -> Result<Option<Event>> {
+        let event_type = event_type.into();
+
+        if self.inner.ci_suppressed {
+            return Ok(None);
+        }
+
+        #[cfg(feature = "sync")]
+        if self.remote_config(|config| config.allows(&event_type)) == Some(false) {
+            return Ok(None);
+        }
+
         // Check privacy settings - should we track this event?
         #[cfg(feature = "privacy")]
         if let Some(privacy_manager) = &self.inner.privacy_manager {
             if !privacy_manager.should_track()? {
                 // User has opted out or denied consent - don't track
-                return Ok(());
+                return Ok(None);
             }
         }
 
-        // Apply data sanitization
-        let mut sanitized_data = data;
-        #[cfg(feature = "privacy")]
-        if let Some(privacy_manager) = &self.inner.privacy_manager {
-            privacy_manager.sanitize_data(&mut sanitized_data);
-        }
+        let context = match self.inner.properties.read() {
+            Ok(properties) if !properties.is_empty() => Some(properties.clone()),
+            _ => None,
+        };
 
-        let event = Event {
+        Ok(Some(Event {
             schema_version: SCHEMA_VERSION.to_string(),
             event_id: Uuid::new_v4(),
             timestamp: Utc::now(),
@@ -219,12 +918,18 @@ impl TelemetryKit {
                 language_version: Some(rustc_version()),
             },
             user_id: self.inner.user_id.clone(),
-            session_id: Some(self.inner.session_id.clone()),
+            session_id: Some(session.session_id.clone()),
             environment: self.inner.environment.clone(),
             event: EventData {
-                event_type: event_type.into(),
+                event_type,
                 category: category.map(|s| s.to_string()),
-                data: sanitized_data,
+                data,
+                context,
+                trace_id: None,
+                parent_event_id: None,
+                sample_rate: None,
+                experiments: (!session.experiments.is_empty()).then(|| session.experiments.clone()),
+                rollup: None,
             },
             metadata: Metadata {
                 sdk_version: format!("telemetry-kit-rust/{}", SDK_VERSION),
@@ -232,62 +937,75 @@ impl TelemetryKit {
                 batch_size: 1,
                 retry_count: 0,
             },
-        };
-
-        // Store event
-        let storage = self.inner.storage.write().await;
-        storage.insert(&event)?;
-        drop(storage);
-
-        // Auto-sync task will pick up the event on next interval (if enabled)
-
-        Ok(())
+        }))
     }
 
     /// Manually trigger a sync
+    ///
+    /// Sends one batch of buffered events to every configured exporter.
     #[cfg(feature = "sync")]
     pub async fn sync(&self) -> Result<()> {
-        if let Some(client) = &self.inner.sync_client {
-            Self::sync_events(Arc::new(client.clone()), self.inner.storage.clone()).await
-        } else {
-            Err(TelemetryError::invalid_config(
+        if !self.has_exporters() {
+            return Err(TelemetryError::invalid_config(
                 "sync",
-                "Sync is not configured. Use .with_sync_credentials() when building TelemetryKit",
-            ))
+                "Sync is not configured. Use .with_sync_credentials() or .exporter() when building TelemetryKit",
+            ));
         }
+
+        self.flush_rollups().await?;
+        self.sync_events().await
     }
 
-    /// Internal sync implementation
+    /// Final sync on shutdown, handed to a detached uploader when one is configured
+    /// and the built-in sync is the only export destination
     #[cfg(feature = "sync")]
-    async fn sync_events(
-        client: Arc<SyncClient>,
-        storage: Arc<RwLock<EventStorage>>,
-    ) -> Result<()> {
-        let storage_read = storage.read().await;
-        let events = storage_read.get_unsynced(client.config().batch_size)?;
-        drop(storage_read);
+    pub(crate) async fn sync_on_shutdown(&self, uploader: Option<&Path>) -> Result<()> {
+        let detached = match (uploader, &self.inner.db_path, &self.inner.sync_client) {
+            (Some(program), Some(db_path), Some(client)) if self.inner.exporters.len() == 1 => {
+                upload::spawn(program, db_path, client.config())
+            }
+            _ => false,
+        };
 
-        if events.is_empty() {
-            return Ok(());
+        if detached {
+            Ok(())
+        } else {
+            self.sync_events().await
         }
+    }
 
-        let event_ids: Vec<Uuid> = events.iter().map(|e| e.event_id).collect();
-        let batch = EventBatch::new(events);
+    /// Check if at least one export destination is configured
+    #[cfg(feature = "sync")]
+    pub(crate) fn has_exporters(&self) -> bool {
+        !self.inner.exporters.is_empty()
+    }
 
-        match client.sync(batch).await {
-            Ok(response) => {
-                if response.accepted() > 0 {
-                    let storage_write = storage.write().await;
-                    storage_write.mark_synced(&event_ids)?;
-                }
-                Ok(())
-            }
-            Err(e) => {
-                let storage_write = storage.write().await;
-                storage_write.increment_retry(&event_ids)?;
-                Err(e)
-            }
+    /// Internal sync implementation, used on the auto-sync interval
+    ///
+    /// Only rollups whose window closed are written; `sync()` writes all of them.
+    #[cfg(feature = "sync")]
+    pub(crate) async fn sync_events(&self) -> Result<()> {
+        if let Some(client) = &self.inner.sync_client {
+            client.refresh_remote_config().await;
+        }
+
+        if let Some(coalescer) = &self.inner.coalescer {
+            let events = coalescer.take_closed();
+            let storage = self.inner.storage.write().await;
+            events.iter().try_for_each(|event| storage.insert(event))?;
         }
+
+        export_pending(&self.inner.exporters, &self.inner.storage).await
+    }
+
+    /// Auto-sync interval set by the remote configuration, if any
+    #[cfg(feature = "blocking")]
+    pub(crate) fn remote_sync_interval(&self) -> Option<Duration> {
+        let client = self.inner.sync_client.as_ref()?;
+        client
+            .remote_config_cache()
+            .with(|config| config.sync_interval_secs)?
+            .map(Duration::from_secs)
     }
 
     /// Get statistics about buffered events
@@ -295,11 +1013,13 @@ impl TelemetryKit {
         let storage = self.inner.storage.read().await;
         let total = storage.total_count()?;
         let unsynced = storage.unsynced_count()?;
+        let dropped = storage.dropped_counts()?.values().sum();
 
         Ok(EventStats {
             total_events: total,
             unsynced_events: unsynced,
             synced_events: total - unsynced,
+            dropped_events: dropped,
         })
     }
 
@@ -354,17 +1074,21 @@ impl TelemetryKit {
     }
 
     /// Gracefully shutdown auto-sync task and optionally perform final sync
+    ///
+    /// Pending metrics and, with session tracking, a `session_end` event are written
+    /// to the local buffer first.
     #[cfg(feature = "sync")]
     pub async fn shutdown(&self) -> Result<()> {
+        self.flush_metrics().await?;
+        self.end_session().await?;
+        self.flush_rollups().await?;
+
         if let Some(task_mutex) = &self.inner.auto_sync_task {
             let mut task = task_mutex.lock().await;
 
             // Perform final sync if configured
             if task.should_sync_on_shutdown() {
-                if let Some(client) = &self.inner.sync_client {
-                    let _ = Self::sync_events(Arc::new(client.clone()), self.inner.storage.clone())
-                        .await;
-                }
+                let _ = self.sync_on_shutdown(task.detached_upload()).await;
             }
 
             // Shutdown the background task
@@ -384,14 +1108,6 @@ impl Drop for TelemetryKit {
     }
 }
 
-impl Clone for SyncClient {
-    fn clone(&self) -> Self {
-        // This is a workaround since SyncClient doesn't derive Clone
-        // In production, we'd use Arc<SyncClient> instead
-        Self::new(self.config().clone()).expect("Failed to clone SyncClient")
-    }
-}
-
 /// Event statistics
 #[derive(Debug, Clone)]
 pub struct EventStats {
@@ -401,34 +1117,32 @@ pub struct EventStats {
     pub unsynced_events: usize,
     /// Number of synced events
     pub synced_events: usize,
+    /// Number of events dropped from a full buffer and not yet reported
+    pub dropped_events: u64,
 }
 
-/// Detect environment information
-fn detect_environment() -> Environment {
-    Environment {
-        os: std::env::consts::OS.to_string(),
-        os_version: None, // Could use sys-info crate for this
-        arch: Some(std::env::consts::ARCH.to_string()),
-        ci: Some(is_ci()),
-        shell: detect_shell(),
+/// Record the service version, returning the lifecycle event it implies
+///
+/// A new database gets `app_installed`, and a version that differs from the last one
+/// seen gets `app_updated`. Databases created before lifecycle tracking was enabled
+/// only have their version recorded.
+fn lifecycle_event(
+    storage: &EventStorage,
+    version: &str,
+) -> Result<Option<(&'static str, serde_json::Value)>> {
+    let last_version = storage.get_metadata(LAST_VERSION_KEY)?;
+    if last_version.as_deref() == Some(version) {
+        return Ok(None);
     }
-}
-
-/// Detect if running in CI
-fn is_ci() -> bool {
-    std::env::var("CI").is_ok()
-        || std::env::var("GITHUB_ACTIONS").is_ok()
-        || std::env::var("JENKINS_HOME").is_ok()
-        || std::env::var("TRAVIS").is_ok()
-        || std::env::var("CIRCLECI").is_ok()
-}
+    storage.set_metadata(LAST_VERSION_KEY, version)?;
 
-/// Detect shell
-fn detect_shell() -> Option<String> {
-    std::env::var("SHELL").ok().and_then(|s| {
-        std::path::Path::new(&s)
-            .file_name()
-            .map(|f| f.to_string_lossy().to_string())
+    Ok(match last_version {
+        _ if storage.is_new() => Some(("app_installed", serde_json::json!({ "version": version }))),
+        Some(from) => Some((
+            "app_updated",
+            serde_json::json!({ "from_version": from, "to_version": version }),
+        )),
+        None => None,
     })
 }
 
@@ -494,14 +1208,449 @@ mod tests {
         assert_eq!(stats.unsynced_events, 1);
     }
 
-    #[test]
-    fn test_ci_detection() {
-        std::env::remove_var("CI");
-        assert!(!is_ci());
+    #[tokio::test]
+    async fn test_metrics_flush_writes_one_event() {
+        use uuid::Uuid;
+        let unique_name = format!("test-metrics-{}", Uuid::new_v4());
+        let telemetry = TelemetryKit::builder()
+            .service_name(&unique_name)
+            .unwrap()
+            .db_path(std::env::temp_dir().join(format!("{}.db", unique_name)))
+            .build()
+            .unwrap();
+
+        for _ in 0..1000 {
+            telemetry.counter("cache_hits").increment();
+        }
+        telemetry.histogram("request_ms").record(12.0);
+
+        telemetry.flush_metrics().await.unwrap();
+        // Nothing new to report
+        telemetry.flush_metrics().await.unwrap();
+
+        let stats = telemetry.stats().await.unwrap();
+        assert_eq!(stats.total_events, 1);
+
+        let storage = telemetry.inner.storage.read().await;
+        let events = storage.get_unsynced(10).unwrap();
+        assert_eq!(events[0].event.event_type, "metrics_summary");
+        assert_eq!(events[0].event.data["counters"]["cache_hits"], 1000);
+    }
+
+    #[tokio::test]
+    async fn test_buffer_limits() {
+        use crate::storage::OverflowPolicy;
+        use uuid::Uuid;
+        let unique_name = format!("test-buffer-{}", Uuid::new_v4());
+        let telemetry = TelemetryKit::builder()
+            .service_name(&unique_name)
+            .unwrap()
+            .db_path(std::env::temp_dir().join(format!("{}.db", unique_name)))
+            .max_buffer_events(2)
+            .buffer_overflow_policy(OverflowPolicy::DropLowPriority)
+            .build()
+            .unwrap();
+
+        telemetry
+            .track_command("build", |event| event.success(true))
+            .await
+            .unwrap();
+        telemetry
+            .track_command("test", |event| event.success(true))
+            .await
+            .unwrap();
+        telemetry.counter("cache_hits").increment();
+        telemetry.flush_metrics().await.unwrap();
+
+        let stats = telemetry.stats().await.unwrap();
+        assert_eq!(stats.unsynced_events, 2);
+        assert_eq!(stats.dropped_events, 1);
+    }
+
+    #[tokio::test]
+    async fn test_coalesced_feature_usage() {
+        use uuid::Uuid;
+        let unique_name = format!("test-coalesce-{}", Uuid::new_v4());
+        let telemetry = TelemetryKit::builder()
+            .service_name(&unique_name)
+            .unwrap()
+            .db_path(std::env::temp_dir().join(format!("{}.db", unique_name)))
+            .coalesce_window(60)
+            .build()
+            .unwrap();
+
+        for _ in 0..200 {
+            telemetry
+                .track_feature("autocomplete", |event| event.success(true))
+                .await
+                .unwrap();
+        }
+        assert_eq!(telemetry.stats().await.unwrap().total_events, 0);
+
+        telemetry.flush_rollups().await.unwrap();
+
+        let storage = telemetry.inner.storage.read().await;
+        let events = storage.get_unsynced(10).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event.rollup.as_ref().unwrap().count, 200);
+    }
+
+    #[tokio::test]
+    async fn test_session_lifecycle_events() {
+        use uuid::Uuid;
+        let unique_name = format!("test-session-{}", Uuid::new_v4());
+        let db_path = std::env::temp_dir().join(format!("{}.db", unique_name));
+        let telemetry = TelemetryKit::builder()
+            .service_name(&unique_name)
+            .unwrap()
+            .db_path(&db_path)
+            .session_tracking(true)
+            .default_properties([("edition", "community")])
+            .build()
+            .unwrap();
+
+        telemetry
+            .track_command("build", |event| event.success(true))
+            .await
+            .unwrap();
+        telemetry.shutdown().await.unwrap();
+        telemetry.shutdown().await.unwrap();
+
+        let events = EventStorage::new(&db_path)
+            .unwrap()
+            .get_unsynced(10)
+            .unwrap();
+        let types: Vec<&str> = events.iter().map(|e| e.event.event_type.as_str()).collect();
+        assert_eq!(types, ["session_start", "command_execution", "session_end"]);
+        assert!(events
+            .iter()
+            .all(|e| e.event.context.as_ref().unwrap()["edition"] == "community"));
+
+        let end = &events[2].event.data;
+        assert_eq!(end["event_count"], 1);
+        assert_eq!(end["reason"], "shutdown");
+        assert!(events.iter().all(|e| e.session_id == events[0].session_id));
+    }
+
+    #[tokio::test]
+    async fn test_app_lifecycle_events() {
+        use uuid::Uuid;
+        let unique_name = format!("test-lifecycle-{}", Uuid::new_v4());
+        let db_path = std::env::temp_dir().join(format!("{}.db", unique_name));
+        let build = |version: &str| {
+            TelemetryKit::builder()
+                .service_name(&unique_name)
+                .unwrap()
+                .service_version(version)
+                .db_path(&db_path)
+                .lifecycle_tracking(true)
+                .default_properties([("edition", "community")])
+                .build()
+                .unwrap()
+        };
+
+        build("1.0.0");
+        build("1.0.0");
+        build("1.1.0");
+
+        let events = EventStorage::new(&db_path)
+            .unwrap()
+            .get_unsynced(10)
+            .unwrap();
+        let types: Vec<&str> = events.iter().map(|e| e.event.event_type.as_str()).collect();
+        assert_eq!(types, ["app_installed", "app_updated"]);
+
+        assert_eq!(events[0].event.category.as_deref(), Some("lifecycle"));
+        assert_eq!(events[0].event.data["version"], "1.0.0");
+        assert_eq!(
+            events[0].event.context.as_ref().unwrap()["edition"],
+            "community"
+        );
+        assert_eq!(events[1].event.data["from_version"], "1.0.0");
+        assert_eq!(events[1].event.data["to_version"], "1.1.0");
+        assert_eq!(events[1].service.version, "1.1.0");
+    }
+
+    #[tokio::test]
+    async fn test_lifecycle_tracking_on_existing_database() {
+        use uuid::Uuid;
+        let unique_name = format!("test-lifecycle-{}", Uuid::new_v4());
+        let db_path = std::env::temp_dir().join(format!("{}.db", unique_name));
+        EventStorage::new(&db_path).unwrap();
+
+        TelemetryKit::builder()
+            .service_name(&unique_name)
+            .unwrap()
+            .service_version("2.0.0")
+            .db_path(&db_path)
+            .lifecycle_tracking(true)
+            .build()
+            .unwrap();
+
+        let storage = EventStorage::new(&db_path).unwrap();
+        assert_eq!(storage.total_count().unwrap(), 0);
+        assert_eq!(
+            storage.get_metadata(LAST_VERSION_KEY).unwrap().as_deref(),
+            Some("2.0.0")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sampling_rules() {
+        use crate::sampling::SamplingRule;
+        use uuid::Uuid;
+        let unique_name = format!("test-sampling-{}", Uuid::new_v4());
+        let telemetry = TelemetryKit::builder()
+            .service_name(&unique_name)
+            .unwrap()
+            .db_path(std::env::temp_dir().join(format!("{}.db", unique_name)))
+            .sampling_rule(
+                SamplingRule::event_type("feature_used")
+                    .name("cache_lookup")
+                    .rate(0.0),
+            )
+            .sampling_rule(SamplingRule::event_type("command_execution").rate(0.5))
+            .build()
+            .unwrap();
+
+        for _ in 0..3 {
+            telemetry
+                .track_feature("cache_lookup", |e| e)
+                .await
+                .unwrap();
+            telemetry.track_command("build", |e| e).await.unwrap();
+        }
+        telemetry.track_feature("login", |e| e).await.unwrap();
+
+        let storage = telemetry.inner.storage.read().await;
+        let events = storage.get_unsynced(10).unwrap();
+        let (commands, others): (Vec<_>, Vec<_>) = events
+            .iter()
+            .partition(|e| e.event.event_type == "command_execution");
+
+        // The installation is either in or out of the sample for every command
+        assert!(commands.is_empty() || commands.len() == 3);
+        assert!(commands.iter().all(|e| e.event.sample_rate == Some(0.5)));
+
+        assert_eq!(others.len(), 1);
+        assert_eq!(others[0].event.data["feature"], "login");
+        assert!(others[0].event.sample_rate.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_properties_attached_and_sanitized() {
+        use uuid::Uuid;
+        let unique_name = format!("test-properties-{}", Uuid::new_v4());
+        let telemetry = TelemetryKit::builder()
+            .service_name(&unique_name)
+            .unwrap()
+            .db_path(std::env::temp_dir().join(format!("{}.db", unique_name)))
+            .default_properties([("edition", "community")])
+            .sanitize_emails(true)
+            .build()
+            .unwrap();
+
+        telemetry.set_property("plugin_count", &4);
+        telemetry.set_property("license_owner", "user@example.com");
+        telemetry
+            .track_custom("first", serde_json::json!({}))
+            .await
+            .unwrap();
+
+        telemetry.remove_property("plugin_count");
+        telemetry.remove_property("license_owner");
+        telemetry.set_property("edition", "enterprise");
+        telemetry
+            .track_custom("second", serde_json::json!({}))
+            .await
+            .unwrap();
+
+        let storage = telemetry.inner.storage.read().await;
+        let events = storage.get_unsynced(10).unwrap();
+        let first = events[0].event.context.as_ref().unwrap();
+        assert_eq!(first["edition"], "community");
+        assert_eq!(first["plugin_count"], 4);
+        assert!(first["license_owner"]
+            .as_str()
+            .unwrap()
+            .starts_with("email_"));
+
+        let second = events[1].event.context.as_ref().unwrap();
+        assert_eq!(second.len(), 1);
+        assert_eq!(second["edition"], "enterprise");
+    }
+
+    #[cfg(feature = "sync")]
+    #[tokio::test]
+    async fn test_remote_config_honored() {
+        use crate::sync::RemoteConfig;
+        use uuid::Uuid;
+        let unique_name = format!("test-remote-config-{}", Uuid::new_v4());
+        let db_path = std::env::temp_dir().join(format!("{}.db", unique_name));
+
+        // Cached by a previous run
+        let cached = RemoteConfig {
+            disabled_event_types: vec!["noisy".to_string()],
+            ..Default::default()
+        };
+        std::fs::write(
+            db_path.with_extension("remote-config.json"),
+            serde_json::to_string(&cached).unwrap(),
+        )
+        .unwrap();
+
+        let telemetry = TelemetryKit::builder()
+            .service_name(&unique_name)
+            .unwrap()
+            .db_path(&db_path)
+            .with_sync_credentials(
+                "550e8400-e29b-41d4-a716-446655440000",
+                "7c9e6679-7425-40de-944b-e07fc1f90ae7",
+                "tk_test_token",
+                "test_secret",
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+
+        telemetry
+            .track_custom("noisy", serde_json::json!({}))
+            .await
+            .unwrap();
+        telemetry
+            .track_custom("kept", serde_json::json!({}))
+            .await
+            .unwrap();
+
+        // Kill switch flipped by a later fetch
+        let client = telemetry.inner.sync_client.as_ref().unwrap();
+        client.remote_config_cache().store(RemoteConfig {
+            enabled: false,
+            ..Default::default()
+        });
+        telemetry
+            .track_custom("after_kill", serde_json::json!({}))
+            .await
+            .unwrap();
+
+        let storage = telemetry.inner.storage.read().await;
+        let events = storage.get_unsynced(10).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event.event_type, "kept");
+        assert_eq!(client.remote_config().map(|c| c.enabled), Some(false));
+    }
+
+    #[cfg(feature = "sync")]
+    #[tokio::test]
+    async fn test_local_rules_take_precedence_over_remote_default_rate() {
+        use crate::sampling::SamplingRule;
+        use crate::sync::RemoteConfig;
+        use uuid::Uuid;
+        let unique_name = format!("test-remote-sampling-{}", Uuid::new_v4());
+        let telemetry = TelemetryKit::builder()
+            .service_name(&unique_name)
+            .unwrap()
+            .db_path(std::env::temp_dir().join(format!("{}.db", unique_name)))
+            .with_sync_credentials(
+                "550e8400-e29b-41d4-a716-446655440000",
+                "7c9e6679-7425-40de-944b-e07fc1f90ae7",
+                "tk_test_token",
+                "test_secret",
+            )
+            .unwrap()
+            .sampling_rule(SamplingRule::category("error"))
+            .build()
+            .unwrap();
+
+        let client = telemetry.inner.sync_client.as_ref().unwrap();
+        client.remote_config_cache().store(RemoteConfig {
+            default_sample_rate: Some(0.0),
+            ..Default::default()
+        });
+
+        let error = TelemetryError::Other("boom".to_string());
+        telemetry.track_error(&error).await.unwrap();
+        telemetry
+            .track_custom("dropped", serde_json::json!({}))
+            .await
+            .unwrap();
+
+        // A rate for the event type itself still overrides the local rule
+        client.remote_config_cache().store(RemoteConfig {
+            sample_rates: [("error".to_string(), 0.0)].into_iter().collect(),
+            ..Default::default()
+        });
+        telemetry.track_error(&error).await.unwrap();
+
+        let storage = telemetry.inner.storage.read().await;
+        let events = storage.get_unsynced(10).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event.event_type, "error");
+        assert!(events[0].event.sample_rate.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_exposures_attached_to_later_events() {
+        use uuid::Uuid;
+        let unique_name = format!("test-exposure-{}", Uuid::new_v4());
+        let telemetry = TelemetryKit::builder()
+            .service_name(&unique_name)
+            .unwrap()
+            .db_path(std::env::temp_dir().join(format!("{}.db", unique_name)))
+            .build()
+            .unwrap();
+
+        let variant = telemetry.variant("new_resolver", &["a", "b"]);
+        assert_eq!(variant, telemetry.variant("new_resolver", &["a", "b"]));
+
+        telemetry
+            .track_custom("before", serde_json::json!({}))
+            .await
+            .unwrap();
+        telemetry
+            .track_exposure("new_resolver", variant)
+            .await
+            .unwrap();
+        telemetry
+            .track_custom("after", serde_json::json!({}))
+            .await
+            .unwrap();
+
+        let storage = telemetry.inner.storage.read().await;
+        let events = storage.get_unsynced(10).unwrap();
+        assert!(events[0].event.experiments.is_none());
+
+        assert_eq!(events[1].event.event_type, "experiment_exposure");
+        assert_eq!(events[1].event.data["experiment"], "new_resolver");
+        assert_eq!(events[1].event.data["variant"], variant);
+
+        let experiments = events[2].event.experiments.as_ref().unwrap();
+        assert_eq!(experiments["new_resolver"], variant);
+    }
+
+    #[tokio::test]
+    async fn test_track_error_records_chain_without_messages() {
+        use uuid::Uuid;
+        let unique_name = format!("test-error-{}", Uuid::new_v4());
+        let telemetry = TelemetryKit::builder()
+            .service_name(&unique_name)
+            .unwrap()
+            .db_path(std::env::temp_dir().join(format!("{}.db", unique_name)))
+            .build()
+            .unwrap();
 
-        std::env::set_var("CI", "true");
-        assert!(is_ci());
+        let error = TelemetryError::from(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            "cannot open /home/alice/.config/app.db",
+        ));
+        telemetry.track_error(&error).await.unwrap();
 
-        std::env::remove_var("CI");
+        let storage = telemetry.inner.storage.read().await;
+        let events = storage.get_unsynced(10).unwrap();
+        let data = &events[0].event.data;
+        assert_eq!(events[0].event.event_type, "error");
+        assert_eq!(data["kind"], "TelemetryError::Io");
+        assert_eq!(data["chain"][1]["kind"], "io::Error::PermissionDenied");
+        assert!(!data.to_string().contains("alice"));
     }
 }
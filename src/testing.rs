@@ -0,0 +1,275 @@
+//! Test helpers for verifying instrumentation
+//!
+//! [`TestTelemetry`] is a [`TelemetryKit`] that keeps its events in memory, so test
+//! suites can check what an application records without touching the home
+//! directory. It is built with [`TelemetryBuilder::build_test`], which accepts the
+//! same options as `build()`, so processors and privacy settings apply as they
+//! would in production.
+//!
+//! Requires the `testing` feature, usually enabled in `[dev-dependencies]`.
+//!
+//! # Example
+//!
+//! ```
+//! use telemetry_kit::prelude::*;
+//! use telemetry_kit::{assert_not_tracked, assert_tracked};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> telemetry_kit::Result<()> {
+//! let t = TelemetryKit::builder().build_test()?;
+//!
+//! t.track_command("build", |e| e.success(true)).await?;
+//!
+//! assert_tracked!(t, command = "build", success = true);
+//! assert_not_tracked!(t, event_type = "feature_used");
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`TelemetryBuilder::build_test`]: crate::TelemetryBuilder::build_test
+
+use crate::error::Result;
+use crate::event::Event;
+use crate::telemetry::TelemetryKit;
+use std::ops::Deref;
+
+/// A [`TelemetryKit`] recording events in memory for assertions
+///
+/// Dereferences to [`TelemetryKit`], so the usual tracking methods are available.
+#[derive(Clone)]
+pub struct TestTelemetry {
+    kit: TelemetryKit,
+}
+
+impl TestTelemetry {
+    pub(crate) fn new(kit: TelemetryKit) -> Self {
+        Self { kit }
+    }
+
+    /// Create a recording instance with default settings
+    pub fn recording() -> Result<Self> {
+        TelemetryKit::builder().build_test()
+    }
+
+    /// Every recorded event, oldest first
+    ///
+    /// Events held for coalescing are written first, so rollups are included.
+    pub async fn events(&self) -> Result<Vec<Event>> {
+        self.kit.flush_rollups().await?;
+        self.kit.storage().read().await.get_all()
+    }
+
+    /// Recorded events matching every field
+    ///
+    /// `event_type` and `category` match the event's type and category; other
+    /// names match fields of the event data, e.g. `command` or `success`.
+    pub async fn tracked(&self, fields: &[(&str, serde_json::Value)]) -> Result<Vec<Event>> {
+        let events = self.events().await?;
+        Ok(events
+            .into_iter()
+            .filter(|event| {
+                fields
+                    .iter()
+                    .all(|(name, value)| matches(event, name, value))
+            })
+            .collect())
+    }
+
+    /// Recorded events with volatile values removed, for comparing with expected output
+    ///
+    /// Each event is reduced to its `type`, `category`, `data` and, when set,
+    /// `context`, after processors and privacy sanitization. IDs, timestamps,
+    /// environment and metadata are left out and `duration_ms` values are replaced
+    /// with `"[duration]"`.
+    pub async fn snapshot(&self) -> Result<serde_json::Value> {
+        let events = self.events().await?;
+        Ok(events.iter().map(snapshot_event).collect())
+    }
+
+    /// Delete every recorded event
+    pub async fn clear(&self) -> Result<()> {
+        self.kit.flush_rollups().await?;
+        self.kit.storage().write().await.clear()?;
+        Ok(())
+    }
+
+    #[doc(hidden)]
+    pub async fn __assert_tracked(&self, fields: &[(&str, serde_json::Value)], expected: bool) {
+        let events = self.events().await.expect("failed to read recorded events");
+        let found = events.iter().any(|event| {
+            fields
+                .iter()
+                .all(|(name, value)| matches(event, name, value))
+        });
+
+        if found != expected {
+            let recorded: Vec<_> = events.iter().map(snapshot_event).collect();
+            panic!(
+                "expected {}an event with {}\nrecorded events: {:#}",
+                if expected { "" } else { "no " },
+                describe(fields),
+                serde_json::Value::Array(recorded)
+            );
+        }
+    }
+}
+
+impl Deref for TestTelemetry {
+    type Target = TelemetryKit;
+
+    fn deref(&self) -> &TelemetryKit {
+        &self.kit
+    }
+}
+
+/// Assert that a [`TestTelemetry`] recorded an event with the given fields
+///
+/// Fields are written as `name = value`, where the value is anything
+/// `serde_json::json!` accepts. `event_type` and `category` match the event's type
+/// and category; other names match fields of the event data. Must be used in an
+/// async context. Panics with the recorded events if none matches.
+///
+/// ```
+/// # use telemetry_kit::prelude::*;
+/// # use telemetry_kit::assert_tracked;
+/// # #[tokio::main]
+/// # async fn main() -> telemetry_kit::Result<()> {
+/// let t = TelemetryKit::builder().build_test()?;
+/// t.track_feature("export", |e| e.method("csv")).await?;
+///
+/// assert_tracked!(t, event_type = "feature_used", feature = "export", method = "csv");
+/// # Ok(())
+/// # }
+/// ```
+#[macro_export]
+macro_rules! assert_tracked {
+    ($telemetry:expr $(, $field:ident = $value:expr)+ $(,)?) => {
+        $telemetry
+            .__assert_tracked(
+                &[$((stringify!($field), $crate::__private::serde_json::json!($value))),+],
+                true,
+            )
+            .await
+    };
+}
+
+/// Assert that a [`TestTelemetry`] recorded no event with the given fields
+///
+/// Takes the same fields as [`assert_tracked!`].
+#[macro_export]
+macro_rules! assert_not_tracked {
+    ($telemetry:expr $(, $field:ident = $value:expr)+ $(,)?) => {
+        $telemetry
+            .__assert_tracked(
+                &[$((stringify!($field), $crate::__private::serde_json::json!($value))),+],
+                false,
+            )
+            .await
+    };
+}
+
+fn matches(event: &Event, name: &str, value: &serde_json::Value) -> bool {
+    match name {
+        "event_type" => value.as_str() == Some(event.event.event_type.as_str()),
+        "category" => value.as_str() == event.event.category.as_deref(),
+        _ => event.event.data.get(name) == Some(value),
+    }
+}
+
+fn describe(fields: &[(&str, serde_json::Value)]) -> String {
+    fields
+        .iter()
+        .map(|(name, value)| format!("{} = {}", name, value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn snapshot_event(event: &Event) -> serde_json::Value {
+    let mut data = event.event.data.clone();
+    if let Some(duration) = data.get_mut("duration_ms") {
+        *duration = serde_json::json!("[duration]");
+    }
+
+    let mut snapshot = serde_json::json!({
+        "type": event.event.event_type,
+        "category": event.event.category,
+        "data": data,
+    });
+    if let Some(context) = &event.event.context {
+        snapshot["context"] = context.clone().into();
+    }
+    snapshot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TelemetryBuilder;
+
+    #[tokio::test]
+    async fn test_records_in_memory() {
+        let t = TestTelemetry::recording().unwrap();
+        t.track_command("build", |e| e.success(true).duration_ms(12))
+            .await
+            .unwrap();
+        t.track_feature("export", |e| e).await.unwrap();
+
+        assert_tracked!(t, command = "build", success = true);
+        assert_tracked!(t, event_type = "feature_used", feature = "export");
+        assert_not_tracked!(t, command = "build", success = false);
+        assert_eq!(
+            t.tracked(&[("command", "build".into())])
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        t.clear().await.unwrap();
+        assert!(t.events().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_omits_volatile_values() {
+        let t = TestTelemetry::recording().unwrap();
+        t.track_command("build", |e| e.success(true).duration_ms(12))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            t.snapshot().await.unwrap(),
+            serde_json::json!([{
+                "type": "command_execution",
+                "category": "usage",
+                "data": { "command": "build", "success": true, "duration_ms": "[duration]" },
+            }])
+        );
+    }
+
+    #[cfg(feature = "privacy")]
+    #[tokio::test]
+    async fn test_snapshot_is_sanitized() {
+        let t = TelemetryBuilder::new()
+            .privacy(crate::privacy::PrivacyConfig::default())
+            .build_test()
+            .unwrap();
+        t.track_custom(
+            "import",
+            serde_json::json!({ "author": "jane@example.com" }),
+        )
+        .await
+        .unwrap();
+
+        let snapshot = t.snapshot().await.unwrap();
+        assert_ne!(snapshot[0]["data"]["author"], "jane@example.com");
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "expected an event with command = \"deploy\"")]
+    async fn test_assert_tracked_panics() {
+        let t = TelemetryBuilder::new().build_test().unwrap();
+        t.track_command("build", |e| e).await.unwrap();
+
+        assert_tracked!(t, command = "deploy");
+    }
+}
@@ -0,0 +1,446 @@
+//! `tracing` integration
+//!
+//! [`TelemetryLayer`] is a [`tracing_subscriber::Layer`] that records existing
+//! `tracing` instrumentation as telemetry:
+//!
+//! - Selected spans are recorded as `command_execution` events with the span name,
+//!   the time from creation to close, the outcome and the span fields. Spans are
+//!   selected by target with [`TelemetryLayer::target`], or one by one with a
+//!   `telemetry = true` field; `telemetry = false` leaves out a span its target
+//!   selects.
+//! - `ERROR` events are recorded as `error` events. An error passed as a field, e.g.
+//!   `error = &e as &dyn std::error::Error`, is classified like
+//!   [`TelemetryKit::track_error`]; otherwise the event's callsite is the error kind.
+//!
+//! A selected span fails when it records an error or an `ERROR` event is emitted
+//! inside it, unless it records a `success` field, which sets the outcome.
+//!
+//! Captured fields are stored in the event data, so they are sanitized by the
+//! instance's privacy settings like any other event data. Event messages are left
+//! out unless [`TelemetryLayer::include_messages`] is enabled.
+//!
+//! Requires the `tracing` feature.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use telemetry_kit::prelude::*;
+//! use telemetry_kit::tracing::TelemetryLayer;
+//! use tracing_subscriber::prelude::*;
+//!
+//! # fn example() -> telemetry_kit::Result<()> {
+//! let telemetry = TelemetryKit::builder()
+//!     .service_name("my-service")?
+//!     .build()?;
+//!
+//! tracing_subscriber::registry()
+//!     .with(TelemetryLayer::new(telemetry.clone()).target("my_service::commands"))
+//!     .init();
+//!
+//! // Recorded as a `deploy` command when the span closes
+//! let span = tracing::info_span!("deploy", region = "eu-west-1", telemetry = true);
+//! span.in_scope(|| tracing::info!("deploying"));
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::event::{CommandEventBuilder, ErrorEventBuilder};
+use crate::telemetry::TelemetryKit;
+use std::fmt;
+use std::time::Instant;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Span field that selects a span, or leaves it out, regardless of its target
+pub const SELECT_FIELD: &str = "telemetry";
+
+/// A [`Layer`] recording selected spans and `ERROR` events into a [`TelemetryKit`]
+///
+/// See the [module documentation](self).
+#[derive(Clone)]
+pub struct TelemetryLayer {
+    kit: TelemetryKit,
+    targets: Vec<String>,
+    track_errors: bool,
+    include_messages: bool,
+}
+
+impl TelemetryLayer {
+    /// Create a layer recording into a TelemetryKit instance
+    ///
+    /// Until targets are added, only spans with a `telemetry = true` field are
+    /// recorded.
+    pub fn new(telemetry: TelemetryKit) -> Self {
+        Self {
+            kit: telemetry,
+            targets: Vec::new(),
+            track_errors: true,
+            include_messages: false,
+        }
+    }
+
+    /// Record spans whose target is `target` or a module below it
+    ///
+    /// Can be called several times to select more targets.
+    pub fn target(mut self, target: impl Into<String>) -> Self {
+        self.targets.push(target.into());
+        self
+    }
+
+    /// Record `ERROR` events as error events (default: true)
+    pub fn track_errors(mut self, enabled: bool) -> Self {
+        self.track_errors = enabled;
+        self
+    }
+
+    /// Record sanitized messages with error events (off by default)
+    ///
+    /// Requires the `privacy` feature; without it messages are never recorded.
+    pub fn include_messages(mut self, include: bool) -> Self {
+        self.include_messages = include;
+        self
+    }
+
+    fn selects_target(&self, metadata: &Metadata<'_>) -> bool {
+        let target = metadata.target();
+        self.targets.iter().any(|prefix| {
+            target
+                .strip_prefix(prefix.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+        })
+    }
+
+    fn record_error(&self, event: &Event<'_>, context: Option<&str>) {
+        let metadata = event.metadata();
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+
+        let error = match visitor.error {
+            Some(error) => error,
+            None => {
+                ErrorEventBuilder::from_kind(metadata.name(), visitor.message.unwrap_or_default())
+            }
+        };
+        let mut data = error
+            .context(context.unwrap_or(metadata.target()))
+            .include_messages(self.include_messages)
+            .build();
+
+        if !visitor.fields.is_empty() {
+            // Sanitized along with the rest of the event data before storage
+            data["fields"] = serde_json::Value::Object(visitor.fields);
+        }
+
+        if let Err(e) = self.kit.track_event_detached("error", Some("error"), data) {
+            eprintln!("Failed to record tracing event: {}", e);
+        }
+    }
+}
+
+impl<S> Layer<S> for TelemetryLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let metadata = attrs.metadata();
+        let by_target = self.selects_target(metadata);
+        if !by_target && metadata.fields().field(SELECT_FIELD).is_none() {
+            return;
+        }
+
+        let mut fields = FieldVisitor::default();
+        attrs.record(&mut fields);
+        let selected = match fields.fields.remove(SELECT_FIELD) {
+            Some(serde_json::Value::Bool(selected)) => selected,
+            _ => by_target,
+        };
+
+        if let (true, Some(span)) = (selected, ctx.span(id)) {
+            span.extensions_mut().insert(SpanRecord {
+                start: Instant::now(),
+                fields,
+                failed: false,
+            });
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+
+        let mut extensions = span.extensions_mut();
+        if let Some(record) = extensions.get_mut::<SpanRecord>() {
+            values.record(&mut record.fields);
+            record.fields.fields.remove(SELECT_FIELD);
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        if *event.metadata().level() != Level::ERROR {
+            return;
+        }
+
+        // Fail every selected span the event is in; the innermost one is the context
+        let mut context = None;
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope {
+                if let Some(record) = span.extensions_mut().get_mut::<SpanRecord>() {
+                    record.failed = true;
+                    context.get_or_insert(span.name());
+                }
+            }
+        }
+
+        if self.track_errors {
+            self.record_error(event, context);
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(record) = span.extensions_mut().remove::<SpanRecord>() else {
+            return;
+        };
+
+        let SpanRecord {
+            start,
+            fields,
+            failed,
+        } = record;
+        let mut values = fields.fields;
+        let success = match values.remove("success") {
+            Some(serde_json::Value::Bool(success)) => success,
+            _ => !failed && fields.error.is_none(),
+        };
+
+        let mut data = CommandEventBuilder::new(span.name())
+            .duration_ms(start.elapsed().as_millis() as u64)
+            .success(success)
+            .build();
+        if !values.is_empty() {
+            // Sanitized along with the rest of the event data before storage
+            data["fields"] = serde_json::Value::Object(values);
+        }
+
+        if let Err(e) = self
+            .kit
+            .track_event_detached("command_execution", Some("usage"), data)
+        {
+            eprintln!("Failed to record tracing span: {}", e);
+        }
+    }
+}
+
+/// State of a selected span, kept in its extensions until it closes
+struct SpanRecord {
+    start: Instant,
+    fields: FieldVisitor,
+    failed: bool,
+}
+
+/// Collects field values as JSON, setting aside the message and the first error
+#[derive(Default)]
+struct FieldVisitor {
+    fields: serde_json::Map<String, serde_json::Value>,
+    message: Option<String>,
+    error: Option<ErrorEventBuilder>,
+}
+
+impl FieldVisitor {
+    fn insert(&mut self, field: &Field, value: serde_json::Value) {
+        self.fields.insert(field.name().to_string(), value);
+    }
+}
+
+impl Visit for FieldVisitor {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = Some(value.to_string());
+        } else {
+            self.insert(field, value.into());
+        }
+    }
+
+    fn record_error(&mut self, _field: &Field, value: &(dyn std::error::Error + 'static)) {
+        if self.error.is_none() {
+            self.error = Some(ErrorEventBuilder::new(value));
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        let value = format!("{:?}", value);
+        if field.name() == "message" {
+            self.message = Some(value);
+        } else {
+            self.insert(field, value.into());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::EventStorage;
+    use tracing_subscriber::layer::SubscriberExt;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_selected_spans_become_commands() {
+        let unique_name = format!("test-tracing-{}", Uuid::new_v4());
+        let db_path = std::env::temp_dir().join(format!("{}.db", unique_name));
+        let telemetry = crate::TelemetryKit::builder()
+            .service_name(&unique_name)
+            .unwrap()
+            .db_path(&db_path)
+            .auto_sync(false)
+            .build()
+            .unwrap();
+
+        let layer = TelemetryLayer::new(telemetry).target("app::commands");
+        tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+            let span = tracing::info_span!(
+                target: "app::commands",
+                "build",
+                release = true,
+                jobs = 4,
+                author = tracing::field::Empty,
+            );
+            span.record("author", "jane@example.com");
+            span.in_scope(|| tracing::info!("building"));
+            drop(span);
+
+            tracing::info_span!("deploy", telemetry = true, success = false).in_scope(|| {});
+            tracing::info_span!(target: "app::commands::nested", "check").in_scope(|| {});
+            tracing::info_span!(target: "app::commands", "skipped", telemetry = false)
+                .in_scope(|| {});
+            tracing::info_span!(target: "app::commandsx", "other").in_scope(|| {});
+            tracing::info_span!("unselected").in_scope(|| {});
+        });
+
+        let events = EventStorage::new(&db_path)
+            .unwrap()
+            .get_unsynced(10)
+            .unwrap();
+        let commands: Vec<_> = events
+            .iter()
+            .map(|e| e.event.data["command"].as_str().unwrap())
+            .collect();
+        assert_eq!(commands, ["build", "deploy", "check"]);
+
+        let build = &events[0].event;
+        assert_eq!(build.event_type, "command_execution");
+        assert_eq!(build.category.as_deref(), Some("usage"));
+        assert_eq!(build.data["success"], true);
+        assert!(build.data["duration_ms"].is_u64());
+        assert_eq!(build.data["fields"]["release"], true);
+        assert_eq!(build.data["fields"]["jobs"], 4);
+        assert_eq!(build.data["fields"]["author"], "jane@example.com");
+
+        assert_eq!(events[1].event.data["success"], false);
+        assert!(events[1].event.data.get("fields").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_error_events() {
+        let unique_name = format!("test-tracing-{}", Uuid::new_v4());
+        let db_path = std::env::temp_dir().join(format!("{}.db", unique_name));
+        let telemetry = crate::TelemetryKit::builder()
+            .service_name(&unique_name)
+            .unwrap()
+            .db_path(&db_path)
+            .auto_sync(false)
+            .build()
+            .unwrap();
+
+        let layer = TelemetryLayer::new(telemetry);
+        tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+            tracing::info_span!("sync", telemetry = true).in_scope(|| {
+                let error = std::io::Error::new(std::io::ErrorKind::NotFound, "missing");
+                tracing::error!(error = &error as &dyn std::error::Error, "read failed");
+                tracing::warn!("retrying");
+            });
+            tracing::error!(attempts = 3, "giving up on /home/jane/data");
+        });
+
+        let events = EventStorage::new(&db_path)
+            .unwrap()
+            .get_unsynced(10)
+            .unwrap();
+        assert_eq!(events.len(), 3);
+
+        let (io, sync, other) = (&events[0].event, &events[1].event, &events[2].event);
+        assert_eq!(io.event_type, "error");
+        assert_eq!(io.data["kind"], "io::Error::NotFound");
+        assert_eq!(io.data["context"], "sync");
+        assert!(io.data["chain"][0].get("message").is_none());
+
+        assert_eq!(sync.data["command"], "sync");
+        assert_eq!(sync.data["success"], false);
+
+        assert!(other.data["kind"]
+            .as_str()
+            .unwrap()
+            .starts_with("event src/tracing.rs"));
+        assert_eq!(other.data["context"], "telemetry_kit::tracing::tests");
+        assert_eq!(other.data["fields"]["attempts"], 3);
+        assert!(other.data["chain"][0].get("message").is_none());
+    }
+
+    #[cfg(feature = "privacy")]
+    #[tokio::test]
+    async fn test_fields_and_messages_are_sanitized() {
+        let unique_name = format!("test-tracing-{}", Uuid::new_v4());
+        let db_path = std::env::temp_dir().join(format!("{}.db", unique_name));
+        let telemetry = crate::TelemetryKit::builder()
+            .service_name(&unique_name)
+            .unwrap()
+            .db_path(&db_path)
+            .auto_sync(false)
+            .sanitize_emails(true)
+            .build()
+            .unwrap();
+
+        let layer = TelemetryLayer::new(telemetry).include_messages(true);
+        tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+            tracing::info_span!("import", telemetry = true, author = "jane@example.com")
+                .in_scope(|| {});
+            tracing::error!("no access for jane@example.com");
+        });
+
+        let events = EventStorage::new(&db_path)
+            .unwrap()
+            .get_unsynced(10)
+            .unwrap();
+        assert_eq!(events.len(), 2);
+
+        let author = events[0].event.data["fields"]["author"].as_str().unwrap();
+        assert!(author.starts_with("email_"));
+
+        let message = events[1].event.data["chain"][0]["message"]
+            .as_str()
+            .unwrap();
+        assert!(!message.contains("jane@example.com"));
+    }
+}
@@ -0,0 +1,308 @@
+//! Detached uploads for short-lived processes
+//!
+//! With [`TelemetryBuilder::detached_upload`](crate::TelemetryBuilder::detached_upload),
+//! `shutdown()` hands the final sync to a background process instead of waiting on
+//! the network, so a CLI can exit as soon as its work is done. The uploader is
+//! started as `<program> __upload --db <path>`, with the sync credentials and
+//! settings (endpoint, batch size, retries, Do Not Track) passed in `TK_*`
+//! environment variables (see [`crate::config`]), and syncs the buffer until it is
+//! empty or a batch fails.
+//!
+//! An upload lock next to the database keeps a single uploader per database. A
+//! second uploader exits straight away; events it would have sent go with the next
+//! sync. A lock left by an uploader that was killed expires after 15 minutes.
+//!
+//! The program can be the `telemetry-kit` CLI, or the application itself when its
+//! `main` starts by checking [`requested_db_path`]:
+//!
+//! ```no_run
+//! use telemetry_kit::prelude::*;
+//! use telemetry_kit::upload;
+//!
+//! #[tokio::main]
+//! async fn main() -> telemetry_kit::Result<()> {
+//!     if let Some(db_path) = upload::requested_db_path() {
+//!         upload::run(db_path).await?;
+//!         return Ok(());
+//!     }
+//!
+//!     let telemetry = TelemetryKit::builder()
+//!         .service_name("my-cli")?
+//!         .with_sync_credentials("org", "app", "token", "secret")?
+//!         .auto_sync(true)
+//!         .detached_upload(std::env::current_exe()?)
+//!         .build()?;
+//!
+//!     telemetry.track_command("build", |event| event.success(true)).await?;
+//!     telemetry.shutdown().await?;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Only the built-in sync is handed over. With OTLP or custom exporters, or when
+//! the uploader cannot be started, `shutdown()` syncs in process as before.
+
+use crate::config::TelemetryConfig;
+use crate::error::{Result, TelemetryError};
+use crate::exporter::{export_pending, Exporter};
+use crate::storage::EventStorage;
+use crate::sync::{SyncClient, SyncConfig};
+use std::ffi::OsString;
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+/// Subcommand a detached uploader is started with
+pub const UPLOAD_COMMAND: &str = "__upload";
+
+/// Age after which an upload lock is considered left behind by a killed uploader
+const STALE_LOCK_AGE: Duration = Duration::from_secs(15 * 60);
+
+/// The database to upload, if this process was started as a detached uploader
+pub fn requested_db_path() -> Option<PathBuf> {
+    parse_args(std::env::args_os().skip(1))
+}
+
+fn parse_args(mut args: impl Iterator<Item = OsString>) -> Option<PathBuf> {
+    if args.next()? != UPLOAD_COMMAND || args.next()? != "--db" {
+        return None;
+    }
+    args.next().map(PathBuf::from)
+}
+
+/// Sync a database with the credentials and settings in `TK_*` environment variables
+///
+/// Returns the number of events synced, or 0 when another uploader holds the lock.
+pub async fn run(db_path: impl AsRef<Path>) -> Result<usize> {
+    let config = TelemetryConfig::from_env()?
+        .sync
+        .ok_or_else(|| {
+            TelemetryError::invalid_config("sync", "TK_ORG_ID and TK_APP_ID must be set")
+        })?
+        .to_sync_config()?;
+
+    upload(db_path.as_ref(), config).await
+}
+
+async fn upload(db_path: &Path, config: SyncConfig) -> Result<usize> {
+    let Some(_lock) = UploadLock::acquire(db_path)? else {
+        return Ok(0);
+    };
+
+    let storage = Arc::new(RwLock::new(EventStorage::new(db_path)?));
+    let exporters: [Arc<dyn Exporter>; 1] = [Arc::new(SyncClient::new(config)?)];
+
+    // Each export sends one batch; stop once a batch makes no progress
+    let pending = storage.read().await.unsynced_count()?;
+    let mut remaining = pending;
+    while remaining > 0 {
+        export_pending(&exporters, &storage).await?;
+        let unsynced = storage.read().await.unsynced_count()?;
+        if unsynced >= remaining {
+            break;
+        }
+        remaining = unsynced;
+    }
+
+    Ok(pending.saturating_sub(remaining))
+}
+
+/// Start a detached uploader for a database, returning whether it started
+pub(crate) fn spawn(program: &Path, db_path: &Path, config: &SyncConfig) -> bool {
+    let mut command = Command::new(program);
+    command
+        .arg(UPLOAD_COMMAND)
+        .arg("--db")
+        .arg(db_path)
+        .env("TK_ORG_ID", &config.org_id)
+        .env("TK_APP_ID", &config.app_id)
+        .env("TK_ENDPOINT", &config.endpoint)
+        .env("TK_BATCH_SIZE", config.batch_size.to_string())
+        .env("TK_MAX_RETRIES", config.max_retries.to_string())
+        .env("TK_RESPECT_DNT", config.respect_dnt.to_string())
+        .env(crate::config::DEFAULT_TOKEN_ENV, &config.token)
+        .env(crate::config::DEFAULT_SECRET_ENV, &config.secret)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    detach(&mut command);
+
+    command.spawn().is_ok()
+}
+
+/// Keep the uploader running when the terminal's process group is interrupted
+#[cfg(unix)]
+fn detach(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+    command.process_group(0);
+}
+
+#[cfg(windows)]
+fn detach(command: &mut Command) {
+    use std::os::windows::process::CommandExt;
+    const DETACHED_PROCESS: u32 = 0x0000_0008;
+    const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+    command.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+}
+
+#[cfg(not(any(unix, windows)))]
+fn detach(_command: &mut Command) {}
+
+/// Lock file held while an uploader runs, removed on drop
+#[derive(Debug)]
+struct UploadLock {
+    path: PathBuf,
+}
+
+impl UploadLock {
+    /// Take the lock for a database, or `None` if another uploader holds it
+    fn acquire(db_path: &Path) -> Result<Option<Self>> {
+        let path = db_path.with_extension("upload.lock");
+
+        for _ in 0..2 {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    let _ = write!(file, "{}", std::process::id());
+                    return Ok(Some(Self { path }));
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    if !is_stale(&path) {
+                        return Ok(None);
+                    }
+                    let _ = std::fs::remove_file(&path);
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl Drop for UploadLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+fn is_stale(path: &Path) -> bool {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age >= STALE_LOCK_AGE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_parse_args() {
+        let args = |args: &[&str]| parse_args(args.iter().map(OsString::from));
+
+        assert_eq!(
+            args(&["__upload", "--db", "/tmp/app.db"]),
+            Some(PathBuf::from("/tmp/app.db"))
+        );
+        assert_eq!(args(&["__upload", "/tmp/app.db"]), None);
+        assert_eq!(args(&["build", "--db", "/tmp/app.db"]), None);
+        assert_eq!(args(&[]), None);
+    }
+
+    #[test]
+    fn test_upload_lock_is_exclusive() {
+        let db_path = std::env::temp_dir().join(format!("test-upload-{}.db", Uuid::new_v4()));
+
+        let lock = UploadLock::acquire(&db_path).unwrap().unwrap();
+        assert!(UploadLock::acquire(&db_path).unwrap().is_none());
+
+        drop(lock);
+        assert!(UploadLock::acquire(&db_path).unwrap().is_some());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_shutdown_spawns_uploader() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let unique_name = format!("test-upload-{}", Uuid::new_v4());
+        let dir = std::env::temp_dir();
+        let db_path = dir.join(format!("{}.db", unique_name));
+        let marker = dir.join(format!("{}.args", unique_name));
+        let program = dir.join(format!("{}.sh", unique_name));
+        std::fs::write(
+            &program,
+            format!(
+                "#!/bin/sh\necho \"$1 $2 $3 $TK_TOKEN $TK_BATCH_SIZE $TK_MAX_RETRIES\" > {}\n",
+                marker.display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let telemetry = crate::TelemetryKit::builder()
+            .service_name(&unique_name)
+            .unwrap()
+            .db_path(&db_path)
+            .sync(
+                SyncConfig::builder()
+                    .org_id("550e8400-e29b-41d4-a716-446655440000")
+                    .unwrap()
+                    .app_id("7c9e6679-7425-40de-944b-e07fc1f90ae7")
+                    .unwrap()
+                    .token("token")
+                    .secret("secret")
+                    .batch_size(25)
+                    .max_retries(1)
+                    .build()
+                    .unwrap(),
+            )
+            .auto_sync(true)
+            .sync_interval(3600)
+            .detached_upload(&program)
+            .build()
+            .unwrap();
+        telemetry.track_command("build", |e| e).await.unwrap();
+        telemetry.shutdown().await.unwrap();
+
+        // The event is left for the uploader
+        assert_eq!(telemetry.stats().await.unwrap().unsynced_events, 1);
+
+        let mut args = String::new();
+        for _ in 0..50 {
+            args = std::fs::read_to_string(&marker).unwrap_or_default();
+            if !args.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(
+            args.trim(),
+            format!("__upload --db {} token 25 1", db_path.display())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_upload_skips_locked_database() {
+        let db_path = std::env::temp_dir().join(format!("test-upload-{}.db", Uuid::new_v4()));
+        let config = SyncConfig::builder()
+            .org_id("550e8400-e29b-41d4-a716-446655440000")
+            .unwrap()
+            .app_id("7c9e6679-7425-40de-944b-e07fc1f90ae7")
+            .unwrap()
+            .token("token")
+            .secret("secret")
+            .endpoint("http://127.0.0.1:9")
+            .build()
+            .unwrap();
+
+        let _lock = UploadLock::acquire(&db_path).unwrap().unwrap();
+        assert_eq!(upload(&db_path, config).await.unwrap(), 0);
+    }
+}
@@ -1,10 +1,14 @@
 //! Procedural macros for telemetry-kit
 //!
-//! This crate provides the `#[instrument]` macro for automatic function instrumentation.
+//! This crate provides the `#[instrument]` macro for automatic function instrumentation
+//! and `#[derive(TelemetryEvent)]` for strongly typed custom events.
 
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{parse_macro_input, ItemFn, ReturnType};
+use syn::{
+    parse_macro_input, Data, DeriveInput, Fields, FnArg, Ident, ItemFn, Lit, LitStr, Pat,
+    ReturnType,
+};
 
 /// Automatically instrument a function with telemetry tracking
 ///
@@ -23,38 +27,66 @@ use syn::{parse_macro_input, ItemFn, ReturnType};
 ///     // Function body
 /// }
 ///
-/// #[instrument]
-/// fn process_data(data: &Data) -> Result<(), Error> {
+/// #[instrument(name = "deploy", category = "usage", sample = 0.1, fields(region, dry_run), skip_errors)]
+/// fn deploy(region: &str, dry_run: bool) -> Result<(), Error> {
 ///     // Function body
 /// }
 /// ```
 ///
+/// # Arguments
+///
+/// - `name = "..."`: command name to record (default: the function name)
+/// - `category = "..."`: event category (default: `"usage"`)
+/// - `sample = 0.1`: fraction of installations whose calls are recorded, from `0` to `1`
+///   (default: `1`); the rate is recorded on kept events
+/// - `fields(a, b)`: function arguments to capture; they must implement `serde::Serialize`
+///   and are sanitized by the privacy manager before storage
+/// - `skip_errors`: do not record calls that return `Err`
+///
 /// # Generated Code
 ///
 /// The macro generates code that:
-/// 1. Records the start time
-/// 2. Executes the original function
-/// 3. Calculates duration
-/// 4. Tracks the command with telemetry-kit
+/// 1. Decides whether the call is sampled and captures the requested fields
+/// 2. Records the start time
+/// 3. Executes the original function
+/// 4. Tracks a `command_execution` event through the global instance
+///    registered with `telemetry_kit::set_global`
 /// 5. Returns the original result
 ///
-/// For async functions, it wraps the future appropriately.
+/// For async functions, it wraps the future appropriately. When no global
+/// instance is registered, nothing is recorded.
 #[proc_macro_attribute]
-pub fn instrument(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn instrument(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut args = InstrumentArgs::default();
+    let parser = syn::meta::parser(|meta| args.parse(meta));
+    parse_macro_input!(attr with parser);
+
     let input = parse_macro_input!(item as ItemFn);
 
+    if let Err(e) = args.validate(&input) {
+        return e.to_compile_error().into();
+    }
+
     let attrs = &input.attrs;
     let vis = &input.vis;
     let sig = &input.sig;
     let block = &input.block;
-    let _fn_name = &sig.ident;
-    let _fn_name_str = _fn_name.to_string();
+
+    let name = args
+        .name
+        .map(|n| n.value())
+        .unwrap_or_else(|| sig.ident.to_string());
+    let category = args
+        .category
+        .map(|c| c.value())
+        .unwrap_or_else(|| "usage".to_string());
+    let sample = args.sample.unwrap_or(1.0);
 
     // Check if function is async
     let is_async = sig.asyncness.is_some();
 
     // Check if function returns Result
-    let _returns_result = match &sig.output {
+    let returns_result = match &sig.output {
         ReturnType::Type(_, ty) => {
             if let syn::Type::Path(type_path) = &**ty {
                 type_path.path.segments.last()
@@ -67,37 +99,56 @@ pub fn instrument(_attr: TokenStream, item: TokenStream) -> TokenStream {
         _ => false,
     };
 
+    let success = if returns_result {
+        quote! { ::std::option::Option::Some(__result.is_ok()) }
+    } else {
+        quote! { ::std::option::Option::None }
+    };
+
+    // Fields are captured before the body runs, since the body may consume them
+    let fields = args.fields.iter().map(|field| {
+        let key = field.to_string();
+        quote! { .field(#key, &#field) }
+    });
+    let skip_errors = args.skip_errors.then(|| quote! { .skip_errors() });
+
+    let call = quote! {
+        let __call = if ::telemetry_kit::__private::should_record(#sample) {
+            ::std::option::Option::Some(
+                ::telemetry_kit::__private::InstrumentedCall::new(#name, #category)
+                    .sample(#sample)
+                    #(#fields)*
+                    #skip_errors
+            )
+        } else {
+            ::std::option::Option::None
+        };
+    };
+
     // Generate the instrumented function
-    // Note: Currently this macro only wraps the function and measures timing
-    // without sending telemetry. Full integration will be added in a future version
-    // when we have a proper global telemetry instance pattern.
     let instrumented = if is_async {
-        // Async function - just wrap and measure
         quote! {
             #(#attrs)*
             #vis #sig {
+                #call
                 let __start = ::std::time::Instant::now();
                 let __result = async move #block.await;
-                let _duration = __start.elapsed();
-
-                // TODO: Send telemetry when global instance is available
-                // For now, timing is measured but not recorded
-
+                if let ::std::option::Option::Some(__call) = __call {
+                    __call.finish_async(__start.elapsed(), #success).await;
+                }
                 __result
             }
         }
     } else {
-        // Sync function - just wrap and measure
         quote! {
             #(#attrs)*
             #vis #sig {
+                #call
                 let __start = ::std::time::Instant::now();
                 let __result = (|| #block)();
-                let _duration = __start.elapsed();
-
-                // TODO: Send telemetry when global instance is available
-                // For now, timing is measured but not recorded
-
+                if let ::std::option::Option::Some(__call) = __call {
+                    __call.finish(__start.elapsed(), #success);
+                }
                 __result
             }
         }
@@ -105,3 +156,287 @@ pub fn instrument(_attr: TokenStream, item: TokenStream) -> TokenStream {
 
     TokenStream::from(instrumented)
 }
+
+/// Arguments accepted by `#[instrument(...)]`
+#[derive(Default)]
+struct InstrumentArgs {
+    name: Option<LitStr>,
+    category: Option<LitStr>,
+    sample: Option<f64>,
+    fields: Vec<Ident>,
+    skip_errors: bool,
+}
+
+impl InstrumentArgs {
+    fn parse(&mut self, meta: syn::meta::ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("name") {
+            self.name = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("category") {
+            self.category = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("sample") {
+            let lit: Lit = meta.value()?.parse()?;
+            let sample: f64 = match &lit {
+                Lit::Float(lit) => lit.base10_parse()?,
+                Lit::Int(lit) => lit.base10_parse()?,
+                _ => {
+                    return Err(syn::Error::new(
+                        lit.span(),
+                        "sample must be a number between 0.0 and 1.0",
+                    ))
+                }
+            };
+            if !(0.0..=1.0).contains(&sample) {
+                return Err(syn::Error::new(
+                    lit.span(),
+                    "sample must be between 0.0 and 1.0",
+                ));
+            }
+            self.sample = Some(sample);
+        } else if meta.path.is_ident("fields") {
+            meta.parse_nested_meta(|field| match field.path.get_ident() {
+                Some(ident) => {
+                    self.fields.push(ident.clone());
+                    Ok(())
+                }
+                None => Err(field.error("expected an argument name")),
+            })?;
+        } else if meta.path.is_ident("skip_errors") {
+            self.skip_errors = true;
+        } else {
+            return Err(meta.error(
+                "unsupported instrument argument, expected one of: name, category, sample, fields, skip_errors",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check that every captured field names an argument of the function
+    fn validate(&self, input: &ItemFn) -> syn::Result<()> {
+        let arg_names: Vec<&Ident> = input
+            .sig
+            .inputs
+            .iter()
+            .filter_map(|arg| match arg {
+                FnArg::Typed(pat_type) => match &*pat_type.pat {
+                    Pat::Ident(pat_ident) => Some(&pat_ident.ident),
+                    _ => None,
+                },
+                FnArg::Receiver(_) => None,
+            })
+            .collect();
+
+        for field in &self.fields {
+            if !arg_names.contains(&field) {
+                return Err(syn::Error::new(
+                    field.span(),
+                    format!("`{}` is not an argument of this function", field),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Derive `telemetry_kit::event::TelemetryEvent` for a struct with named fields
+///
+/// Each field becomes a key in the event data and must implement `serde::Serialize`.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use telemetry_kit::prelude::*;
+///
+/// #[derive(TelemetryEvent)]
+/// #[telemetry(event_type = "deploy_started", category = "usage")]
+/// struct DeployStarted {
+///     region: String,
+///     #[telemetry(hash)]
+///     email: String,
+///     #[telemetry(redact)]
+///     token: String,
+///     #[telemetry(skip)]
+///     retries: u32,
+/// }
+///
+/// telemetry.track(DeployStarted { .. }).await?;
+/// ```
+///
+/// # Arguments
+///
+/// On the struct:
+/// - `event_type = "..."`: event type (default: the struct name in snake_case)
+/// - `category = "..."`: event category (default: none)
+///
+/// On fields:
+/// - `redact`: keep the key but replace the value with `"[REDACTED]"`
+/// - `hash`: replace the value with a stable SHA-256 hash
+/// - `skip`: leave the field out of the event
+#[proc_macro_derive(TelemetryEvent, attributes(telemetry))]
+pub fn derive_telemetry_event(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
+
+    match expand_telemetry_event(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand_telemetry_event(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let mut event_type: Option<LitStr> = None;
+    let mut category: Option<LitStr> = None;
+
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|a| a.path().is_ident("telemetry"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("event_type") {
+                event_type = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("category") {
+                category = Some(meta.value()?.parse()?);
+            } else {
+                return Err(meta.error(
+                    "unsupported telemetry argument, expected one of: event_type, category",
+                ));
+            }
+            Ok(())
+        })?;
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    input,
+                    "TelemetryEvent can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "TelemetryEvent can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut inserts = Vec::new();
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field");
+        let mut treatment = FieldTreatment::Keep;
+
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|a| a.path().is_ident("telemetry"))
+        {
+            attr.parse_nested_meta(|meta| {
+                let next = if meta.path.is_ident("redact") {
+                    FieldTreatment::Redact
+                } else if meta.path.is_ident("hash") {
+                    FieldTreatment::Hash
+                } else if meta.path.is_ident("skip") {
+                    FieldTreatment::Skip
+                } else {
+                    return Err(meta.error(
+                        "unsupported telemetry field argument, expected one of: redact, hash, skip",
+                    ));
+                };
+                if treatment != FieldTreatment::Keep {
+                    return Err(meta.error("only one of redact, hash or skip may be used"));
+                }
+                treatment = next;
+                Ok(())
+            })?;
+        }
+
+        let key = ident.to_string();
+        let value = match treatment {
+            FieldTreatment::Keep => {
+                quote! { ::telemetry_kit::__private::field_value(&self.#ident) }
+            }
+            FieldTreatment::Hash => {
+                quote! { ::telemetry_kit::__private::hashed_field_value(&self.#ident) }
+            }
+            FieldTreatment::Redact => quote! {
+                {
+                    let _ = &self.#ident;
+                    ::telemetry_kit::__private::serde_json::Value::String(
+                        ::telemetry_kit::__private::REDACTED.to_string()
+                    )
+                }
+            },
+            FieldTreatment::Skip => {
+                // Keep the field "read" so it does not trigger dead code warnings
+                inserts.push(quote! { let _ = &self.#ident; });
+                continue;
+            }
+        };
+
+        inserts.push(quote! {
+            __data.insert(#key.to_string(), #value);
+        });
+    }
+
+    let name = &input.ident;
+    let event_type = event_type
+        .map(|e| e.value())
+        .unwrap_or_else(|| to_snake_case(&name.to_string()));
+    let category = match category {
+        Some(category) => quote! { ::std::option::Option::Some(#category) },
+        None => quote! { ::std::option::Option::None },
+    };
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::telemetry_kit::event::TelemetryEvent for #name #ty_generics #where_clause {
+            fn event_type(&self) -> &'static str {
+                #event_type
+            }
+
+            fn category(&self) -> ::std::option::Option<&'static str> {
+                #category
+            }
+
+            fn to_data(&self) -> ::telemetry_kit::__private::serde_json::Value {
+                let mut __data = ::telemetry_kit::__private::serde_json::Map::new();
+                #(#inserts)*
+                ::telemetry_kit::__private::serde_json::Value::Object(__data)
+            }
+        }
+    })
+}
+
+/// How a field is recorded by `#[derive(TelemetryEvent)]`
+#[derive(PartialEq)]
+enum FieldTreatment {
+    Keep,
+    Redact,
+    Hash,
+    Skip,
+}
+
+/// Convert a `CamelCase` type name to `snake_case`
+fn to_snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() {
+            // A capital starts a word after a lowercase letter or digit, or when it
+            // ends a run of capitals (the `R` in `HTTPRequest`)
+            let prev = i.checked_sub(1).map(|p| chars[p]);
+            let next = chars.get(i + 1);
+            let starts_word = prev.is_some_and(|p| p.is_lowercase() || p.is_ascii_digit())
+                || (prev.is_some_and(char::is_uppercase) && next.is_some_and(|n| n.is_lowercase()));
+            if starts_word {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
@@ -0,0 +1,34 @@
+//! Test: TelemetryEvent derive compiles
+
+use telemetry_kit::event::TelemetryEvent;
+use telemetry_kit_macros::TelemetryEvent;
+
+#[derive(TelemetryEvent)]
+#[telemetry(event_type = "deploy_started", category = "usage")]
+struct DeployStarted {
+    region: String,
+    #[telemetry(hash)]
+    email: String,
+    #[telemetry(redact)]
+    token: String,
+    #[telemetry(skip)]
+    retries: u32,
+}
+
+#[derive(TelemetryEvent)]
+struct ProjectCreated<T: serde::Serialize> {
+    template: T,
+}
+
+fn main() {
+    let event = DeployStarted {
+        region: "eu-west-1".to_string(),
+        email: "user@example.com".to_string(),
+        token: "secret".to_string(),
+        retries: 0,
+    };
+    assert_eq!(event.event_type(), "deploy_started");
+
+    let event = ProjectCreated { template: "web" };
+    assert_eq!(event.event_type(), "project_created");
+}
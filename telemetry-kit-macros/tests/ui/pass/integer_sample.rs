@@ -0,0 +1,11 @@
+//! Test: Integer sample rates compile
+
+use telemetry_kit_macros::instrument;
+
+#[instrument(sample = 1)]
+fn always() {}
+
+#[instrument(sample = 0)]
+fn never() {}
+
+fn main() {}
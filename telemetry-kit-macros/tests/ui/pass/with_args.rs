@@ -0,0 +1,25 @@
+//! Test: Attribute arguments compile
+
+use telemetry_kit_macros::instrument;
+
+#[instrument(
+    name = "deploy",
+    category = "usage",
+    sample = 0.1,
+    fields(region, dry_run),
+    skip_errors
+)]
+async fn deploy(region: &str, dry_run: bool) -> Result<(), String> {
+    if dry_run {
+        Ok(())
+    } else {
+        Err(format!("cannot deploy to {}", region))
+    }
+}
+
+#[instrument(fields(items))]
+fn total(items: Vec<u32>) -> u32 {
+    items.into_iter().sum()
+}
+
+fn main() {}
@@ -0,0 +1,83 @@
+//! Integration tests for CI detection and policies
+//!
+//! Kept in their own test binary because they set CI environment variables.
+
+use telemetry_kit::environment::CiPolicy;
+use telemetry_kit::prelude::*;
+use telemetry_kit::storage::EventStorage;
+use uuid::Uuid;
+
+fn build(policy: CiPolicy) -> (TelemetryKit, std::path::PathBuf) {
+    std::env::set_var("GITHUB_ACTIONS", "true");
+    std::env::set_var("ImageOS", "ubuntu22");
+
+    let unique_name = format!("test-ci-{}", Uuid::new_v4());
+    let db_path = std::env::temp_dir().join(format!("{}.db", unique_name));
+    let telemetry = TelemetryKit::builder()
+        .service_name(&unique_name)
+        .unwrap()
+        .db_path(&db_path)
+        .ci_policy(policy)
+        .build()
+        .unwrap();
+
+    (telemetry, db_path)
+}
+
+#[tokio::test]
+async fn test_ci_provider_recorded() {
+    let (telemetry, db_path) = build(CiPolicy::Track);
+    telemetry
+        .track_command("build", |event| event.success(true))
+        .await
+        .unwrap();
+
+    let events = EventStorage::new(&db_path)
+        .unwrap()
+        .get_unsynced(10)
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    let environment = &events[0].environment;
+    assert_eq!(environment.ci, Some(true));
+    assert_eq!(environment.ci_provider.as_deref(), Some("github_actions"));
+    assert_eq!(environment.ci_runner_image.as_deref(), Some("ubuntu22"));
+    assert!(events[0].event.context.is_none());
+}
+
+#[tokio::test]
+async fn test_ci_policy_tag() {
+    let (telemetry, db_path) = build(CiPolicy::Tag);
+    telemetry
+        .track_command("build", |event| event.success(true))
+        .await
+        .unwrap();
+
+    let events = EventStorage::new(&db_path)
+        .unwrap()
+        .get_unsynced(10)
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        events[0].event.context.as_ref().unwrap()["ci_provider"],
+        "github_actions"
+    );
+}
+
+#[tokio::test]
+async fn test_ci_policy_suppress() {
+    let (telemetry, db_path) = build(CiPolicy::Suppress);
+    telemetry
+        .track_command("build", |event| event.success(true))
+        .await
+        .unwrap();
+    telemetry
+        .track_feature("cache", |event| event.success(true))
+        .await
+        .unwrap();
+
+    let events = EventStorage::new(&db_path)
+        .unwrap()
+        .get_unsynced(10)
+        .unwrap();
+    assert!(events.is_empty());
+}
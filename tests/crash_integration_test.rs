@@ -0,0 +1,62 @@
+//! Integration tests for panic capture
+
+#![cfg(feature = "sync")]
+
+use telemetry_kit::prelude::*;
+use telemetry_kit::storage::EventStorage;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_panic_records_crash_event() {
+    let unique_name = format!("test-crash-{}", Uuid::new_v4());
+    let db_path = std::env::temp_dir().join(format!("{}.db", unique_name));
+
+    let telemetry = TelemetryKit::builder()
+        .service_name(&unique_name)
+        .unwrap()
+        .service_version("2.1.0")
+        .db_path(&db_path)
+        .auto_sync(false)
+        .capture_panics()
+        .include_panic_messages(true)
+        .build()
+        .unwrap();
+
+    let result = std::thread::Builder::new()
+        .name("worker".to_string())
+        .spawn(|| panic!("something went wrong"))
+        .unwrap()
+        .join();
+    assert!(result.is_err());
+
+    // Panic inside an async task while the runtime is busy
+    let task = tokio::spawn(async { panic!("async failure") });
+    assert!(task.await.is_err());
+
+    let events = EventStorage::new(&db_path)
+        .unwrap()
+        .get_unsynced(10)
+        .unwrap();
+    assert_eq!(events.len(), 2);
+
+    let crash = &events[0];
+    assert_eq!(crash.event.event_type, "crash");
+    assert_eq!(crash.event.category.as_deref(), Some("error"));
+    assert_eq!(crash.service.version, "2.1.0");
+    assert_eq!(crash.event.data["message"], "something went wrong");
+    assert_eq!(crash.event.data["thread"], "worker");
+    assert_eq!(
+        crash.event.data["location"]["file"],
+        "tests/crash_integration_test.rs"
+    );
+    let frames = crash.event.data["backtrace"].as_array().unwrap();
+    assert!(frames[0]["function"]
+        .as_str()
+        .unwrap()
+        .starts_with("crash_integration_test::"));
+
+    assert_eq!(events[1].event.data["message"], "async failure");
+
+    drop(telemetry);
+    let _ = std::fs::remove_file(db_path);
+}
@@ -0,0 +1,101 @@
+//! Integration tests for `#[instrument]` recording through the global instance
+
+#![cfg(feature = "macros")]
+
+use telemetry_kit::prelude::*;
+use telemetry_kit::storage::EventStorage;
+use uuid::Uuid;
+
+#[instrument]
+async fn fetch(ok: bool) -> std::result::Result<u32, String> {
+    if ok {
+        Ok(1)
+    } else {
+        Err("failed".to_string())
+    }
+}
+
+#[instrument]
+fn compute(items: &[u32]) -> u32 {
+    items.iter().sum()
+}
+
+#[instrument(
+    name = "deploy",
+    category = "ops",
+    fields(region, dry_run),
+    skip_errors
+)]
+fn deploy(region: &str, dry_run: bool) -> std::result::Result<(), String> {
+    if dry_run {
+        Ok(())
+    } else {
+        Err("refused".to_string())
+    }
+}
+
+#[instrument(fields(email))]
+fn invite(email: &str) -> bool {
+    !email.is_empty()
+}
+
+#[instrument(sample = 0.0)]
+fn never_sampled() {}
+
+#[tokio::test]
+async fn test_instrumented_functions_record_events() {
+    let unique_name = format!("test-instrument-{}", Uuid::new_v4());
+    let db_path = std::env::temp_dir().join(format!("{}.db", unique_name));
+
+    let telemetry = TelemetryKit::builder()
+        .service_name(&unique_name)
+        .unwrap()
+        .db_path(&db_path)
+        .sanitize_emails(true)
+        .auto_sync(false)
+        .build()
+        .unwrap();
+
+    // Nothing registered yet: instrumented functions still run
+    assert_eq!(compute(&[1, 2]), 3);
+
+    set_global(telemetry.clone()).unwrap();
+    assert!(set_global(telemetry.clone()).is_err());
+
+    assert!(fetch(true).await.is_ok());
+    assert!(fetch(false).await.is_err());
+    assert_eq!(compute(&[1, 2, 3]), 6);
+
+    // Skipped: sampled out, and an error with skip_errors
+    never_sampled();
+    assert!(deploy("eu-west-1", false).is_err());
+
+    assert!(deploy("eu-west-1", true).is_ok());
+    assert!(invite("user@example.com"));
+
+    let stats = telemetry.stats().await.unwrap();
+    assert_eq!(stats.total_events, 5);
+
+    let events = EventStorage::new(&db_path)
+        .unwrap()
+        .get_unsynced(10)
+        .unwrap();
+    let deploy_event = events
+        .iter()
+        .find(|e| e.event.data["command"] == "deploy")
+        .unwrap();
+    assert_eq!(deploy_event.event.category.as_deref(), Some("ops"));
+    assert_eq!(deploy_event.event.data["success"], true);
+    assert_eq!(deploy_event.event.data["fields"]["region"], "eu-west-1");
+    assert_eq!(deploy_event.event.data["fields"]["dry_run"], true);
+
+    // Captured arguments are sanitized like any other event data
+    let invite_event = events
+        .iter()
+        .find(|e| e.event.data["command"] == "invite")
+        .unwrap();
+    let email = invite_event.event.data["fields"]["email"].as_str().unwrap();
+    assert!(email.starts_with("email_"));
+
+    let _ = std::fs::remove_file(db_path);
+}
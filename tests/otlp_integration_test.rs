@@ -0,0 +1,128 @@
+//! Integration tests for the OTLP exporter
+
+#![cfg(feature = "otlp")]
+
+use telemetry_kit::otlp::{OtlpConfig, OtlpProtocol};
+use telemetry_kit::prelude::*;
+use uuid::Uuid;
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn build_telemetry(server: &MockServer, protocol: OtlpProtocol) -> TelemetryKit {
+    let unique_name = format!("test-otlp-{}", Uuid::new_v4());
+    let db_path = std::env::temp_dir().join(format!("{}.db", unique_name));
+
+    TelemetryKit::builder()
+        .service_name(&unique_name)
+        .unwrap()
+        .service_version("1.0.0")
+        .db_path(db_path)
+        .otlp(
+            OtlpConfig::builder()
+                .endpoint(server.uri())
+                .protocol(protocol)
+                .header("x-api-key", "secret")
+                .max_retries(0)
+                .build()
+                .unwrap(),
+        )
+        .auto_sync(false)
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_otlp_protobuf_export() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/logs"))
+        .and(header("content-type", "application/x-protobuf"))
+        .and(header("x-api-key", "secret"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let telemetry = build_telemetry(&server, OtlpProtocol::HttpProtobuf);
+    telemetry
+        .track_command("build", |event| event.success(true).duration_ms(10))
+        .await
+        .unwrap();
+
+    telemetry.sync().await.unwrap();
+
+    let stats = telemetry.stats().await.unwrap();
+    assert_eq!(stats.unsynced_events, 0);
+    assert_eq!(stats.synced_events, 1);
+}
+
+#[tokio::test]
+async fn test_otlp_json_export() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/logs"))
+        .and(header("content-type", "application/json"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("{}"))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let telemetry = build_telemetry(&server, OtlpProtocol::HttpJson);
+    telemetry
+        .track_feature("export", |event| event.method("otlp"))
+        .await
+        .unwrap();
+
+    telemetry.sync().await.unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    let record = &body["resourceLogs"][0]["scopeLogs"][0]["logRecords"][0];
+    assert_eq!(record["eventName"], "feature_used");
+}
+
+#[tokio::test]
+async fn test_otlp_failure_keeps_events_buffered() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(400))
+        .mount(&server)
+        .await;
+
+    let telemetry = build_telemetry(&server, OtlpProtocol::HttpProtobuf);
+    telemetry
+        .track_command("build", |event| event.success(false))
+        .await
+        .unwrap();
+
+    assert!(telemetry.sync().await.is_err());
+
+    let stats = telemetry.stats().await.unwrap();
+    assert_eq!(stats.unsynced_events, 1);
+}
+
+#[tokio::test]
+async fn test_otlp_partial_success_is_not_retried() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(
+            r#"{"partialSuccess":{"rejectedLogRecords":1,"errorMessage":"invalid body"}}"#,
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let telemetry = build_telemetry(&server, OtlpProtocol::HttpJson);
+    telemetry
+        .track_command("build", |event| event.success(true))
+        .await
+        .unwrap();
+
+    telemetry.sync().await.unwrap();
+    // Nothing left to re-send
+    telemetry.sync().await.unwrap();
+
+    let stats = telemetry.stats().await.unwrap();
+    assert_eq!(stats.unsynced_events, 0);
+    assert_eq!(stats.synced_events, 1);
+}
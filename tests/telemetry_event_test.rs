@@ -0,0 +1,114 @@
+//! Integration tests for strongly typed events via `#[derive(TelemetryEvent)]`
+
+#![cfg(all(feature = "macros", feature = "privacy"))]
+
+use telemetry_kit::event::REDACTED;
+use telemetry_kit::prelude::*;
+use telemetry_kit::storage::EventStorage;
+use uuid::Uuid;
+
+#[derive(TelemetryEvent)]
+#[telemetry(event_type = "deploy_started", category = "usage")]
+struct DeployStarted {
+    region: String,
+    #[telemetry(hash)]
+    email: String,
+    #[telemetry(redact)]
+    token: String,
+    #[telemetry(skip)]
+    retries: u32,
+}
+
+#[derive(TelemetryEvent)]
+struct ProjectCreated {
+    template: &'static str,
+    path: String,
+}
+
+#[derive(TelemetryEvent)]
+struct HTTPRequestSent {
+    status: u16,
+}
+
+#[test]
+fn test_default_event_type_keeps_acronyms_together() {
+    assert_eq!(
+        ProjectCreated {
+            template: "web",
+            path: String::new(),
+        }
+        .event_type(),
+        "project_created"
+    );
+    assert_eq!(
+        HTTPRequestSent { status: 200 }.event_type(),
+        "http_request_sent"
+    );
+}
+
+#[tokio::test]
+async fn test_track_typed_events() {
+    let unique_name = format!("test-typed-{}", Uuid::new_v4());
+    let db_path = std::env::temp_dir().join(format!("{}.db", unique_name));
+
+    let telemetry = TelemetryKit::builder()
+        .service_name(&unique_name)
+        .unwrap()
+        .db_path(&db_path)
+        .sanitize_paths(true)
+        .auto_sync(false)
+        .build()
+        .unwrap();
+
+    telemetry
+        .track(DeployStarted {
+            region: "eu-west-1".to_string(),
+            email: "user@example.com".to_string(),
+            token: "secret".to_string(),
+            retries: 3,
+        })
+        .await
+        .unwrap();
+
+    let home = dirs::home_dir().unwrap();
+    telemetry
+        .track(ProjectCreated {
+            template: "web",
+            path: home.join("project").to_string_lossy().to_string(),
+        })
+        .await
+        .unwrap();
+
+    let events = EventStorage::new(&db_path)
+        .unwrap()
+        .get_unsynced(10)
+        .unwrap();
+    assert_eq!(events.len(), 2);
+
+    let deploy = events
+        .iter()
+        .find(|e| e.event.event_type == "deploy_started")
+        .unwrap();
+    assert_eq!(deploy.event.category.as_deref(), Some("usage"));
+    assert_eq!(deploy.event.data["region"], "eu-west-1");
+    assert_eq!(deploy.event.data["token"], REDACTED);
+    assert!(deploy.event.data["email"]
+        .as_str()
+        .unwrap()
+        .starts_with("hash_"));
+    assert!(deploy.event.data.get("retries").is_none());
+
+    // Typed events still go through privacy sanitization
+    let created = events
+        .iter()
+        .find(|e| e.event.event_type == "project_created")
+        .unwrap();
+    assert_eq!(created.event.category, None);
+    assert_eq!(created.event.data["template"], "web");
+    assert!(created.event.data["path"]
+        .as_str()
+        .unwrap()
+        .starts_with('~'));
+
+    let _ = std::fs::remove_file(db_path);
+}